@@ -1,7 +1,10 @@
 use cosmwasm_std::{to_binary, Addr, CosmosMsg, Decimal, QuerierWrapper, StdResult, WasmMsg};
 
 use crate::{
-    msg::{ExecuteMsg, ExecuteSettings, GrantQueryResponse, QueryMsg, SimulateExecuteResponse},
+    msg::{
+        ActiveGrantsByDelegatorResponse, ExecuteMsg, ExecuteSettings, QueryMsg, RewardThresholds,
+        RewardTotals, SimulateExecuteResponse,
+    },
     ContractError,
 };
 
@@ -11,6 +14,12 @@ pub struct WithdrawRewardsTaxClient<'a> {
 
     /// Address of the delegator/granter
     delegator_addr: &'a Addr,
+
+    /// Address of the grantee whose grant should be used, if the delegator has more than one
+    grantee_addr: Option<&'a Addr>,
+
+    /// Minimum rewards for the execution on top of the grant's own
+    reward_thresholds: Option<RewardThresholds>,
 }
 
 impl<'a> WithdrawRewardsTaxClient<'a> {
@@ -19,9 +28,23 @@ impl<'a> WithdrawRewardsTaxClient<'a> {
         Self {
             authzpp_contract_addr,
             delegator_addr,
+            grantee_addr: None,
+            reward_thresholds: None,
         }
     }
 
+    /// Uses the delegator's grant to the given grantee rather than the grant to the sender
+    pub fn with_grantee(mut self, grantee_addr: &'a Addr) -> Self {
+        self.grantee_addr = Some(grantee_addr);
+        self
+    }
+
+    /// Only claims rewards that reach the given thresholds, on top of the grant's own thresholds
+    pub fn with_reward_thresholds(mut self, reward_thresholds: RewardThresholds) -> Self {
+        self.reward_thresholds = Some(reward_thresholds);
+        self
+    }
+
     /// Queries the contract for a simulation of the grant execution for the given delegator.
     /// Returns both the amount expected to go to the delegator and the taxation address
     pub fn simulate(
@@ -34,6 +57,8 @@ impl<'a> WithdrawRewardsTaxClient<'a> {
             &QueryMsg::SimulateExecute(ExecuteSettings {
                 delegator: self.delegator_addr.to_string(),
                 percentage,
+                grantee: self.grantee_addr.map(Addr::to_string),
+                reward_thresholds: self.reward_thresholds.clone(),
             }),
         );
         simulation
@@ -46,6 +71,8 @@ impl<'a> WithdrawRewardsTaxClient<'a> {
             msg: to_binary(&ExecuteMsg::Execute(ExecuteSettings {
                 delegator: self.delegator_addr.to_string(),
                 percentage,
+                grantee: self.grantee_addr.map(Addr::to_string),
+                reward_thresholds: self.reward_thresholds.clone(),
             }))?,
             funds: vec![],
         }))
@@ -63,11 +90,22 @@ impl<'a> WithdrawRewardsTaxClient<'a> {
         ))
     }
 
-    /// Queries the contract for the active grant for the delegator, if any
-    pub fn query_grant(&self, querier: QuerierWrapper) -> StdResult<GrantQueryResponse> {
+    /// Queries the contract for the active grants for the delegator
+    pub fn query_grants(
+        &self,
+        querier: QuerierWrapper,
+    ) -> StdResult<ActiveGrantsByDelegatorResponse> {
         querier.query_wasm_smart(
             self.authzpp_contract_addr.to_string(),
             &QueryMsg::ActiveGrantsByDelegator(self.delegator_addr.to_string()),
         )
     }
+
+    /// Queries the contract for the running totals of the rewards withdrawn for the delegator
+    pub fn query_reward_totals(&self, querier: QuerierWrapper) -> StdResult<RewardTotals> {
+        querier.query_wasm_smart(
+            self.authzpp_contract_addr.to_string(),
+            &QueryMsg::RewardTotalsByDelegator(self.delegator_addr.to_string()),
+        )
+    }
 }
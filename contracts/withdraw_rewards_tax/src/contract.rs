@@ -1,25 +1,46 @@
 use crate::error::ContractError;
 use crate::execute::{generate_reward_withdrawl_msgs, RewardExecutionMsgs};
-use crate::helpers::{split_rewards, validate_grantee_address, validate_granter_address};
+use crate::helpers::{
+    add_to_reward_totals, apply_reward_thresholds, combine_reward_thresholds, filter_empty_coins,
+    split_rewards, validate_compound_policy, validate_grantee_address, validate_granter_address,
+    validate_taxation_address, validate_taxation_recipients, RewardSplit,
+};
 use crate::msg::{
-    AllowedWithdrawlSettings, ExecuteMsg, ExecuteSettings, GrantSpecData, InstantiateMsg, QueryMsg,
+    AllowedWithdrawlSettings, BatchSimulation, CompoundValidatorPolicy, ExecuteMsg,
+    ExecuteSettings, GrantSpecData, InstantiateMsg, MigrateMsg, QueryMsg, RecipientRewards,
+    RewardTotals, SimulateExecuteResponse,
+};
+use crate::queries::{
+    self, query_active_grants_by_delegator, resolve_active_grant, AllPendingRewards,
+};
+use crate::queries::{
+    query_active_grants_by_grantee, query_delegator_withdraw_address, query_pending_rewards,
+};
+use crate::state::{
+    ExecutionRecord, DELEGATOR_TOTALS, GRANTEE_TOTALS, GRANTS, LAST_EXECUTIONS, LEGACY_GRANTS,
+    PENDING_EXECUTIONS, TAXATION_ADDRESS_TOTALS,
 };
-use crate::queries::{self, query_active_grants_by_delegator};
-use crate::queries::{query_active_grants_by_grantee, query_pending_rewards};
-use crate::state::GRANTS;
 use authzpp_utils::helpers::Expirable;
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
 use cosmwasm_std::{
-    to_binary, Binary, Deps, DepsMut, Env, MessageInfo, Order, Response, StdResult, Timestamp,
+    to_binary, Addr, Binary, Coin, CosmosMsg, Deps, DepsMut, Env, MessageInfo, Order, Reply,
+    Response, StdError, StdResult, Storage, SubMsg, SubMsgResult, Timestamp, WasmMsg,
 };
+use cw_grant_spec::describe::describe_grant_reqs;
 use cw_grant_spec::grantable_trait::{GrantStructure, Grantable};
-use cw_grant_spec::grants::{AuthorizationType, GrantRequirement, RevokeRequirement};
+use cw_grant_spec::grants::{
+    AuthorizationType, GrantExpiration, GrantRequirement, RevokeRequirement,
+    StakeAuthorizationPolicy, StakeAuthorizationType, StakeAuthorizationValidators,
+};
 
 // version info for migration info
 const CONTRACT_NAME: &str = "crates.io:authzpp-withdraw-rewards-tax-grant";
 const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 
+// the most legacy grants that are moved over in one migration unless a limit is given
+const DEFAULT_MIGRATION_LIMIT: u32 = 100;
+
 #[cfg_attr(not(feature = "library"), entry_point)]
 #[cfg_attr(feature = "interface", cw_orch::interface_entry_point)]
 pub fn instantiate(
@@ -35,8 +56,57 @@ pub fn instantiate(
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 #[cfg_attr(feature = "interface", cw_orch::interface_entry_point)]
-pub fn migrate(_deps: DepsMut, _env: Env, _msg: InstantiateMsg) -> Result<Response, ContractError> {
-    Ok(Response::default())
+pub fn migrate(deps: DepsMut, _env: Env, msg: MigrateMsg) -> Result<Response, ContractError> {
+    // only this contract can be migrated and only to a newer version
+    let stored = cw2::get_contract_version(deps.storage)?;
+    if stored.contract.ne(CONTRACT_NAME)
+        || semver::Version::parse(&stored.version)? >= semver::Version::parse(CONTRACT_VERSION)?
+    {
+        return Err(ContractError::InvalidMigration {
+            from: format!("{}@{}", stored.contract, stored.version),
+            to: format!("{CONTRACT_NAME}@{CONTRACT_VERSION}"),
+        });
+    }
+    cw2::set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    migrate_legacy_grants(deps, msg.limit, "migrate")
+}
+
+/// moves the grants that were only keyed by the delegator over to being keyed by delegator and grantee,
+/// their single taxation address becomes the only taxation recipient. the rest are left for `MigrateLegacyGrants`
+fn migrate_legacy_grants(
+    deps: DepsMut,
+    limit: Option<u32>,
+    action: &str,
+) -> Result<Response, ContractError> {
+    let legacy_grants = LEGACY_GRANTS
+        .range(deps.storage, None, None, Order::Ascending)
+        .take(limit.unwrap_or(DEFAULT_MIGRATION_LIMIT) as usize)
+        .collect::<StdResult<Vec<_>>>()?;
+
+    for (delegator_addr, grant_settings) in legacy_grants.iter() {
+        let grantee_addr = validate_grantee_address(deps.api, &grant_settings.grantee)?;
+
+        GRANTS.save(
+            deps.storage,
+            (delegator_addr, &grantee_addr),
+            &grant_settings.clone().into(),
+        )?;
+        LEGACY_GRANTS.remove(deps.storage, delegator_addr);
+    }
+
+    let has_remaining_legacy_grants = LEGACY_GRANTS
+        .keys(deps.storage, None, None, Order::Ascending)
+        .next()
+        .is_some();
+
+    Ok(Response::default()
+        .add_attribute("action", action)
+        .add_attribute("num_migrated_grants", legacy_grants.len().to_string())
+        .add_attribute(
+            "has_remaining_legacy_grants",
+            has_remaining_legacy_grants.to_string(),
+        ))
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
@@ -51,86 +121,142 @@ pub fn execute(
         ExecuteMsg::Grant(grant_spec) => {
             let grantee_addr = validate_grantee_address(deps.api, &grant_spec.grantee)?;
 
-            // validate that the withdraw share address is valid
-            deps.api
-                .addr_validate(&grant_spec.taxation_address)
-                .map_err(|_| {
-                    ContractError::InvalidWithdrawShareAddress(
-                        grant_spec.taxation_address.to_string(),
-                    )
-                })?;
+            // validate that the withdraw share addresses are valid and don't take more than all of the rewards
+            validate_taxation_recipients(deps.api, &grant_spec.taxation_recipients)?;
+
+            // validate that the validators to restake to exist
+            if let Some(policy) = &grant_spec.auto_compound {
+                validate_compound_policy(&deps.querier, policy)?;
+            }
 
-            GRANTS.save(deps.storage, &info.sender, &grant_spec)?;
+            GRANTS.save(deps.storage, (&info.sender, &grantee_addr), &grant_spec)?;
 
             Ok(Response::default()
                 .add_attribute("action", "grant")
                 .add_attribute("granter", info.sender)
                 .add_attribute("grantee", grantee_addr.to_string()))
         }
-        ExecuteMsg::Revoke() => {
-            // remove the grant from state
-            GRANTS.remove(deps.storage, &info.sender);
+        ExecuteMsg::Revoke(Some(grantee)) => {
+            let grantee_addr = validate_grantee_address(deps.api, &grantee)?;
+
+            // remove the grant to the given grantee from state
+            GRANTS.remove(deps.storage, (&info.sender, &grantee_addr));
+            LAST_EXECUTIONS.remove(deps.storage, (&info.sender, &grantee_addr));
 
             Ok(Response::default()
                 .add_attribute("action", "revoke")
-                .add_attribute("granter", info.sender))
+                .add_attribute("granter", info.sender)
+                .add_attribute("grantee", grantee_addr))
         }
-        ExecuteMsg::Execute(ExecuteSettings {
-            delegator,
-            percentage,
-        }) => {
-            let delegator_addr = validate_granter_address(deps.api, &delegator)?;
+        ExecuteMsg::Revoke(None) => {
+            let grantees = GRANTS
+                .prefix(&info.sender)
+                .keys(deps.storage, None, None, Order::Ascending)
+                .collect::<StdResult<Vec<_>>>()?;
+
+            // remove all of the sender's grants from state
+            for grantee_addr in grantees.iter() {
+                GRANTS.remove(deps.storage, (&info.sender, grantee_addr));
+                LAST_EXECUTIONS.remove(deps.storage, (&info.sender, grantee_addr));
+            }
 
-            // query the grant settings, this will error if there is no active/unexpired grant
-            let grant_settings =
-                match query_active_grants_by_delegator(deps.storage, &env.block, &delegator_addr) {
-                    Ok(Some(grant)) => grant,
-                    _ => {
-                        return Err(ContractError::NoActiveGrant(delegator_addr.to_string()));
+            Ok(Response::default()
+                .add_attribute("action", "revoke")
+                .add_attribute("granter", info.sender)
+                .add_attribute("num_revoked_grants", grantees.len().to_string()))
+        }
+        ExecuteMsg::Execute(settings) => {
+            execute_withdraw_rewards_split(deps, &env, &info.sender, settings)
+        }
+        ExecuteMsg::ExecuteBatch(batch) => {
+            let mut response =
+                Response::default().add_attribute("action", "execute_batch_withdraw_rewards_split");
+            let mut executed_delegators: Vec<Addr> = vec![];
+            let mut num_skipped = 0u64;
+
+            for (reply_id, settings) in (0u64..).zip(batch) {
+                let delegator = settings.delegator.clone();
+
+                // a delegator can only be executed once per batch since every execution after
+                // the first would split the same pending rewards again
+                let execution =
+                    validate_granter_address(deps.api, &delegator).and_then(|delegator_addr| {
+                        if executed_delegators.contains(&delegator_addr) {
+                            return Err(ContractError::DuplicateBatchDelegator(delegator.clone()));
+                        }
+                        let (msgs, execution) =
+                            withdraw_rewards_split(deps.as_ref(), &env, &info.sender, settings)?;
+                        Ok((delegator_addr, msgs, execution))
+                    });
+
+                // a failing delegator is skipped and reported rather than failing the whole batch
+                match execution {
+                    Ok((delegator_addr, msgs, execution)) => {
+                        // all of the delegator's messages are sent by a single call back into the contract,
+                        // so a message failing on chain reverts only that delegator, who is skipped in the reply
+                        PENDING_EXECUTIONS.save(deps.storage, reply_id, &execution)?;
+                        response = response
+                            .add_submessage(SubMsg::reply_on_error(
+                                WasmMsg::Execute {
+                                    contract_addr: env.contract.address.to_string(),
+                                    msg: to_binary(&ExecuteMsg::ExecuteBatchEntry {
+                                        reply_id,
+                                        msgs,
+                                    })?,
+                                    funds: vec![],
+                                },
+                                reply_id,
+                            ))
+                            .add_attribute("executed", &delegator);
+                        executed_delegators.push(delegator_addr);
+                    }
+                    Err(err) => {
+                        num_skipped += 1;
+                        response = response.add_attribute("skipped", format!("{delegator}: {err}"));
                     }
                 }
-                .allowed_withdrawls;
-
-            // query the pending rewards for the delegator
-            let pending_rewards = query_pending_rewards(&deps.querier, &delegator_addr)?;
+            }
 
-            // if there are no pending rewards then throw an error
-            if pending_rewards.total.len().eq(&0) {
-                return Err(ContractError::NoPendingRewards(delegator_addr.to_string()));
+            Ok(response
+                .add_attribute("num_executed", executed_delegators.len().to_string())
+                .add_attribute("num_skipped", num_skipped.to_string()))
+        }
+        ExecuteMsg::ExecuteBatchEntry { reply_id, msgs } => {
+            if info.sender.ne(&env.contract.address) {
+                return Err(ContractError::Unauthorized {});
             }
 
-            // generate the messages to execute the withdrawl, both the MsgExec and the MsgSends
-            let RewardExecutionMsgs { msgs, grantee } = generate_reward_withdrawl_msgs(
-                pending_rewards,
-                grant_settings,
-                &info.sender,
-                &env.contract.address,
-                &delegator_addr,
-                percentage,
-            )?;
+            // the execution is recorded along with its messages so that both are reverted if one of them fails
+            let execution = PENDING_EXECUTIONS.load(deps.storage, reply_id)?;
+            PENDING_EXECUTIONS.remove(deps.storage, reply_id);
+            save_execution(deps.storage, env.block.time, &execution)?;
 
             Ok(Response::default()
                 .add_messages(msgs)
-                .add_attribute("action", "execute_withdraw_rewards_split")
-                .add_attribute("granter", delegator_addr)
-                .add_attribute("grantee", grantee))
+                .add_attribute("action", "record_batch_execution")
+                .add_attribute("granter", execution.delegator)
+                .add_attribute("grantee", execution.grantee))
+        }
+        ExecuteMsg::MigrateLegacyGrants(limit) => {
+            migrate_legacy_grants(deps, limit, "migrate_legacy_grants")
         }
         ExecuteMsg::PruneExpiredGrants() => {
             let mut expired_grants = vec![];
 
             // iterate through all the grants and check if they are expired
             for grant in GRANTS.range(deps.storage, None, None, Order::Ascending) {
-                let (delegator_addr, grant_settings) = grant?;
+                let (grant_key, grant_settings) = grant?;
 
                 // if the grant is expired, add it to the list of expired grants
                 if grant_settings.is_expired(&env.block) {
-                    expired_grants.push(delegator_addr);
+                    expired_grants.push(grant_key);
                 }
             }
 
             // remove all the expired grants
-            for expired_grant in expired_grants.clone() {
-                GRANTS.remove(deps.storage, &expired_grant);
+            for (delegator_addr, grantee_addr) in expired_grants.iter() {
+                GRANTS.remove(deps.storage, (delegator_addr, grantee_addr));
+                LAST_EXECUTIONS.remove(deps.storage, (delegator_addr, grantee_addr));
             }
 
             Ok(Response::default()
@@ -140,6 +266,24 @@ pub fn execute(
     }
 }
 
+#[cfg_attr(not(feature = "library"), entry_point)]
+#[cfg_attr(feature = "interface", cw_orch::interface_entry_point)]
+pub fn reply(deps: DepsMut, _env: Env, msg: Reply) -> Result<Response, ContractError> {
+    // only failed batch entries are replied to, whatever the entry recorded was reverted along with it
+    let execution = match PENDING_EXECUTIONS.may_load(deps.storage, msg.id)? {
+        Some(execution) => execution,
+        None => return Ok(Response::default()),
+    };
+    PENDING_EXECUTIONS.remove(deps.storage, msg.id);
+
+    match msg.result {
+        SubMsgResult::Ok(_) => Ok(Response::default()),
+        SubMsgResult::Err(err) => Ok(Response::default()
+            .add_attribute("action", "skip_batch_execution")
+            .add_attribute("skipped", format!("{}: {err}", execution.delegator))),
+    }
+}
+
 #[cfg_attr(not(feature = "library"), entry_point)]
 #[cfg_attr(feature = "interface", cw_orch::interface_entry_point)]
 pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> Result<Binary, ContractError> {
@@ -148,10 +292,10 @@ pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> Result<Binary, ContractErro
         QueryMsg::ActiveGrantsByDelegator(delegator) => {
             let delegator_addr = validate_granter_address(deps.api, &delegator)?;
 
-            let grant =
+            let grants =
                 query_active_grants_by_delegator(deps.storage, &env.block, &delegator_addr)?;
 
-            to_binary(&grant).map_err(ContractError::Std)
+            to_binary(&grants).map_err(ContractError::Std)
         }
         QueryMsg::ActiveGrantsByGrantee(grantee) => {
             let grantee = validate_grantee_address(deps.api, &grantee)?;
@@ -162,93 +306,422 @@ pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> Result<Binary, ContractErro
 
             to_binary(&grants).map_err(ContractError::Std)
         }
-        QueryMsg::SimulateExecute(ExecuteSettings {
-            delegator,
-            percentage: requested_percentage,
-        }) => {
+        QueryMsg::RewardTotalsByDelegator(delegator) => {
             let delegator_addr = validate_granter_address(deps.api, &delegator)?;
 
-            // get the max fee percentage from the delegator's grant settings
-            let max_fee_percentage = if let Ok(Some(grant)) =
-                query_active_grants_by_delegator(deps.storage, &env.block, &delegator_addr)
-            {
-                grant.allowed_withdrawls.max_fee_percentage
-            } else {
-                return Err(ContractError::NoActiveGrant(delegator));
-            };
-
-            // get the split rewards
-            let split_rewards = split_rewards(
-                query_pending_rewards(&deps.querier, &delegator_addr)?.total,
-                max_fee_percentage,
-                &requested_percentage,
-            );
-
-            to_binary(&split_rewards).map_err(ContractError::Std)
+            to_binary(
+                &DELEGATOR_TOTALS
+                    .may_load(deps.storage, &delegator_addr)?
+                    .unwrap_or_default(),
+            )
+            .map_err(ContractError::Std)
+        }
+        QueryMsg::RewardTotalsByTaxationAddress(taxation_address) => {
+            let taxation_addr = validate_taxation_address(deps.api, &taxation_address)?;
+
+            to_binary(
+                &TAXATION_ADDRESS_TOTALS
+                    .may_load(deps.storage, &taxation_addr)?
+                    .unwrap_or_default(),
+            )
+            .map_err(ContractError::Std)
+        }
+        QueryMsg::RewardTotalsByGrantee(grantee) => {
+            let grantee_addr = validate_grantee_address(deps.api, &grantee)?;
+
+            to_binary(
+                &GRANTEE_TOTALS
+                    .may_load(deps.storage, &grantee_addr)?
+                    .unwrap_or_default(),
+            )
+            .map_err(ContractError::Std)
+        }
+        QueryMsg::SimulateExecute(settings) => {
+            to_binary(&simulate_execute(deps, &env, None, settings)?).map_err(ContractError::Std)
+        }
+        QueryMsg::SimulateExecuteBatch { sender, batch } => {
+            let sender = deps.api.addr_validate(&sender)?;
+            let mut simulated_delegators: Vec<String> = vec![];
+            let mut simulations = vec![];
+
+            // mirrors the batch execution, reporting the delegators that would be skipped instead of failing
+            for settings in batch {
+                let delegator = settings.delegator.clone();
+                let simulation = if simulated_delegators.contains(&delegator) {
+                    Err(ContractError::DuplicateBatchDelegator(delegator.clone()))
+                } else {
+                    simulate_execute(deps, &env, Some(&sender), settings)
+                };
+
+                simulations.push(match simulation {
+                    Ok(simulation) => {
+                        simulated_delegators.push(delegator.clone());
+                        BatchSimulation {
+                            delegator,
+                            simulation: Some(simulation),
+                            error: None,
+                        }
+                    }
+                    Err(err) => BatchSimulation {
+                        delegator,
+                        simulation: None,
+                        error: Some(err.to_string()),
+                    },
+                });
+            }
+
+            to_binary(&simulations).map_err(ContractError::Std)
+        }
+        QueryMsg::RevokeSpec {
+            granter,
+            grantee,
+            grant_data,
+        } => {
+            // the authz grants to the contract are shared by all of the delegator's grantees,
+            // so they are only revoked along with the last one
+            let other_grantees =
+                query_active_grants_by_delegator(deps.storage, &env.block, &granter)?
+                    .iter()
+                    .any(|grant| grant.allowed_withdrawls.grantee.ne(grantee.as_str()));
+
+            let revokes = <QueryMsg as Grantable>::query_revokes(GrantStructure {
+                granter,
+                grantee,
+                expiration: GrantExpiration::Never,
+                grant_contract: env.contract.address,
+                grant_data,
+            })?
+            .into_iter()
+            .filter(|revoke| {
+                !other_grantees || matches!(revoke, RevokeRequirement::ContractExec { .. })
+            })
+            .collect::<Vec<_>>();
+
+            to_binary(&revokes).map_err(ContractError::Std)
         }
         QueryMsg::GrantSpec {
+            granter,
+            grantee,
             expiration,
+            grant_data,
+        } => to_binary(&query_shared_grants(
+            deps,
+            &env,
+            GrantStructure {
+                granter,
+                grantee,
+                expiration,
+                grant_contract: env.contract.address.clone(),
+                grant_data,
+            },
+        )?)
+        .map_err(ContractError::Std),
+        QueryMsg::DescribeGrantSpec {
             granter,
             grantee,
-            max_fee_percentage,
-            taxation_addr,
+            expiration,
+            grant_data,
         } => {
-            let grant_spec = QueryMsg::query_grants(
+            let grants = query_shared_grants(
+                deps,
+                &env,
                 GrantStructure {
                     granter,
                     grantee,
                     expiration,
-                    grant_contract: env.contract.address,
-                    grant_data: GrantSpecData {
-                        max_fee_percentage,
-                        taxation_addr,
-                    },
+                    grant_contract: env.contract.address.clone(),
+                    grant_data,
                 },
-                env.block.time,
-            );
+            )?;
+
+            to_binary(&describe_grant_reqs(grants).map_err(StdError::from)?)
+                .map_err(ContractError::Std)
+        }
+    }
+}
+
+/// the authz grants to the contract are shared by all of the delegator's grantees, so granting them again
+/// for a new grantee has to keep them around for as long as the longest lasting of the other grantees
+fn query_shared_grants(
+    deps: Deps,
+    env: &Env,
+    grant: GrantStructure<GrantSpecData>,
+) -> Result<Vec<GrantRequirement>, ContractError> {
+    let other_expirations =
+        query_active_grants_by_delegator(deps.storage, &env.block, &grant.granter)?
+            .into_iter()
+            .filter(|active| active.allowed_withdrawls.grantee.ne(grant.grantee.as_str()))
+            .map(|active| match active.allowed_withdrawls.expiration {
+                expiration if expiration.nanos() == u64::MAX => GrantExpiration::Never,
+                expiration => GrantExpiration::AtTime(expiration),
+            })
+            .collect::<Vec<_>>();
+
+    <QueryMsg as Grantable>::query_grants(grant, env.block.time)?
+        .into_iter()
+        .map(|requirement| match requirement {
+            GrantRequirement::GrantSpec {
+                grant_type,
+                granter,
+                grantee,
+                expiration,
+            } => Ok(GrantRequirement::GrantSpec {
+                grant_type,
+                granter,
+                grantee,
+                expiration: other_expirations
+                    .iter()
+                    .try_fold(expiration, |latest, other| latest.later(*other))
+                    .map_err(StdError::from)?,
+            }),
+            contract_exec => Ok(contract_exec),
+        })
+        .collect()
+}
+
+/// what an execution of a grant claims, after all the checks that decide whether it can go ahead
+struct PreparedExecution {
+    delegator_addr: Addr,
+    grantee_addr: Addr,
+    grant_settings: AllowedWithdrawlSettings,
+    pending_rewards: AllPendingRewards,
+}
+
+/// runs the checks shared by executing and simulating a grant, so a simulation fails exactly when the execution would.
+/// without a sender the execution is checked as if it was sent by the grant's grantee
+fn prepare_execution(
+    deps: Deps,
+    env: &Env,
+    sender: Option<&Addr>,
+    ExecuteSettings {
+        delegator,
+        grantee,
+        reward_thresholds,
+        ..
+    }: ExecuteSettings,
+) -> Result<PreparedExecution, ContractError> {
+    let delegator_addr = validate_granter_address(deps.api, &delegator)?;
 
-            to_binary(&grant_spec?).map_err(ContractError::Std)
+    // the sender's own grant is used unless they are the delegator or specified a grantee
+    let grantee_addr = match (grantee, sender) {
+        (Some(grantee), _) => Some(validate_grantee_address(deps.api, &grantee)?),
+        (None, Some(sender)) if sender.ne(&delegator_addr) => Some(sender.clone()),
+        (None, _) => None,
+    };
+
+    // query the grant settings, this will error if there is no active/unexpired grant
+    let grant = resolve_active_grant(
+        deps.storage,
+        &env.block,
+        &delegator_addr,
+        grantee_addr.as_ref(),
+    )?;
+    let grant_settings = grant.allowed_withdrawls;
+    let grant_grantee_addr = validate_grantee_address(deps.api, &grant_settings.grantee)?;
+
+    // validate that the executor is either the granter or the grantee
+    if let Some(sender) = sender {
+        if sender.ne(&delegator_addr) && sender.ne(&grant_grantee_addr) {
+            return Err(ContractError::Unauthorized {});
         }
     }
+
+    // reject the execution if the grant's min interval hasn't passed since its last execution
+    if let Some(next_eligible_execution) = grant.next_eligible_execution {
+        if env.block.time.lt(&next_eligible_execution) {
+            return Err(ContractError::ExecutionCooldown {
+                delegator: delegator_addr.to_string(),
+                grantee: grant_grantee_addr.to_string(),
+                next_eligible_execution,
+            });
+        }
+    }
+
+    // query the pending rewards for the delegator
+    let pending_rewards = query_pending_rewards(&deps.querier, &delegator_addr)?;
+
+    // if there are no pending rewards then throw an error
+    if pending_rewards.total.len().eq(&0) {
+        return Err(ContractError::NoPendingRewards(delegator_addr.to_string()));
+    }
+
+    // skip the validators whose rewards aren't worth claiming
+    let pending_rewards = apply_reward_thresholds(
+        pending_rewards,
+        &combine_reward_thresholds(grant_settings.reward_thresholds.clone(), reward_thresholds),
+        &delegator_addr,
+    )?;
+
+    Ok(PreparedExecution {
+        delegator_addr,
+        grantee_addr: grant_grantee_addr,
+        grant_settings,
+        pending_rewards,
+    })
+}
+
+/// withdraws a delegator's rewards and splits them between the delegator and the taxation recipients of their grant
+fn execute_withdraw_rewards_split(
+    deps: DepsMut,
+    env: &Env,
+    sender: &Addr,
+    settings: ExecuteSettings,
+) -> Result<Response, ContractError> {
+    let (msgs, execution) = withdraw_rewards_split(deps.as_ref(), env, sender, settings)?;
+    save_execution(deps.storage, env.block.time, &execution)?;
+
+    Ok(Response::default()
+        .add_messages(msgs)
+        .add_attribute("action", "execute_withdraw_rewards_split")
+        .add_attribute("granter", execution.delegator)
+        .add_attribute("grantee", execution.grantee))
+}
+
+/// generates the messages of an execution along with what it adds to the state once they went through
+fn withdraw_rewards_split(
+    deps: Deps,
+    env: &Env,
+    sender: &Addr,
+    settings: ExecuteSettings,
+) -> Result<(Vec<CosmosMsg>, ExecutionRecord), ContractError> {
+    let percentage = settings.percentage;
+    let PreparedExecution {
+        delegator_addr,
+        grantee_addr: grant_grantee_addr,
+        grant_settings,
+        pending_rewards,
+    } = prepare_execution(deps, env, Some(sender), settings)?;
+
+    // the split that the messages will carry out, kept for the running totals
+    let RewardSplit {
+        delegator_rewards,
+        taxation_address_rewards,
+        recipient_rewards,
+    } = split_rewards(
+        pending_rewards.total.clone(),
+        &grant_settings.taxation_recipients,
+        &percentage,
+    );
+
+    // generate the messages to execute the withdrawl, both the MsgExec and the MsgSends
+    let RewardExecutionMsgs { msgs, .. } = generate_reward_withdrawl_msgs(
+        pending_rewards,
+        grant_settings,
+        sender,
+        &env.contract.address,
+        &delegator_addr,
+        &query_delegator_withdraw_address(&deps.querier, &delegator_addr)?,
+        percentage,
+        &deps.querier.query_bonded_denom()?,
+    )?;
+
+    // only the taxation recipients that received a share of the rewards are added to the totals
+    let recipient_rewards = recipient_rewards
+        .into_iter()
+        .filter(|RecipientRewards { rewards, .. }| !filter_empty_coins(rewards.clone()).is_empty())
+        .map(|RecipientRewards { address, rewards }| {
+            Ok((validate_taxation_address(deps.api, &address)?, rewards))
+        })
+        .collect::<Result<Vec<_>, ContractError>>()?;
+
+    Ok((
+        msgs,
+        ExecutionRecord {
+            delegator: delegator_addr,
+            grantee: grant_grantee_addr,
+            delegator_rewards,
+            taxation_address_rewards,
+            recipient_rewards,
+        },
+    ))
+}
+
+/// saves the grant's last execution and adds the execution to the running totals of the delegator,
+/// grantee and taxation addresses
+fn save_execution(
+    storage: &mut dyn Storage,
+    time: Timestamp,
+    ExecutionRecord {
+        delegator,
+        grantee,
+        delegator_rewards,
+        taxation_address_rewards,
+        recipient_rewards,
+    }: &ExecutionRecord,
+) -> StdResult<()> {
+    LAST_EXECUTIONS.save(storage, (delegator, grantee), &time)?;
+
+    let add_execution = |totals: Option<RewardTotals>, taxation_rewards: &[Coin]| {
+        StdResult::Ok(add_to_reward_totals(
+            totals.unwrap_or_default(),
+            delegator_rewards,
+            taxation_rewards,
+            time,
+        ))
+    };
+    DELEGATOR_TOTALS.update(storage, delegator, |totals| {
+        add_execution(totals, taxation_address_rewards)
+    })?;
+    GRANTEE_TOTALS.update(storage, grantee, |totals| {
+        add_execution(totals, taxation_address_rewards)
+    })?;
+    for (taxation_addr, rewards) in recipient_rewards {
+        TAXATION_ADDRESS_TOTALS.update(storage, taxation_addr, |totals| {
+            add_execution(totals, rewards)
+        })?;
+    }
+
+    Ok(())
+}
+
+/// simulates how a delegator's rewards would be split if the grant was executed by the sender
+fn simulate_execute(
+    deps: Deps,
+    env: &Env,
+    sender: Option<&Addr>,
+    settings: ExecuteSettings,
+) -> Result<SimulateExecuteResponse, ContractError> {
+    let requested_percentage = settings.percentage;
+    let PreparedExecution {
+        grant_settings,
+        pending_rewards,
+        ..
+    } = prepare_execution(deps, env, sender, settings)?;
+
+    // get the split rewards
+    let RewardSplit {
+        delegator_rewards,
+        taxation_address_rewards,
+        recipient_rewards,
+    } = split_rewards(
+        pending_rewards.total,
+        &grant_settings.taxation_recipients,
+        &requested_percentage,
+    );
+
+    Ok(SimulateExecuteResponse {
+        claimed_validators: pending_rewards
+            .rewards
+            .into_iter()
+            .map(|reward| reward.validator)
+            .collect(),
+        delegator_rewards,
+        taxation_address_rewards,
+        recipient_rewards,
+    })
 }
 
 impl Grantable for QueryMsg {
     type GrantSettings = GrantSpecData;
 
-    fn query_revokes(grant: GrantStructure<GrantSpecData>) -> StdResult<Vec<RevokeRequirement>> {
-        Self::query_grants(grant, Timestamp::default())?
-            .into_iter()
-            .map(|grant_req| -> StdResult<RevokeRequirement> {
-                match grant_req {
-                    GrantRequirement::GrantSpec {
-                        grant_type,
-                        granter,
-                        grantee,
-                        expiration,
-                    } => Ok(GrantRequirement::GrantSpec {
-                        grant_type,
-                        granter,
-                        grantee,
-                        expiration,
-                    }
-                    .into()),
-                    GrantRequirement::ContractExec {
-                        contract_addr,
-                        sender,
-                        ..
-                    } => Ok(RevokeRequirement::ContractExec {
-                        contract_addr,
-                        msg: to_binary(&ExecuteMsg::Revoke())?,
-                        sender,
-                    }),
-                }
-            })
-            .collect::<StdResult<Vec<RevokeRequirement>>>()
+    // the contract's own grant is removed by revoking the grantee
+    fn revoke_msg(grant: &GrantStructure<GrantSpecData>) -> StdResult<Option<Binary>> {
+        to_binary(&ExecuteMsg::Revoke(Some(grant.grantee.to_string()))).map(Some)
     }
 
     fn query_grants(
         grant: GrantStructure<GrantSpecData>,
-        _current_timestamp: Timestamp,
+        current_timestamp: Timestamp,
     ) -> StdResult<Vec<GrantRequirement>> {
         let GrantStructure {
             granter,
@@ -257,8 +730,9 @@ impl Grantable for QueryMsg {
             grant_contract,
             grant_data,
         } = grant;
+        let expiration = expiration.resolve(current_timestamp);
 
-        Ok(vec![
+        let mut grants = vec![
             GrantRequirement::GrantSpec {
                 grant_type: AuthorizationType::GenericAuthorization {
                     msg: "/cosmos.distribution.v1beta1.MsgWithdrawDelegatorReward".to_string(),
@@ -275,16 +749,50 @@ impl Grantable for QueryMsg {
                 grantee: grant_contract.clone(),
                 expiration,
             },
-            GrantRequirement::ContractExec {
-                sender: granter,
-                contract_addr: grant_contract,
-                msg: to_binary(&ExecuteMsg::Grant(AllowedWithdrawlSettings {
-                    grantee: grantee.to_string(),
-                    taxation_address: grant_data.taxation_addr.to_string(),
-                    max_fee_percentage: grant_data.max_fee_percentage,
-                    expiration,
-                }))?,
-            },
-        ])
+        ];
+
+        // restaking the delegator's share needs the contract to be able to delegate for them
+        if let Some(policy) = &grant_data.auto_compound {
+            grants.push(GrantRequirement::GrantSpec {
+                grant_type: AuthorizationType::StakeAuthorization {
+                    max_tokens: None,
+                    authorization_type: StakeAuthorizationType::Delegate,
+                    validators: match policy {
+                        CompoundValidatorPolicy::SameValidators => None,
+                        CompoundValidatorPolicy::FixedValidator(validator) => Some(
+                            StakeAuthorizationPolicy::AllowList(StakeAuthorizationValidators {
+                                address: vec![validator.clone()],
+                            }),
+                        ),
+                        CompoundValidatorPolicy::AllowList(validators) => Some(
+                            StakeAuthorizationPolicy::AllowList(StakeAuthorizationValidators {
+                                address: validators.clone(),
+                            }),
+                        ),
+                    },
+                },
+                granter: granter.clone(),
+                grantee: grant_contract.clone(),
+                expiration,
+            });
+        }
+
+        grants.push(GrantRequirement::ContractExec {
+            sender: granter,
+            contract_addr: grant_contract,
+            msg: to_binary(&ExecuteMsg::Grant(AllowedWithdrawlSettings {
+                grantee: grantee.to_string(),
+                taxation_recipients: grant_data.taxation_recipients,
+                auto_compound: grant_data.auto_compound,
+                reward_thresholds: grant_data.reward_thresholds,
+                min_interval: grant_data.min_interval,
+                // the contract tracks its own expiration so grants that never expire get the latest one possible
+                expiration: expiration
+                    .timestamp()?
+                    .unwrap_or(Timestamp::from_nanos(u64::MAX)),
+            }))?,
+        });
+
+        Ok(grants)
     }
 }
@@ -1,5 +1,5 @@
 use cosmos_sdk_proto::prost::{DecodeError, EncodeError};
-use cosmwasm_std::StdError;
+use cosmwasm_std::{Decimal, StdError, Timestamp};
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -13,12 +13,25 @@ pub enum ContractError {
     #[error("Semver parsing error: {0}")]
     SemVer(String),
 
+    #[error("Cannot migrate from {from} to {to}")]
+    InvalidMigration { from: String, to: String },
+
     #[error("Decode Error: {0}. This is likely from a failing stargate query.")]
     Decode(#[from] DecodeError),
 
     #[error("No pending rewards for {0}")]
     NoPendingRewards(String),
 
+    #[error("None of the pending rewards for {0} meet the minimum reward thresholds")]
+    RewardsBelowThreshold(String),
+
+    #[error("The grant from {delegator} to {grantee} can't be executed again until {next_eligible_execution}")]
+    ExecutionCooldown {
+        delegator: String,
+        grantee: String,
+        next_eligible_execution: Timestamp,
+    },
+
     #[error("Target Not Implemented")]
     NotImplemented {},
 
@@ -34,11 +47,29 @@ pub enum ContractError {
     #[error("{0} is not a valid withdraw share address.")]
     InvalidWithdrawShareAddress(String),
 
+    #[error("A grant needs at least one taxation recipient.")]
+    NoTaxationRecipients,
+
+    #[error("The taxation recipients' max fee percentages add up to {0} which is more than 100%.")]
+    MaxFeePercentageExceeded(Decimal),
+
+    #[error("{0} is not a known validator.")]
+    UnknownValidator(String),
+
+    #[error("The auto compound validator allow list needs at least one validator.")]
+    EmptyValidatorAllowList,
+
     #[error("Falied to query pending rewards.")]
     QueryPendingRewardsFailure,
 
     #[error("No active grant for {0}")]
     NoActiveGrant(String),
+
+    #[error("{0} has more than one active grant, the grantee must be specified")]
+    GranteeRequired(String),
+
+    #[error("{0} is already being executed in this batch")]
+    DuplicateBatchDelegator(String),
 }
 
 impl From<semver::Error> for ContractError {
@@ -1,20 +1,22 @@
-use cosmwasm_std::{Addr, BankMsg, CosmosMsg, Decimal};
+use cosmwasm_std::{Addr, BankMsg, Coin, CosmosMsg, Decimal};
 
 use crate::{
     helpers::{
-        filter_empty_coins, set_withdraw_rewards_address_msg, split_rewards, withdraw_rewards_msgs,
+        compound_delegations, delegate_msgs, filter_empty_coins, set_withdraw_rewards_address_msg,
+        split_rewards, sum_coins, withdraw_rewards_msgs, RewardSplit,
     },
-    msg::{AllowedWithdrawlSettings, SimulateExecuteResponse},
+    msg::{AllowedWithdrawlSettings, RecipientRewards},
     queries::{AllPendingRewards, PendingReward},
     ContractError,
 };
 
 /// creates the message that gets broadcast to claim the rewards to this contract
-/// and then ensure that the withdraw address is set back to the grantee
+/// and then ensure that the withdraw address is set back to what the delegator had it set to
 /// this is all wrapped in one MsgExec to interface with the native Authz module
 pub fn create_withdraw_rewards_exec_msg(
     delegator_addr: &Addr,
     contract_addr: &Addr,
+    withdraw_addr: &Addr,
     rewards: &[PendingReward],
 ) -> Result<CosmosMsg, ContractError> {
     let mut claim_rewards_msgs = vec![];
@@ -28,10 +30,10 @@ pub fn create_withdraw_rewards_exec_msg(
     // claim all of the users rewards. these should now be sent into this contract
     claim_rewards_msgs.extend(withdraw_rewards_msgs(delegator_addr, rewards)?);
 
-    // put the delegator's withdraw address back to them so they dont accidentally send us tokens
+    // put the delegator's withdraw address back to what it was so they dont accidentally send us tokens
     claim_rewards_msgs.push(set_withdraw_rewards_address_msg(
         delegator_addr,
-        delegator_addr,
+        withdraw_addr,
     )?);
 
     // wrap the messages for claiming into a single exec message as these will get done via native Authz
@@ -46,14 +48,17 @@ pub struct RewardExecutionMsgs {
 }
 
 /// Generates the withdraw rewards messages and the messages to disburse the funds to both
-/// the granter and the fee wallet.
+/// the granter and each of the taxation recipients.
 ///
 /// * `all_pending_rewards` - the rewards that are being withdrawn
-/// * `grant_settings` - the settings for the grant including the maximum fee split
+/// * `grant_settings` - the settings for the grant including the taxation recipients and their maximum fee splits
 /// * `sender_addr` - the address of the wallet that initiated the withdraw. this must be the delegator or the grantee
 /// * `contract_addr` - the address of the contract this function is running in
 /// * `delegator_addr` - the address of the delegator that is withdrawing the rewards
+/// * `withdraw_addr` - the address the delegator's rewards are withdrawn to, their share of the rewards is sent here
 /// * `percentage` - the percentage of the rewards to withdraw. If None, the max allowed fee will be used
+/// * `bond_denom` - the staking denom of the chain, only the rewards in this denom are restaked when auto compounding
+#[allow(clippy::too_many_arguments)]
 pub fn generate_reward_withdrawl_msgs(
     AllPendingRewards {
         rewards,
@@ -61,62 +66,130 @@ pub fn generate_reward_withdrawl_msgs(
     }: AllPendingRewards,
     AllowedWithdrawlSettings {
         grantee,
-        taxation_address,
-        max_fee_percentage,
+        taxation_recipients,
+        auto_compound,
         ..
     }: AllowedWithdrawlSettings,
     sender_addr: &Addr,
     contract_addr: &Addr,
     delegator_addr: &Addr,
+    withdraw_addr: &Addr,
     percentage: Option<Decimal>,
+    bond_denom: &str,
 ) -> Result<RewardExecutionMsgs, ContractError> {
     // validate that the executor is either the granter or the grantee
     if sender_addr.ne(delegator_addr) && sender_addr.ne(&grantee) {
         return Err(ContractError::Unauthorized {});
     }
 
+    // the delegator's share of the rewards that gets restaked, if auto compounding is on
+    let delegations = |delegator_rewards: &[Coin]| match (
+        &auto_compound,
+        delegator_rewards
+            .iter()
+            .find(|coin| coin.denom.eq(bond_denom)),
+    ) {
+        (Some(policy), Some(restake_amount)) => {
+            compound_delegations(policy, &rewards, restake_amount.clone())
+        }
+        _ => vec![],
+    };
+
     // if the percentage is zero, just send the rewards to the grantee, no need to mess with
-    // the taxation address
-    if percentage.is_some() && percentage.unwrap().is_zero() {
+    // the taxation address. if the rewards go straight to the delegator they can be restaked
+    // in the same MsgExec
+    if percentage.is_some()
+        && percentage.unwrap().is_zero()
+        && (auto_compound.is_none() || withdraw_addr.eq(delegator_addr))
+    {
+        let mut exec_msgs = withdraw_rewards_msgs(delegator_addr, &rewards)?;
+        exec_msgs.extend(delegate_msgs(
+            delegator_addr,
+            &delegations(&all_pending_rewards),
+        )?);
+
         return Ok(RewardExecutionMsgs {
-            msgs: vec![authzpp_utils::msg_gen::exec_msg(
-                contract_addr,
-                withdraw_rewards_msgs(delegator_addr, &rewards)?,
-            )
-            .map_err(ContractError::EncodeError)?],
+            msgs: vec![authzpp_utils::msg_gen::exec_msg(contract_addr, exec_msgs)
+                .map_err(ContractError::EncodeError)?],
             grantee,
         });
     }
 
-    // calculate how much the granter and grantee/withdraw address should get from the staking rewards
-    let SimulateExecuteResponse {
+    // calculate how much the granter and each taxation recipient should get from the staking rewards
+    let RewardSplit {
         delegator_rewards,
-        taxation_address_rewards,
-    } = split_rewards(all_pending_rewards, max_fee_percentage, &percentage);
+        recipient_rewards,
+        ..
+    } = split_rewards(all_pending_rewards, &taxation_recipients, &percentage);
 
     // create the message to execute the rewards withdraw
     let withdraw_rewards_exec_msg =
-        create_withdraw_rewards_exec_msg(delegator_addr, contract_addr, &rewards)?;
+        create_withdraw_rewards_exec_msg(delegator_addr, contract_addr, withdraw_addr, &rewards)?;
 
     let mut msgs = vec![withdraw_rewards_exec_msg];
 
-    // send the share address their share of the rewards if there are any
-    if filter_empty_coins(taxation_address_rewards.clone())
-        .len()
-        .gt(&0)
-    {
-        msgs.push(cosmwasm_std::CosmosMsg::Bank(BankMsg::Send {
-            to_address: taxation_address,
-            amount: taxation_address_rewards,
-        }));
+    // send each taxation recipient their share of the rewards if there are any
+    for RecipientRewards { address, rewards } in recipient_rewards {
+        if filter_empty_coins(rewards.clone()).len().gt(&0) {
+            msgs.push(cosmwasm_std::CosmosMsg::Bank(BankMsg::Send {
+                to_address: address,
+                amount: rewards,
+            }));
+        }
     }
 
     // send the granter their share of the rewards
-    if filter_empty_coins(delegator_rewards.clone()).len().gt(&0) {
-        msgs.push(cosmwasm_std::CosmosMsg::Bank(BankMsg::Send {
-            to_address: delegator_addr.to_string(),
-            amount: delegator_rewards,
-        }));
+    let delegations = delegations(&delegator_rewards);
+    if withdraw_addr.eq(delegator_addr) {
+        if filter_empty_coins(delegator_rewards.clone()).len().gt(&0) {
+            msgs.push(cosmwasm_std::CosmosMsg::Bank(BankMsg::Send {
+                to_address: delegator_addr.to_string(),
+                amount: delegator_rewards,
+            }));
+        }
+    } else {
+        // only the part being restaked goes to the delegator, the rest goes to their withdraw address
+        let restaked_rewards = delegations
+            .iter()
+            .fold(vec![], |acc, (_, coin)| sum_coins(acc, vec![coin.clone()]));
+        let withdrawn_rewards = delegator_rewards
+            .into_iter()
+            .map(|Coin { denom, amount }| {
+                let restaked = restaked_rewards
+                    .iter()
+                    .find(|coin| coin.denom.eq(&denom))
+                    .map(|coin| coin.amount)
+                    .unwrap_or_default();
+
+                Coin {
+                    denom,
+                    amount: amount - restaked,
+                }
+            })
+            .collect::<Vec<_>>();
+
+        for (to_address, amount) in [
+            (delegator_addr, restaked_rewards),
+            (withdraw_addr, filter_empty_coins(withdrawn_rewards)),
+        ] {
+            if amount.len().gt(&0) {
+                msgs.push(cosmwasm_std::CosmosMsg::Bank(BankMsg::Send {
+                    to_address: to_address.to_string(),
+                    amount,
+                }));
+            }
+        }
+    }
+
+    // restake the granter's share once it is back in their wallet
+    if !delegations.is_empty() {
+        msgs.push(
+            authzpp_utils::msg_gen::exec_msg(
+                contract_addr,
+                delegate_msgs(delegator_addr, &delegations)?,
+            )
+            .map_err(ContractError::EncodeError)?,
+        );
     }
 
     Ok(RewardExecutionMsgs { msgs, grantee })
@@ -1,16 +1,20 @@
 use std::str::FromStr;
 
-use crate::msg::{AllowedWithdrawlSettings, SimulateExecuteResponse};
-use crate::queries::PendingReward;
+use crate::msg::{
+    AllowedWithdrawlSettings, CompoundValidatorPolicy, RecipientRewards, RewardThresholds,
+    RewardTotals, TaxationRecipient,
+};
+use crate::queries::{AllPendingRewards, PendingReward};
 use crate::ContractError;
 use authzpp_utils::helpers::Expirable;
 use cosmos_sdk_proto::cosmos::base::v1beta1::DecCoin;
 use cosmos_sdk_proto::cosmos::distribution::v1beta1::MsgSetWithdrawAddress;
+use cosmos_sdk_proto::cosmos::{base::v1beta1::Coin as ProtoCoin, staking::v1beta1::MsgDelegate};
 use cosmos_sdk_proto::traits::MessageExt;
 use cosmos_sdk_proto::{
     cosmos::distribution::v1beta1::MsgWithdrawDelegatorReward, prost::EncodeError, Any,
 };
-use cosmwasm_std::{Addr, Api, BlockInfo, Coin, Decimal, Uint128};
+use cosmwasm_std::{Addr, Api, BlockInfo, Coin, Decimal, QuerierWrapper, Timestamp, Uint128};
 
 pub fn validate_granter_address(api: &dyn Api, granter: &str) -> Result<Addr, ContractError> {
     api.addr_validate(granter)
@@ -22,6 +26,11 @@ pub fn validate_grantee_address(api: &dyn Api, grantee: &str) -> Result<Addr, Co
         .map_err(|_| ContractError::InvalidGranteeAddress(grantee.to_string()))
 }
 
+pub fn validate_taxation_address(api: &dyn Api, address: &str) -> Result<Addr, ContractError> {
+    api.addr_validate(address)
+        .map_err(|_| ContractError::InvalidWithdrawShareAddress(address.to_string()))
+}
+
 /// Combines two vectors of coins into just one where any overlapping denoms are added together
 pub fn sum_coins(xs: Vec<Coin>, ys: Vec<Coin>) -> Vec<Coin> {
     let mut coins = xs;
@@ -66,24 +75,138 @@ pub fn partition_coins_by_percentage(
     (percentage_coins, remaining_coins)
 }
 
-/// computes the rewards that should be sent to the granter and the withdraw address
+/// validates the taxation recipients' addresses and that their max percentages don't add up to more than 100%
+pub fn validate_taxation_recipients(
+    api: &dyn Api,
+    recipients: &[TaxationRecipient],
+) -> Result<(), ContractError> {
+    if recipients.is_empty() {
+        return Err(ContractError::NoTaxationRecipients);
+    }
+
+    for TaxationRecipient { address, .. } in recipients {
+        validate_taxation_address(api, address)?;
+    }
+
+    let max_percentage = total_max_fee_percentage(recipients);
+    if max_percentage > Decimal::one() {
+        return Err(ContractError::MaxFeePercentageExceeded(max_percentage));
+    }
+
+    Ok(())
+}
+
+/// sums the max percentages of all the taxation recipients
+pub fn total_max_fee_percentage(recipients: &[TaxationRecipient]) -> Decimal {
+    recipients.iter().fold(Decimal::zero(), |acc, recipient| {
+        acc + recipient.max_fee_percentage
+    })
+}
+
+/// How the claimed rewards are divided between the granter and the taxation recipients
+#[derive(Clone, Debug, PartialEq)]
+pub struct RewardSplit {
+    /// rewards that the granter will receive
+    pub delegator_rewards: Vec<Coin>,
+    /// rewards that all of the taxation recipients will receive combined
+    pub taxation_address_rewards: Vec<Coin>,
+    /// rewards that each of the taxation recipients will receive
+    pub recipient_rewards: Vec<RecipientRewards>,
+}
+
+/// adds the rewards from an execution at the given time to the running totals
+pub fn add_to_reward_totals(
+    RewardTotals {
+        executions,
+        delegator_rewards,
+        taxation_rewards,
+        ..
+    }: RewardTotals,
+    execution_delegator_rewards: &[Coin],
+    execution_taxation_rewards: &[Coin],
+    time: Timestamp,
+) -> RewardTotals {
+    RewardTotals {
+        executions: executions + 1,
+        delegator_rewards: sum_coins(
+            delegator_rewards,
+            filter_empty_coins(execution_delegator_rewards.to_vec()),
+        ),
+        taxation_rewards: sum_coins(
+            taxation_rewards,
+            filter_empty_coins(execution_taxation_rewards.to_vec()),
+        ),
+        last_execution: Some(time),
+    }
+}
+
+/// computes the rewards that should be sent to the granter and each of the taxation recipients.
+/// the percentage taken is shared between the recipients in proportion to their max percentages
 pub fn split_rewards(
     total_rewards: Vec<Coin>,
-    max_percentage: Decimal,
+    recipients: &[TaxationRecipient],
     requested_percentage: &Option<Decimal>,
-) -> SimulateExecuteResponse {
-    // figure out what percentage of the rewards to send to the grantee
+) -> RewardSplit {
+    let max_percentage = total_max_fee_percentage(recipients);
+
+    // figure out what percentage of the rewards to send to the recipients
     let percentage_to_send = requested_percentage
         .unwrap_or(max_percentage)
         .min(max_percentage);
 
-    // get the list of tokens that the granter and grantee should each recieve
-    let (withdraw_address_rewards, delegator_rewards) =
-        partition_coins_by_percentage(percentage_to_send, total_rewards);
+    // get the list of tokens that each recipient should recieve
+    let recipient_rewards = recipients
+        .iter()
+        .map(
+            |TaxationRecipient {
+                 address,
+                 max_fee_percentage,
+             }| {
+                let recipient_percentage = if max_fee_percentage.eq(&max_percentage) {
+                    percentage_to_send
+                } else {
+                    // multiply before dividing so that the shares dont lose precision
+                    percentage_to_send * *max_fee_percentage / max_percentage
+                };
+
+                RecipientRewards {
+                    address: address.to_string(),
+                    rewards: partition_coins_by_percentage(
+                        recipient_percentage,
+                        total_rewards.clone(),
+                    )
+                    .0,
+                }
+            },
+        )
+        .collect::<Vec<_>>();
+
+    // the recipients' rewards combined, in the same order as the total rewards
+    let taxation_address_rewards = recipient_rewards.iter().fold(
+        total_rewards
+            .iter()
+            .map(|Coin { denom, .. }| Coin {
+                denom: denom.to_string(),
+                amount: Uint128::zero(),
+            })
+            .collect::<Vec<_>>(),
+        |acc, RecipientRewards { rewards, .. }| sum_coins(acc, rewards.clone()),
+    );
+
+    // the granter keeps whatever isn't sent to the recipients
+    let delegator_rewards = total_rewards
+        .into_iter()
+        .zip(taxation_address_rewards.iter())
+        .map(|(Coin { denom, amount }, taxed)| Coin {
+            denom,
+            amount: amount - taxed.amount,
+        })
+        .collect();
 
-    SimulateExecuteResponse {
+    RewardSplit {
         delegator_rewards,
-        taxation_address_rewards: withdraw_address_rewards,
+        taxation_address_rewards,
+        recipient_rewards,
     }
 }
 
@@ -120,6 +243,206 @@ pub fn set_withdraw_rewards_address_msg(
     Ok(set_withdraw_address_msg)
 }
 
+/// combines the grant's thresholds with the ones requested for an execution, keeping the higher minimum of each denom
+pub fn combine_reward_thresholds(
+    grant_thresholds: Option<RewardThresholds>,
+    execute_thresholds: Option<RewardThresholds>,
+) -> RewardThresholds {
+    let max_coins = |xs: Vec<Coin>, ys: Vec<Coin>| {
+        let mut coins = xs;
+        for y in ys {
+            match coins.iter_mut().find(|x| x.denom.eq(&y.denom)) {
+                Some(x) => x.amount = x.amount.max(y.amount),
+                None => coins.push(y),
+            }
+        }
+        coins
+    };
+
+    let grant_thresholds = grant_thresholds.unwrap_or_default();
+    let execute_thresholds = execute_thresholds.unwrap_or_default();
+
+    RewardThresholds {
+        min_total_rewards: max_coins(
+            grant_thresholds.min_total_rewards,
+            execute_thresholds.min_total_rewards,
+        ),
+        min_validator_rewards: max_coins(
+            grant_thresholds.min_validator_rewards,
+            execute_thresholds.min_validator_rewards,
+        ),
+    }
+}
+
+/// whether any of the coins reach the minimum for their denom, only the listed denoms count.
+/// without any minimums there is nothing to reach
+fn any_coin_meets_minimum(coins: &[Coin], minimums: &[Coin]) -> bool {
+    minimums.is_empty()
+        || coins.iter().any(|Coin { denom, amount }| {
+            minimums
+                .iter()
+                .find(|minimum| minimum.denom.eq(denom))
+                .is_some_and(|minimum| !amount.is_zero() && amount.ge(&minimum.amount))
+        })
+}
+
+/// drops the validators whose rewards are too small to be worth claiming.
+/// errors if what is left doesn't reach the minimum total rewards
+pub fn apply_reward_thresholds(
+    pending_rewards: AllPendingRewards,
+    RewardThresholds {
+        min_total_rewards,
+        min_validator_rewards,
+    }: &RewardThresholds,
+    delegator_addr: &Addr,
+) -> Result<AllPendingRewards, ContractError> {
+    // without any thresholds everything is claimed as is
+    if min_total_rewards.is_empty() && min_validator_rewards.is_empty() {
+        return Ok(pending_rewards);
+    }
+
+    let AllPendingRewards { rewards, .. } = pending_rewards;
+    let rewards = rewards
+        .into_iter()
+        .filter(|reward| any_coin_meets_minimum(&reward.amount, min_validator_rewards))
+        .collect::<Vec<_>>();
+
+    let total = filter_empty_coins(
+        rewards
+            .iter()
+            .fold(vec![], |acc, reward| sum_coins(acc, reward.amount.clone())),
+    );
+
+    if !any_coin_meets_minimum(&total, min_total_rewards) {
+        return Err(ContractError::RewardsBelowThreshold(
+            delegator_addr.to_string(),
+        ));
+    }
+
+    Ok(AllPendingRewards { rewards, total })
+}
+
+/// validates that the validators the policy restakes to exist
+pub fn validate_compound_policy(
+    querier: &QuerierWrapper,
+    policy: &CompoundValidatorPolicy,
+) -> Result<(), ContractError> {
+    let validators = match policy {
+        CompoundValidatorPolicy::SameValidators => return Ok(()),
+        CompoundValidatorPolicy::FixedValidator(validator) => vec![validator.clone()],
+        CompoundValidatorPolicy::AllowList(validators) if validators.is_empty() => {
+            return Err(ContractError::EmptyValidatorAllowList)
+        }
+        CompoundValidatorPolicy::AllowList(validators) => validators.clone(),
+    };
+
+    for validator in validators {
+        if querier.query_validator(&validator)?.is_none() {
+            return Err(ContractError::UnknownValidator(validator));
+        }
+    }
+
+    Ok(())
+}
+
+/// splits the amount to restake between the validators picked by the policy.
+/// validators are weighted by the amount of the restaked denom that was claimed from them
+pub fn compound_delegations(
+    policy: &CompoundValidatorPolicy,
+    pending_rewards: &[PendingReward],
+    Coin { denom, amount }: Coin,
+) -> Vec<(String, Coin)> {
+    // the amount of the restaked denom that was claimed from a given validator
+    let claimed_from = |validator: &str| -> Uint128 {
+        pending_rewards
+            .iter()
+            .filter(|reward| reward.validator.eq(validator))
+            .flat_map(|reward| reward.amount.iter())
+            .filter(|coin| coin.denom.eq(&denom))
+            .fold(Uint128::zero(), |acc, coin| acc + coin.amount)
+    };
+
+    let weights: Vec<(String, Uint128)> = match policy {
+        CompoundValidatorPolicy::SameValidators => pending_rewards
+            .iter()
+            .map(|reward| (reward.validator.clone(), claimed_from(&reward.validator)))
+            .collect(),
+        CompoundValidatorPolicy::FixedValidator(validator) => {
+            vec![(validator.clone(), Uint128::one())]
+        }
+        CompoundValidatorPolicy::AllowList(validators) => {
+            let weights = validators
+                .iter()
+                .map(|validator| (validator.clone(), claimed_from(validator)))
+                .collect::<Vec<_>>();
+
+            // fall back to restaking evenly if nothing was claimed from the listed validators
+            if weights.iter().all(|(_, weight)| weight.is_zero()) {
+                validators
+                    .iter()
+                    .map(|validator| (validator.clone(), Uint128::one()))
+                    .collect()
+            } else {
+                weights
+            }
+        }
+    };
+
+    let weights = weights
+        .into_iter()
+        .filter(|(_, weight)| !weight.is_zero())
+        .collect::<Vec<_>>();
+    let total_weight = weights
+        .iter()
+        .fold(Uint128::zero(), |acc, (_, weight)| acc + weight);
+
+    let mut remaining = amount;
+    let mut delegations = vec![];
+    for (i, (validator, weight)) in weights.iter().enumerate() {
+        // the last validator gets whatever is left over from rounding down
+        let delegation_amount = if i == weights.len() - 1 {
+            remaining
+        } else {
+            amount.multiply_ratio(*weight, total_weight)
+        };
+        remaining -= delegation_amount;
+
+        if !delegation_amount.is_zero() {
+            delegations.push((
+                validator.clone(),
+                Coin {
+                    denom: denom.clone(),
+                    amount: delegation_amount,
+                },
+            ));
+        }
+    }
+
+    delegations
+}
+
+/// Generates the messages for delegating the given amounts to their validators
+pub fn delegate_msgs(
+    delegator_addr: &Addr,
+    delegations: &[(String, Coin)],
+) -> Result<Vec<Any>, ContractError> {
+    let delegate_msgs = delegations
+        .iter()
+        .map(|(validator, Coin { denom, amount })| {
+            Any::from_msg(&MsgDelegate {
+                delegator_address: delegator_addr.to_string(),
+                validator_address: validator.to_string(),
+                amount: Some(ProtoCoin {
+                    denom: denom.to_string(),
+                    amount: amount.to_string(),
+                }),
+            })
+        })
+        .collect::<Result<Vec<_>, EncodeError>>()?;
+
+    Ok(delegate_msgs)
+}
+
 impl Expirable for AllowedWithdrawlSettings {
     fn is_not_expired(&self, block: &BlockInfo) -> bool {
         block.time <= (self.expiration)
@@ -1,17 +1,23 @@
 use cosmwasm_schema::{cw_serde, QueryResponses};
-use cosmwasm_std::{Addr, Coin, Decimal, Timestamp};
-use cw_grant_spec::grants::GrantRequirement;
+use cosmwasm_std::{Addr, Coin, CosmosMsg, Decimal, Timestamp};
+use cw_grant_spec::{grant_spec_queries, MessageKeys};
 
 #[cw_serde]
 pub struct InstantiateMsg {}
 
 #[cw_serde]
-pub struct MigrateMsg {}
+pub struct MigrateMsg {
+    /// the most legacy grants to move over in this migration, the rest can be moved by migrating again
+    pub limit: Option<u32>,
+}
 
 pub type ActiveGrantsByGranteeResponse = Vec<GrantQueryResponse>;
 
-pub type ActiveGrantsByDelegatorResponse = Option<GrantQueryResponse>;
+pub type ActiveGrantsByDelegatorResponse = Vec<GrantQueryResponse>;
+
+pub type SimulateExecuteBatchResponse = Vec<BatchSimulation>;
 
+#[grant_spec_queries(GrantSpecData)]
 #[cw_serde]
 #[derive(QueryResponses)]
 #[cfg_attr(feature = "interface", derive(cw_orch::QueryFns))]
@@ -20,7 +26,7 @@ pub enum QueryMsg {
     Version {},
 
     /// Returns the grant information for the given delegator.
-    /// Will either return the active grant info or nothing if there is no active grant.
+    /// Will return a list of all the delegator's active grants, one per grantee.
     #[returns(ActiveGrantsByDelegatorResponse)]
     ActiveGrantsByDelegator(String),
 
@@ -33,28 +39,60 @@ pub enum QueryMsg {
     #[returns(SimulateExecuteResponse)]
     SimulateExecute(ExecuteSettings),
 
-    #[returns(Vec<GrantRequirement>)]
-    GrantSpec {
-        expiration: Timestamp,
-        granter: Addr,
-        grantee: Addr,
-        max_fee_percentage: Decimal,
-        taxation_addr: Addr,
+    /// Returns the simulation of each execution in the batch sent by the sender or the reason it would be skipped
+    #[returns(SimulateExecuteBatchResponse)]
+    SimulateExecuteBatch {
+        sender: String,
+        batch: Vec<ExecuteSettings>,
     },
+
+    /// Returns the running totals of the rewards withdrawn for the given delegator across all of their grants.
+    #[returns(RewardTotals)]
+    RewardTotalsByDelegator(String),
+
+    /// Returns the running totals of the rewards from the executions the given taxation address received a share of.
+    /// The taxation rewards are only the ones sent to that address.
+    #[returns(RewardTotals)]
+    RewardTotalsByTaxationAddress(String),
+
+    /// Returns the running totals of the rewards withdrawn by the given grantee across all of their grants.
+    #[returns(RewardTotals)]
+    RewardTotalsByGrantee(String),
 }
 
 #[cw_serde]
 pub struct GrantSpecData {
-    pub max_fee_percentage: Decimal,
-    pub taxation_addr: Addr,
+    pub taxation_recipients: Vec<TaxationRecipient>,
+    pub auto_compound: Option<CompoundValidatorPolicy>,
+    pub reward_thresholds: Option<RewardThresholds>,
+    pub min_interval: Option<u64>,
 }
 
 #[cw_serde]
 pub struct SimulateExecuteResponse {
+    /// validators whose rewards would be claimed
+    pub claimed_validators: Vec<String>,
     /// rewards that the granter will receive
     pub delegator_rewards: Vec<Coin>,
-    /// rewards that the taxation address will receive
+    /// rewards that all of the taxation recipients will receive combined
     pub taxation_address_rewards: Vec<Coin>,
+    /// rewards that each of the taxation recipients will receive
+    pub recipient_rewards: Vec<RecipientRewards>,
+}
+
+#[cw_serde]
+pub struct BatchSimulation {
+    pub delegator: String,
+    /// the simulated execution, none if the delegator would be skipped
+    pub simulation: Option<SimulateExecuteResponse>,
+    /// the reason the delegator would be skipped
+    pub error: Option<String>,
+}
+
+#[cw_serde]
+pub struct RecipientRewards {
+    pub address: String,
+    pub rewards: Vec<Coin>,
 }
 
 #[cw_serde]
@@ -62,6 +100,21 @@ pub struct GrantQueryResponse {
     pub delegator_addr: Addr,
     /// grant settings
     pub allowed_withdrawls: AllowedWithdrawlSettings,
+    /// earliest time the grant can be executed again, none if it has no min interval or was never executed
+    pub next_eligible_execution: Option<Timestamp>,
+}
+
+#[cw_serde]
+#[derive(Default)]
+pub struct RewardTotals {
+    /// number of executions that have been counted
+    pub executions: u64,
+    /// rewards that went to the delegators
+    pub delegator_rewards: Vec<Coin>,
+    /// rewards that went to the taxation recipients
+    pub taxation_rewards: Vec<Coin>,
+    /// block time of the latest execution that was counted
+    pub last_execution: Option<Timestamp>,
 }
 
 #[cw_serde]
@@ -70,38 +123,91 @@ pub struct VersionResponse {
 }
 
 #[cw_serde]
+#[derive(MessageKeys)]
 #[cfg_attr(feature = "interface", derive(cw_orch::ExecuteFns))]
 pub enum ExecuteMsg {
     /// Creates a new grant that allows portions of one's staking rewards to be claimed by other addresses
     Grant(AllowedWithdrawlSettings),
 
-    /// Revokes an existing grant so that it can no longer be used
-    Revoke(),
+    /// Revokes the existing grant to the given grantee so that it can no longer be used.
+    /// If no grantee is specified all of the sender's grants are revoked
+    Revoke(Option<String>),
 
     /// Withdraws a user's rewards while sending the granted address a, specified, portion of the rewards
     Execute(ExecuteSettings),
 
+    /// Executes the grants of many delegators at once. A delegator whose execution fails is skipped
+    /// and reported in the `skipped` attributes instead of failing the whole batch
+    ExecuteBatch(Vec<ExecuteSettings>),
+
+    /// Sends the messages of one delegator's batch execution and records it. Only the contract itself
+    /// can call it, so that a failing message reverts just that delegator's execution
+    ExecuteBatchEntry { reply_id: u64, msgs: Vec<CosmosMsg> },
+
     /// Prunes expired grants from state
     /// This function should be called periodically to clean up free up contract space and
     PruneExpiredGrants(),
+
+    /// Moves over the legacy grants that the migration left behind, at most the given number of them
+    MigrateLegacyGrants(Option<u32>),
 }
 
 #[cw_serde]
 pub struct ExecuteSettings {
     /// originating delegator address to withdraw the rewards for
     pub delegator: String,
-    /// the percentage of rewards to be shared. if none is specified, the max is used
+    /// the percentage of rewards to be shared in total, split between the taxation recipients
+    /// by their max percentages. if none is specified, the max is used
     pub percentage: Option<Decimal>,
+    /// the grantee whose grant should be used. if none is specified, the sender's grant is used
+    /// or the delegator's only active grant if the sender is the delegator
+    pub grantee: Option<String>,
+    /// minimum rewards for the execution, these can only raise the grant's own thresholds
+    pub reward_thresholds: Option<RewardThresholds>,
 }
 
 #[cw_serde]
 pub struct AllowedWithdrawlSettings {
     /// the address that is allowed to execute the withdraw function
     pub grantee: String,
+    /// addresses to withdraw portions of the rewards to, their max percentages can add up to at most 100%
+    pub taxation_recipients: Vec<TaxationRecipient>,
+    /// if set, the delegator's share of the rewards is restaked to the validators picked by the policy
+    pub auto_compound: Option<CompoundValidatorPolicy>,
+    /// minimum rewards for an execution to go ahead
+    pub reward_thresholds: Option<RewardThresholds>,
+    /// minimum number of seconds between executions of the grant
+    pub min_interval: Option<u64>,
+    /// expiration date of the grant as a unix timestamp
+    pub expiration: Timestamp,
+}
+
+#[cw_serde]
+pub struct TaxationRecipient {
     /// address to withdraw the given percentage of rewards to
-    pub taxation_address: String,
+    pub address: String,
     /// percentage of rewards that can be withdrawn to the given address
     pub max_fee_percentage: Decimal,
-    /// expiration date of the grant as a unix timestamp
-    pub expiration: Timestamp,
+}
+
+/// How the delegator's share of the staking rewards gets split between validators when it is restaked
+#[cw_serde]
+pub enum CompoundValidatorPolicy {
+    /// restake to the validators the rewards came from, in proportion to the rewards from each of them
+    SameValidators,
+    /// restake everything to the given validator
+    FixedValidator(String),
+    /// restake to the listed validators in proportion to the rewards from each of them,
+    /// or evenly if none of the rewards came from the listed validators
+    AllowList(Vec<String>),
+}
+
+/// Minimum amounts of rewards that are worth claiming, denoms that aren't listed have no minimum
+#[cw_serde]
+#[derive(Default)]
+pub struct RewardThresholds {
+    /// the least of a denom that has to be claimed in total. at least one denom has to reach its minimum
+    pub min_total_rewards: Vec<Coin>,
+    /// validators that don't have at least one denom reaching its minimum are not claimed from
+    pub min_validator_rewards: Vec<Coin>,
 }
@@ -2,20 +2,21 @@ use std::io::Cursor;
 
 use crate::{
     helpers::{dec_coin_to_coin, filter_empty_coins, sum_coins},
-    msg::{GrantQueryResponse, VersionResponse},
-    state::GRANTS,
+    msg::{AllowedWithdrawlSettings, GrantQueryResponse, VersionResponse},
+    state::{GRANTS, LAST_EXECUTIONS},
 };
 use authzpp_utils::helpers::Expirable;
 use cosmos_sdk_proto::{
     cosmos::distribution::v1beta1::{
         DelegationDelegatorReward, QueryDelegationTotalRewardsRequest,
-        QueryDelegationTotalRewardsResponse,
+        QueryDelegationTotalRewardsResponse, QueryDelegatorWithdrawAddressRequest,
+        QueryDelegatorWithdrawAddressResponse,
     },
     traits::Message,
 };
 use cosmwasm_std::{
     Addr, Binary, BlockInfo, Coin, FullDelegation, Order, QuerierWrapper, QueryRequest, StdResult,
-    Storage,
+    Storage, Timestamp,
 };
 
 use crate::ContractError;
@@ -171,23 +172,124 @@ pub fn query_pending_rewards(
     }
 }
 
-/// search for and return the grant settings for an abitrary granter
-pub fn query_active_grants_by_delegator(
+/// Queries the address that the delegator's staking rewards are currently withdrawn to
+pub fn query_delegator_withdraw_address(
+    querier: &QuerierWrapper,
+    delegator_addr: &Addr,
+) -> Result<Addr, ContractError> {
+    let bin = QueryDelegatorWithdrawAddressRequest {
+        delegator_address: delegator_addr.to_string(),
+    }
+    .encode_to_vec();
+
+    let query = QueryRequest::Stargate {
+        path: "/cosmos.distribution.v1beta1.Query/DelegatorWithdrawAddress".to_string(),
+        data: Binary::from(bin),
+    };
+
+    let bin: Binary = querier.query(&query)?;
+    let QueryDelegatorWithdrawAddressResponse { withdraw_address } =
+        QueryDelegatorWithdrawAddressResponse::decode(&mut Cursor::new(bin.to_vec()))
+            .map_err(ContractError::Decode)?;
+
+    Ok(Addr::unchecked(withdraw_address))
+}
+
+/// returns the earliest time the grant can be executed again based on its min interval and last execution
+pub fn query_next_eligible_execution(
+    storage: &dyn Storage,
+    delegator_addr: &Addr,
+    grantee_addr: &Addr,
+    allowed_withdrawls: &AllowedWithdrawlSettings,
+) -> StdResult<Option<Timestamp>> {
+    let Some(min_interval) = allowed_withdrawls.min_interval else {
+        return Ok(None);
+    };
+
+    Ok(LAST_EXECUTIONS
+        .may_load(storage, (delegator_addr, grantee_addr))?
+        .map(|last_execution| last_execution.plus_seconds(min_interval)))
+}
+
+/// search for and return the active grant settings for an abitrary granter and grantee
+pub fn query_active_grant(
     storage: &dyn Storage,
     block: &BlockInfo,
     delegator_addr: &Addr,
+    grantee_addr: &Addr,
 ) -> StdResult<Option<GrantQueryResponse>> {
-    // get the grant for the delegator from state
-    let grant_settings = GRANTS.load(storage, delegator_addr)?;
+    // get the grant for the delegator and grantee from state
+    let grant_settings = GRANTS.may_load(storage, (delegator_addr, grantee_addr))?;
 
-    Ok(Option::from(grant_settings)
+    grant_settings
         .filter(|grant|
             // validate that the grant is still active and not expired
             grant.is_not_expired(block))
-        .map(|allowed_withdrawls| GrantQueryResponse {
-            delegator_addr: delegator_addr.clone(),
-            allowed_withdrawls,
-        }))
+        .map(|allowed_withdrawls| {
+            Ok(GrantQueryResponse {
+                next_eligible_execution: query_next_eligible_execution(
+                    storage,
+                    delegator_addr,
+                    grantee_addr,
+                    &allowed_withdrawls,
+                )?,
+                delegator_addr: delegator_addr.clone(),
+                allowed_withdrawls,
+            })
+        })
+        .transpose()
+}
+
+/// search for and return all of the active grant settings for an abitrary granter
+pub fn query_active_grants_by_delegator(
+    storage: &dyn Storage,
+    block: &BlockInfo,
+    delegator_addr: &Addr,
+) -> StdResult<Vec<GrantQueryResponse>> {
+    GRANTS
+        .prefix(delegator_addr)
+        .range(storage, None, None, Order::Ascending)
+        .filter_map(|item| match item {
+            // only keep the grants that are still active and not expired
+            Ok((_, allowed_withdrawls)) if allowed_withdrawls.is_expired(block) => None,
+            Ok((grantee_addr, allowed_withdrawls)) => Some(
+                query_next_eligible_execution(
+                    storage,
+                    delegator_addr,
+                    &grantee_addr,
+                    &allowed_withdrawls,
+                )
+                .map(|next_eligible_execution| GrantQueryResponse {
+                    delegator_addr: delegator_addr.clone(),
+                    allowed_withdrawls,
+                    next_eligible_execution,
+                }),
+            ),
+            Err(err) => Some(Err(err)),
+        })
+        .collect()
+}
+
+/// finds the grant that an execution for the delegator should use.
+/// if no grantee is given the delegator must only have a single active grant
+pub fn resolve_active_grant(
+    storage: &dyn Storage,
+    block: &BlockInfo,
+    delegator_addr: &Addr,
+    grantee_addr: Option<&Addr>,
+) -> Result<GrantQueryResponse, ContractError> {
+    let grant = match grantee_addr {
+        Some(grantee_addr) => query_active_grant(storage, block, delegator_addr, grantee_addr)?,
+        None => {
+            let mut grants = query_active_grants_by_delegator(storage, block, delegator_addr)?;
+            if grants.len() > 1 {
+                return Err(ContractError::GranteeRequired(delegator_addr.to_string()));
+            }
+            grants.pop()
+        }
+    };
+
+    grant.ok_or_else(|| ContractError::NoActiveGrant(delegator_addr.to_string()))
 }
 
 /// returns all of the grant settings that are for grants to the given grantee
@@ -201,12 +303,18 @@ pub fn query_active_grants_by_grantee(
         .range(storage, None, None, Order::Ascending)
         // filter out the grants that are not for the requested grantee
         .filter_map(|item| {
-            if let Ok((granter, allowed_withdrawls)) = item {
+            if let Ok(((granter, grant_grantee), allowed_withdrawls)) = item {
                 // also ensure that the grant is active and unexpired
-                if allowed_withdrawls.is_not_expired(block)
-                    && allowed_withdrawls.grantee.eq(&grantee)
-                {
+                if allowed_withdrawls.is_not_expired(block) && grant_grantee.eq(&grantee) {
                     return Some(GrantQueryResponse {
+                        next_eligible_execution: query_next_eligible_execution(
+                            storage,
+                            &granter,
+                            &grant_grantee,
+                            &allowed_withdrawls,
+                        )
+                        .ok()
+                        .flatten(),
                         delegator_addr: granter,
                         allowed_withdrawls,
                     });
@@ -1,5 +1,66 @@
-use crate::msg::AllowedWithdrawlSettings;
-use cosmwasm_std::Addr;
+use crate::msg::{AllowedWithdrawlSettings, RewardTotals, TaxationRecipient};
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{Addr, Coin, Decimal, Timestamp};
 use cw_storage_plus::Map;
 
-pub const GRANTS: Map<&Addr, AllowedWithdrawlSettings> = Map::new("grants");
+// map from Delegator & Grantee addresses to the grant settings
+pub const GRANTS: Map<(&Addr, &Addr), AllowedWithdrawlSettings> = Map::new("delegator_grants");
+
+// map from Delegator & Grantee addresses to the block time the grant was last executed at
+pub const LAST_EXECUTIONS: Map<(&Addr, &Addr), Timestamp> = Map::new("last_executions");
+
+// running totals of the rewards withdrawn through the contract by delegator, taxation address and grantee
+pub const DELEGATOR_TOTALS: Map<&Addr, RewardTotals> = Map::new("delegator_totals");
+pub const TAXATION_ADDRESS_TOTALS: Map<&Addr, RewardTotals> = Map::new("taxation_address_totals");
+pub const GRANTEE_TOTALS: Map<&Addr, RewardTotals> = Map::new("grantee_totals");
+
+// executions from a batch keyed by their reply id, they are only recorded once their messages went through.
+// the entry is removed by either the batch entry call or the reply to it failing
+pub const PENDING_EXECUTIONS: Map<u64, ExecutionRecord> = Map::new("pending_executions");
+
+// grants from before a delegator could have more than one grant, keyed by the delegator only.
+// these only remain in state until the contract is migrated
+pub const LEGACY_GRANTS: Map<&Addr, LegacyAllowedWithdrawlSettings> = Map::new("grants");
+
+/// what an execution adds to the grant's last execution and the running totals
+#[cw_serde]
+pub struct ExecutionRecord {
+    pub delegator: Addr,
+    pub grantee: Addr,
+    pub delegator_rewards: Vec<Coin>,
+    pub taxation_address_rewards: Vec<Coin>,
+    /// the taxation recipients that received a share of the rewards
+    pub recipient_rewards: Vec<(Addr, Vec<Coin>)>,
+}
+
+/// grant settings from before a grant could have more than one taxation recipient
+#[cw_serde]
+pub struct LegacyAllowedWithdrawlSettings {
+    pub grantee: String,
+    pub taxation_address: String,
+    pub max_fee_percentage: Decimal,
+    pub expiration: Timestamp,
+}
+
+impl From<LegacyAllowedWithdrawlSettings> for AllowedWithdrawlSettings {
+    fn from(
+        LegacyAllowedWithdrawlSettings {
+            grantee,
+            taxation_address,
+            max_fee_percentage,
+            expiration,
+        }: LegacyAllowedWithdrawlSettings,
+    ) -> Self {
+        AllowedWithdrawlSettings {
+            grantee,
+            taxation_recipients: vec![TaxationRecipient {
+                address: taxation_address,
+                max_fee_percentage,
+            }],
+            auto_compound: None,
+            reward_thresholds: None,
+            min_interval: None,
+            expiration,
+        }
+    }
+}
@@ -2,23 +2,49 @@ use authzpp_utils::msg_gen::exec_msg;
 use cosmos_sdk_proto::{
     cosmos::{
         authz::v1beta1::MsgExec,
-        base::v1beta1::DecCoin,
+        base::v1beta1::{Coin as ProtoCoin, DecCoin},
         distribution::v1beta1::{
             DelegationDelegatorReward, MsgSetWithdrawAddress, MsgWithdrawDelegatorReward,
-            QueryDelegationTotalRewardsResponse,
+            QueryDelegationTotalRewardsResponse, QueryDelegatorWithdrawAddressRequest,
+            QueryDelegatorWithdrawAddressResponse,
         },
+        staking::v1beta1::MsgDelegate,
     },
     traits::{Message, MessageExt},
 };
-use cosmwasm_std::{coins, Addr, BankMsg, Binary, Coin, CosmosMsg, Decimal, Timestamp};
+use cosmwasm_std::{
+    coins, from_binary, from_slice,
+    testing::{mock_dependencies, mock_env, mock_info, MockApi, MockQuerier, MockStorage},
+    to_binary, Addr, BankMsg, Binary, Coin, ContractResult, CosmosMsg, Decimal, Deps, Empty,
+    FullDelegation, OwnedDeps, Querier, QuerierResult, QueryRequest, Reply, ReplyOn, SubMsgResult,
+    SystemResult, Timestamp, Validator, WasmMsg,
+};
+use cw_grant_spec::grants::{GrantExpiration, GrantRequirement, RevokeRequirement};
+use std::{collections::HashMap, marker::PhantomData};
 
 use crate::{
+    contract::{execute, migrate, query, reply},
     execute::{
         create_withdraw_rewards_exec_msg, generate_reward_withdrawl_msgs, RewardExecutionMsgs,
     },
-    helpers::{dec_coin_to_coin, partition_coins_by_percentage, split_rewards, sum_coins},
-    msg::{AllowedWithdrawlSettings, SimulateExecuteResponse},
-    queries::{process_delegation_total_rewards_response, AllPendingRewards, PendingReward},
+    helpers::{
+        add_to_reward_totals, apply_reward_thresholds, combine_reward_thresholds,
+        compound_delegations, dec_coin_to_coin, partition_coins_by_percentage, split_rewards,
+        sum_coins, validate_taxation_recipients, RewardSplit,
+    },
+    msg::{
+        AllowedWithdrawlSettings, CompoundValidatorPolicy, ExecuteMsg, ExecuteSettings,
+        GrantSpecData, MigrateMsg, QueryMsg, RecipientRewards, RewardThresholds, RewardTotals,
+        SimulateExecuteBatchResponse, TaxationRecipient,
+    },
+    queries::{
+        process_delegation_total_rewards_response, query_active_grants_by_delegator,
+        resolve_active_grant, AllPendingRewards, PendingReward,
+    },
+    state::{
+        LegacyAllowedWithdrawlSettings, GRANTS, LAST_EXECUTIONS, LEGACY_GRANTS, PENDING_EXECUTIONS,
+    },
+    ContractError,
 };
 
 // unit tests for the sum_coins helper function
@@ -149,6 +175,13 @@ fn partition_coins() {
     assert_eq!(coins_to_remain, expected_to_remain);
 }
 
+fn taxation_recipient(address: &str, max_fee_percent: u64) -> TaxationRecipient {
+    TaxationRecipient {
+        address: address.to_string(),
+        max_fee_percentage: Decimal::percent(max_fee_percent),
+    }
+}
+
 #[test]
 pub fn split_rewards_test() {
     let rewards = vec![
@@ -161,8 +194,12 @@ pub fn split_rewards_test() {
             amount: 200u128.into(),
         },
     ];
-    let sim_rewards = split_rewards(rewards, Decimal::percent(5), &Some(Decimal::percent(5)));
-    let expected_sim_rewards = SimulateExecuteResponse {
+    let sim_rewards = split_rewards(
+        rewards,
+        &[taxation_recipient("taxman", 5)],
+        &Some(Decimal::percent(5)),
+    );
+    let expected_sim_rewards = RewardSplit {
         delegator_rewards: vec![
             Coin {
                 denom: "ujuno".to_string(),
@@ -183,6 +220,19 @@ pub fn split_rewards_test() {
                 amount: 10u128.into(),
             },
         ],
+        recipient_rewards: vec![RecipientRewards {
+            address: "taxman".to_string(),
+            rewards: vec![
+                Coin {
+                    denom: "ujuno".to_string(),
+                    amount: 5u128.into(),
+                },
+                Coin {
+                    denom: "uosmo".to_string(),
+                    amount: 10u128.into(),
+                },
+            ],
+        }],
     };
     assert_eq!(sim_rewards, expected_sim_rewards);
 
@@ -193,10 +243,10 @@ pub fn split_rewards_test() {
     }];
     let sim_rewards = split_rewards(
         rewards.clone(),
-        Decimal::percent(33),
+        &[taxation_recipient("taxman", 33)],
         &Some(Decimal::percent(33)),
     );
-    let expected_sim_rewards = SimulateExecuteResponse {
+    let expected_sim_rewards = RewardSplit {
         delegator_rewards: vec![Coin {
             denom: "ujuno".to_string(),
             amount: 7u128.into(),
@@ -205,12 +255,19 @@ pub fn split_rewards_test() {
             denom: "ujuno".to_string(),
             amount: 3u128.into(),
         }],
+        recipient_rewards: vec![RecipientRewards {
+            address: "taxman".to_string(),
+            rewards: vec![Coin {
+                denom: "ujuno".to_string(),
+                amount: 3u128.into(),
+            }],
+        }],
     };
     assert_eq!(sim_rewards, expected_sim_rewards);
 
     // test falling back to the max percentage //
-    let sim_rewards = split_rewards(rewards.clone(), Decimal::percent(33), &None);
-    let expected_sim_rewards = SimulateExecuteResponse {
+    let sim_rewards = split_rewards(rewards.clone(), &[taxation_recipient("taxman", 33)], &None);
+    let expected_sim_rewards = RewardSplit {
         delegator_rewards: vec![Coin {
             denom: "ujuno".to_string(),
             amount: 7u128.into(),
@@ -219,16 +276,23 @@ pub fn split_rewards_test() {
             denom: "ujuno".to_string(),
             amount: 3u128.into(),
         }],
+        recipient_rewards: vec![RecipientRewards {
+            address: "taxman".to_string(),
+            rewards: vec![Coin {
+                denom: "ujuno".to_string(),
+                amount: 3u128.into(),
+            }],
+        }],
     };
     assert_eq!(sim_rewards, expected_sim_rewards);
 
     // test using a percentage that's less than the maxiumum
     let sim_rewards = split_rewards(
         rewards.clone(),
-        Decimal::percent(33),
+        &[taxation_recipient("taxman", 33)],
         &Some(Decimal::percent(10)),
     );
-    let expected_sim_rewards = SimulateExecuteResponse {
+    let expected_sim_rewards = RewardSplit {
         delegator_rewards: vec![Coin {
             denom: "ujuno".to_string(),
             amount: 9u128.into(),
@@ -237,12 +301,23 @@ pub fn split_rewards_test() {
             denom: "ujuno".to_string(),
             amount: 1u128.into(),
         }],
+        recipient_rewards: vec![RecipientRewards {
+            address: "taxman".to_string(),
+            rewards: vec![Coin {
+                denom: "ujuno".to_string(),
+                amount: 1u128.into(),
+            }],
+        }],
     };
     assert_eq!(sim_rewards, expected_sim_rewards);
 
     // test using a percentage that's greater than the maxiumum
-    let sim_rewards = split_rewards(rewards, Decimal::percent(33), &Some(Decimal::percent(50)));
-    let expected_sim_rewards = SimulateExecuteResponse {
+    let sim_rewards = split_rewards(
+        rewards,
+        &[taxation_recipient("taxman", 33)],
+        &Some(Decimal::percent(50)),
+    );
+    let expected_sim_rewards = RewardSplit {
         delegator_rewards: vec![Coin {
             denom: "ujuno".to_string(),
             amount: 7u128.into(),
@@ -251,10 +326,78 @@ pub fn split_rewards_test() {
             denom: "ujuno".to_string(),
             amount: 3u128.into(),
         }],
+        recipient_rewards: vec![RecipientRewards {
+            address: "taxman".to_string(),
+            rewards: vec![Coin {
+                denom: "ujuno".to_string(),
+                amount: 3u128.into(),
+            }],
+        }],
     };
     assert_eq!(sim_rewards, expected_sim_rewards);
 }
 
+#[test]
+fn split_rewards_multiple_recipients() {
+    let recipients = [
+        taxation_recipient("treasury", 5),
+        taxation_recipient("dev_fund", 2),
+        taxation_recipient("bot", 1),
+    ];
+
+    // each recipient gets their max percentage when none is requested
+    let sim_rewards = split_rewards(coins(1_000, "ujuno"), &recipients, &None);
+    assert_eq!(
+        sim_rewards,
+        RewardSplit {
+            delegator_rewards: coins(920, "ujuno"),
+            taxation_address_rewards: coins(80, "ujuno"),
+            recipient_rewards: vec![
+                RecipientRewards {
+                    address: "treasury".to_string(),
+                    rewards: coins(50, "ujuno"),
+                },
+                RecipientRewards {
+                    address: "dev_fund".to_string(),
+                    rewards: coins(20, "ujuno"),
+                },
+                RecipientRewards {
+                    address: "bot".to_string(),
+                    rewards: coins(10, "ujuno"),
+                },
+            ],
+        }
+    );
+
+    // a lower percentage is shared in proportion to the recipients' max percentages
+    let sim_rewards = split_rewards(
+        coins(1_000, "ujuno"),
+        &recipients,
+        &Some(Decimal::percent(4)),
+    );
+    assert_eq!(sim_rewards.delegator_rewards, coins(960, "ujuno"));
+    assert_eq!(
+        sim_rewards
+            .recipient_rewards
+            .into_iter()
+            .map(|RecipientRewards { rewards, .. }| rewards)
+            .collect::<Vec<_>>(),
+        vec![coins(25, "ujuno"), coins(10, "ujuno"), coins(5, "ujuno")]
+    );
+
+    // the max percentages can't add up to more than 100%
+    assert!(matches!(
+        validate_taxation_recipients(
+            &MockApi::default(),
+            &[
+                taxation_recipient("treasury", 60),
+                taxation_recipient("dev_fund", 50)
+            ]
+        ),
+        Err(ContractError::MaxFeePercentageExceeded(_))
+    ));
+}
+
 #[test]
 fn withdraw_to_contract_msgs() {
     let contract_addr = Addr::unchecked("contract");
@@ -265,6 +408,7 @@ fn withdraw_to_contract_msgs() {
     let generated_msg = create_withdraw_rewards_exec_msg(
         &granter_addr,
         &contract_addr,
+        &granter_addr,
         &[PendingReward {
             amount: vec![Coin {
                 denom: "ujuno".to_string(),
@@ -334,14 +478,21 @@ fn gen_reward_withdrawl_msgs() {
         },
         AllowedWithdrawlSettings {
             grantee: grantee_addr.to_string(),
-            taxation_address: take_rate_addr.to_string(),
-            max_fee_percentage: Decimal::percent(15),
+            taxation_recipients: vec![TaxationRecipient {
+                address: take_rate_addr.to_string(),
+                max_fee_percentage: Decimal::percent(15),
+            }],
+            auto_compound: None,
+            reward_thresholds: None,
+            min_interval: None,
             expiration: Timestamp::from_seconds(1000),
         },
         &grantee_addr,
         &contract_addr,
         &granter_addr,
+        &granter_addr,
         None,
+        "ujuno",
     )
     .unwrap();
 
@@ -417,14 +568,21 @@ fn gen_reward_withdrawl_msgs_zero_fee() {
         },
         AllowedWithdrawlSettings {
             grantee: grantee_addr.to_string(),
-            taxation_address: take_rate_addr.to_string(),
-            max_fee_percentage: Decimal::percent(15),
+            taxation_recipients: vec![TaxationRecipient {
+                address: take_rate_addr.to_string(),
+                max_fee_percentage: Decimal::percent(15),
+            }],
+            auto_compound: None,
+            reward_thresholds: None,
+            min_interval: None,
             expiration: Timestamp::from_seconds(1000),
         },
         &grantee_addr,
         &contract_addr,
         &granter_addr,
+        &granter_addr,
         Some(Decimal::zero()),
+        "ujuno",
     )
     .unwrap();
 
@@ -473,14 +631,21 @@ pub fn generate_rewards_msgs_without_rewards() {
         },
         AllowedWithdrawlSettings {
             grantee: grantee_addr.to_string(),
-            taxation_address: take_rate_addr.to_string(),
-            max_fee_percentage: Decimal::percent(15),
+            taxation_recipients: vec![TaxationRecipient {
+                address: take_rate_addr.to_string(),
+                max_fee_percentage: Decimal::percent(15),
+            }],
+            auto_compound: None,
+            reward_thresholds: None,
+            min_interval: None,
             expiration: Timestamp::from_seconds(1000),
         },
         &grantee_addr,
         &contract_addr,
         &granter_addr,
+        &granter_addr,
         None,
+        "ujuno",
     )
     .unwrap();
 
@@ -524,6 +689,271 @@ pub fn generate_rewards_msgs_without_rewards() {
     assert_eq!(generated_msgs, expected_msgs);
 }
 
+#[test]
+fn compound_delegations_by_policy() {
+    let pending_rewards = vec![
+        PendingReward {
+            validator: "val1".to_string(),
+            amount: vec![
+                Coin {
+                    denom: "ujuno".to_string(),
+                    amount: 60u128.into(),
+                },
+                Coin {
+                    denom: "uatom".to_string(),
+                    amount: 5u128.into(),
+                },
+            ],
+        },
+        PendingReward {
+            validator: "val2".to_string(),
+            amount: coins(40, "ujuno"),
+        },
+    ];
+    let delegation = |validator: &str, amount: u128| {
+        (
+            validator.to_string(),
+            Coin {
+                denom: "ujuno".to_string(),
+                amount: amount.into(),
+            },
+        )
+    };
+    let restake = Coin {
+        denom: "ujuno".to_string(),
+        amount: 50u128.into(),
+    };
+
+    // restake in proportion to the rewards claimed from each validator
+    assert_eq!(
+        compound_delegations(
+            &CompoundValidatorPolicy::SameValidators,
+            &pending_rewards,
+            restake.clone()
+        ),
+        vec![delegation("val1", 30), delegation("val2", 20)]
+    );
+
+    // the last validator picks up what is lost to rounding
+    assert_eq!(
+        compound_delegations(
+            &CompoundValidatorPolicy::SameValidators,
+            &pending_rewards,
+            Coin {
+                denom: "ujuno".to_string(),
+                amount: 7u128.into(),
+            }
+        ),
+        vec![delegation("val1", 4), delegation("val2", 3)]
+    );
+
+    assert_eq!(
+        compound_delegations(
+            &CompoundValidatorPolicy::FixedValidator("val3".to_string()),
+            &pending_rewards,
+            restake.clone()
+        ),
+        vec![delegation("val3", 50)]
+    );
+
+    // listed validators that had no rewards get nothing
+    assert_eq!(
+        compound_delegations(
+            &CompoundValidatorPolicy::AllowList(vec!["val2".to_string(), "val3".to_string()]),
+            &pending_rewards,
+            restake.clone()
+        ),
+        vec![delegation("val2", 50)]
+    );
+
+    // unless none of the listed validators had rewards
+    assert_eq!(
+        compound_delegations(
+            &CompoundValidatorPolicy::AllowList(vec!["val3".to_string(), "val4".to_string()]),
+            &pending_rewards,
+            restake
+        ),
+        vec![delegation("val3", 25), delegation("val4", 25)]
+    );
+}
+
+#[test]
+fn gen_reward_withdrawl_msgs_auto_compound() {
+    let contract_addr = Addr::unchecked("contract");
+    let grantee_addr = Addr::unchecked("grantee");
+    let granter_addr = Addr::unchecked("granter");
+    let validator1 = "validator1".to_string();
+
+    let generated_msgs = generate_reward_withdrawl_msgs(
+        AllPendingRewards {
+            rewards: vec![PendingReward {
+                amount: coins(100, "ujuno"),
+                validator: validator1.to_string(),
+            }],
+            total: coins(100, "ujuno"),
+        },
+        AllowedWithdrawlSettings {
+            grantee: grantee_addr.to_string(),
+            taxation_recipients: vec![taxation_recipient("taxman", 10)],
+            auto_compound: Some(CompoundValidatorPolicy::SameValidators),
+            reward_thresholds: None,
+            min_interval: None,
+            expiration: Timestamp::from_seconds(1000),
+        },
+        &grantee_addr,
+        &contract_addr,
+        &granter_addr,
+        &granter_addr,
+        None,
+        "ujuno",
+    )
+    .unwrap();
+
+    // the granter's share is sent back to them and then restaked
+    assert_eq!(generated_msgs.msgs.len(), 4);
+    assert_eq!(
+        generated_msgs.msgs[2],
+        CosmosMsg::Bank(BankMsg::Send {
+            to_address: granter_addr.to_string(),
+            amount: coins(90, "ujuno"),
+        })
+    );
+    assert_eq!(
+        generated_msgs.msgs[3],
+        exec_msg(
+            &contract_addr,
+            vec![MsgDelegate {
+                delegator_address: granter_addr.to_string(),
+                validator_address: validator1,
+                amount: Some(ProtoCoin {
+                    denom: "ujuno".to_string(),
+                    amount: "90".to_string(),
+                }),
+            }
+            .to_any()
+            .unwrap()],
+        )
+        .unwrap()
+    );
+}
+
+#[test]
+fn gen_reward_withdrawl_msgs_custom_withdraw_address() {
+    let contract_addr = Addr::unchecked("contract");
+    let grantee_addr = Addr::unchecked("grantee");
+    let granter_addr = Addr::unchecked("granter");
+    let cold_wallet_addr = Addr::unchecked("cold_wallet");
+    let validator1 = "validator1".to_string();
+
+    let generated_msgs = generate_reward_withdrawl_msgs(
+        AllPendingRewards {
+            rewards: vec![PendingReward {
+                amount: coins(100, "ujuno"),
+                validator: validator1.to_string(),
+            }],
+            total: coins(100, "ujuno"),
+        },
+        AllowedWithdrawlSettings {
+            grantee: grantee_addr.to_string(),
+            taxation_recipients: vec![taxation_recipient("taxman", 10)],
+            auto_compound: None,
+            reward_thresholds: None,
+            min_interval: None,
+            expiration: Timestamp::from_seconds(1000),
+        },
+        &grantee_addr,
+        &contract_addr,
+        &granter_addr,
+        &cold_wallet_addr,
+        None,
+        "ujuno",
+    )
+    .unwrap();
+
+    // the withdraw address is restored and the granter's share is sent there
+    assert_eq!(
+        generated_msgs.msgs,
+        vec![
+            create_withdraw_rewards_exec_msg(
+                &granter_addr,
+                &contract_addr,
+                &cold_wallet_addr,
+                &[PendingReward {
+                    amount: coins(100, "ujuno"),
+                    validator: validator1,
+                }],
+            )
+            .unwrap(),
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "taxman".to_string(),
+                amount: coins(10, "ujuno"),
+            }),
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: cold_wallet_addr.to_string(),
+                amount: coins(90, "ujuno"),
+            }),
+        ]
+    );
+
+    // when restaking only the restaked part goes to the granter, the rest still goes to the withdraw address
+    let generated_msgs = generate_reward_withdrawl_msgs(
+        AllPendingRewards {
+            rewards: vec![PendingReward {
+                amount: vec![
+                    Coin {
+                        denom: "uatom".to_string(),
+                        amount: 100u128.into(),
+                    },
+                    Coin {
+                        denom: "ujuno".to_string(),
+                        amount: 100u128.into(),
+                    },
+                ],
+                validator: "validator1".to_string(),
+            }],
+            total: vec![
+                Coin {
+                    denom: "uatom".to_string(),
+                    amount: 100u128.into(),
+                },
+                Coin {
+                    denom: "ujuno".to_string(),
+                    amount: 100u128.into(),
+                },
+            ],
+        },
+        AllowedWithdrawlSettings {
+            grantee: grantee_addr.to_string(),
+            taxation_recipients: vec![taxation_recipient("taxman", 10)],
+            auto_compound: Some(CompoundValidatorPolicy::SameValidators),
+            reward_thresholds: None,
+            min_interval: None,
+            expiration: Timestamp::from_seconds(1000),
+        },
+        &grantee_addr,
+        &contract_addr,
+        &granter_addr,
+        &cold_wallet_addr,
+        None,
+        "ujuno",
+    )
+    .unwrap();
+
+    assert_eq!(
+        generated_msgs.msgs[2..4],
+        [
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: granter_addr.to_string(),
+                amount: coins(90, "ujuno"),
+            }),
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: cold_wallet_addr.to_string(),
+                amount: coins(90, "uatom"),
+            }),
+        ]
+    );
+}
+
 #[test]
 fn test_deccoin_to_coin_fn() {
     assert_eq!(
@@ -643,3 +1073,1073 @@ fn test_delegation_total_rewards_response() {
         expected_response
     );
 }
+
+fn withdrawl_settings(grantee: &str, max_fee_percent: u64) -> AllowedWithdrawlSettings {
+    AllowedWithdrawlSettings {
+        grantee: grantee.to_string(),
+        taxation_recipients: vec![taxation_recipient("taxman", max_fee_percent)],
+        auto_compound: None,
+        reward_thresholds: None,
+        min_interval: None,
+        expiration: Timestamp::from_seconds(4_102_444_800),
+    }
+}
+
+#[test]
+fn multiple_grants_per_delegator() {
+    let mut deps = mock_dependencies();
+    let env = mock_env();
+    let delegator = Addr::unchecked("delegator");
+
+    for (grantee, max_fee_percentage) in [("compounder", 5), ("collector", 10)] {
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(delegator.as_str(), &[]),
+            ExecuteMsg::Grant(withdrawl_settings(grantee, max_fee_percentage)),
+        )
+        .unwrap();
+    }
+
+    // the second grant should not overwrite the first
+    let grants =
+        query_active_grants_by_delegator(deps.as_ref().storage, &env.block, &delegator).unwrap();
+    assert_eq!(grants.len(), 2);
+
+    // with more than one grant the grantee needs to be specified
+    assert!(matches!(
+        resolve_active_grant(deps.as_ref().storage, &env.block, &delegator, None),
+        Err(ContractError::GranteeRequired(_))
+    ));
+    assert_eq!(
+        resolve_active_grant(
+            deps.as_ref().storage,
+            &env.block,
+            &delegator,
+            Some(&Addr::unchecked("collector"))
+        )
+        .unwrap()
+        .allowed_withdrawls,
+        withdrawl_settings("collector", 10)
+    );
+
+    // revoking a single grantee leaves the other grant in place
+    execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info(delegator.as_str(), &[]),
+        ExecuteMsg::Revoke(Some("collector".to_string())),
+    )
+    .unwrap();
+    assert_eq!(
+        resolve_active_grant(deps.as_ref().storage, &env.block, &delegator, None)
+            .unwrap()
+            .allowed_withdrawls,
+        withdrawl_settings("compounder", 5)
+    );
+
+    // revoking without a grantee removes the rest
+    execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info(delegator.as_str(), &[]),
+        ExecuteMsg::Revoke(None),
+    )
+    .unwrap();
+    assert!(
+        query_active_grants_by_delegator(deps.as_ref().storage, &env.block, &delegator)
+            .unwrap()
+            .is_empty()
+    );
+}
+
+#[test]
+fn revoke_spec_shared_grants() {
+    let mut deps = mock_dependencies();
+    let env = mock_env();
+    let delegator = Addr::unchecked("delegator");
+
+    for grantee in ["compounder", "collector"] {
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(delegator.as_str(), &[]),
+            ExecuteMsg::Grant(withdrawl_settings(grantee, 5)),
+        )
+        .unwrap();
+    }
+
+    let revoke_spec = |deps: Deps, grantee: &str| -> Vec<RevokeRequirement> {
+        from_binary(
+            &query(
+                deps,
+                mock_env(),
+                QueryMsg::RevokeSpec {
+                    granter: Addr::unchecked("delegator"),
+                    grantee: Addr::unchecked(grantee),
+                    grant_data: GrantSpecData {
+                        taxation_recipients: vec![taxation_recipient("taxman", 5)],
+                        auto_compound: None,
+                        reward_thresholds: None,
+                        min_interval: None,
+                    },
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap()
+    };
+
+    // the other grantee still needs the authz grants to the contract
+    assert_eq!(
+        revoke_spec(deps.as_ref(), "collector"),
+        vec![RevokeRequirement::ContractExec {
+            contract_addr: env.contract.address.clone(),
+            msg: to_binary(&ExecuteMsg::Revoke(Some("collector".to_string()))).unwrap(),
+            sender: delegator.clone(),
+        }]
+    );
+
+    execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info(delegator.as_str(), &[]),
+        ExecuteMsg::Revoke(Some("collector".to_string())),
+    )
+    .unwrap();
+
+    // the last grantee takes the authz grants with it
+    let revokes = revoke_spec(deps.as_ref(), "compounder");
+    assert_eq!(revokes.len(), 3);
+    assert!(revokes.contains(&RevokeRequirement::RevokeSpec {
+        grant_type: "/cosmos.distribution.v1beta1.MsgWithdrawDelegatorReward".to_string(),
+        granter: delegator.clone(),
+        grantee: env.contract.address.clone(),
+    }));
+    assert!(revokes.contains(&RevokeRequirement::RevokeSpec {
+        grant_type: "/cosmos.distribution.v1beta1.MsgSetWithdrawAddress".to_string(),
+        granter: delegator.clone(),
+        grantee: env.contract.address,
+    }));
+}
+
+#[test]
+fn grant_spec_shared_expiration() {
+    let mut deps = mock_dependencies();
+    let env = mock_env();
+    let delegator = Addr::unchecked("delegator");
+
+    execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info(delegator.as_str(), &[]),
+        ExecuteMsg::Grant(withdrawl_settings("compounder", 5)),
+    )
+    .unwrap();
+
+    let grant_spec = |deps: Deps, grantee: &str| -> Vec<GrantRequirement> {
+        from_binary(
+            &query(
+                deps,
+                mock_env(),
+                QueryMsg::GrantSpec {
+                    granter: Addr::unchecked("delegator"),
+                    grantee: Addr::unchecked(grantee),
+                    expiration: GrantExpiration::SecondsFromNow(86_400),
+                    grant_data: GrantSpecData {
+                        taxation_recipients: vec![taxation_recipient("taxman", 5)],
+                        auto_compound: None,
+                        reward_thresholds: None,
+                        min_interval: None,
+                    },
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap()
+    };
+
+    // a shorter grant to another grantee can't cut the shared authz grants short
+    let grants = grant_spec(deps.as_ref(), "collector");
+    assert_eq!(grants.len(), 3);
+    for grant in grants.iter() {
+        match grant {
+            GrantRequirement::GrantSpec { expiration, .. } => assert_eq!(
+                expiration,
+                &GrantExpiration::AtTime(Timestamp::from_seconds(4_102_444_800))
+            ),
+            GrantRequirement::ContractExec { msg, .. } => {
+                match from_binary::<ExecuteMsg>(msg).unwrap() {
+                    ExecuteMsg::Grant(settings) => {
+                        assert_eq!(settings.expiration, env.block.time.plus_seconds(86_400))
+                    }
+                    _ => panic!("expected the grant to the collector"),
+                }
+            }
+            _ => panic!("unexpected grant requirement"),
+        }
+    }
+
+    // the grantee's own grant is the one being replaced so it doesn't count
+    for grant in grant_spec(deps.as_ref(), "compounder").iter() {
+        if let GrantRequirement::GrantSpec { expiration, .. } = grant {
+            assert_eq!(
+                expiration,
+                &GrantExpiration::AtTime(env.block.time.plus_seconds(86_400))
+            );
+        }
+    }
+}
+
+#[test]
+fn migrate_legacy_grants() {
+    let mut deps = mock_dependencies();
+    let env = mock_env();
+    let delegators = [Addr::unchecked("delegator1"), Addr::unchecked("delegator2")];
+
+    // migrating a different contract or to an older version is rejected
+    cw2::set_contract_version(deps.as_mut().storage, "crates.io:other-contract", "0.1.0").unwrap();
+    let err = migrate(deps.as_mut(), env.clone(), MigrateMsg { limit: None }).unwrap_err();
+    assert!(matches!(err, ContractError::InvalidMigration { .. }));
+    cw2::set_contract_version(
+        deps.as_mut().storage,
+        "crates.io:authzpp-withdraw-rewards-tax-grant",
+        "99.0.0",
+    )
+    .unwrap();
+    let err = migrate(deps.as_mut(), env.clone(), MigrateMsg { limit: None }).unwrap_err();
+    assert!(matches!(err, ContractError::InvalidMigration { .. }));
+
+    cw2::set_contract_version(
+        deps.as_mut().storage,
+        "crates.io:authzpp-withdraw-rewards-tax-grant",
+        "0.1.0",
+    )
+    .unwrap();
+    for delegator in delegators.iter() {
+        LEGACY_GRANTS
+            .save(
+                deps.as_mut().storage,
+                delegator,
+                &LegacyAllowedWithdrawlSettings {
+                    grantee: "compounder".to_string(),
+                    taxation_address: "taxman".to_string(),
+                    max_fee_percentage: Decimal::percent(5),
+                    expiration: Timestamp::from_seconds(4_102_444_800),
+                },
+            )
+            .unwrap();
+    }
+
+    // the limit leaves the second legacy grant for the next migration
+    let res = migrate(deps.as_mut(), env.clone(), MigrateMsg { limit: Some(1) }).unwrap();
+    assert!(res
+        .attributes
+        .iter()
+        .any(|attr| attr.key == "has_remaining_legacy_grants" && attr.value == "true"));
+    assert!(LEGACY_GRANTS.has(deps.as_ref().storage, &delegators[1]));
+
+    // migrating again to the same version is rejected, the rest are moved over by anyone executing
+    let err = migrate(deps.as_mut(), env.clone(), MigrateMsg { limit: Some(1) }).unwrap_err();
+    assert!(matches!(err, ContractError::InvalidMigration { .. }));
+    let res = execute(
+        deps.as_mut(),
+        env,
+        mock_info("anyone", &[]),
+        ExecuteMsg::MigrateLegacyGrants(Some(1)),
+    )
+    .unwrap();
+    assert!(res
+        .attributes
+        .iter()
+        .any(|attr| attr.key == "has_remaining_legacy_grants" && attr.value == "false"));
+
+    for delegator in delegators.iter() {
+        assert_eq!(
+            GRANTS
+                .load(
+                    deps.as_ref().storage,
+                    (delegator, &Addr::unchecked("compounder"))
+                )
+                .unwrap(),
+            withdrawl_settings("compounder", 5)
+        );
+        assert!(!LEGACY_GRANTS.has(deps.as_ref().storage, delegator));
+    }
+    assert_eq!(
+        cw2::get_contract_version(deps.as_ref().storage)
+            .unwrap()
+            .version,
+        env!("CARGO_PKG_VERSION")
+    );
+}
+
+#[test]
+fn reward_thresholds() {
+    let combined = combine_reward_thresholds(
+        Some(RewardThresholds {
+            min_total_rewards: coins(1_000, "ujuno"),
+            min_validator_rewards: coins(100, "ujuno"),
+        }),
+        Some(RewardThresholds {
+            min_total_rewards: coins(500, "ujuno"),
+            min_validator_rewards: vec![Coin::new(200, "ujuno"), Coin::new(10, "uatom")],
+        }),
+    );
+    assert_eq!(
+        combined,
+        RewardThresholds {
+            min_total_rewards: coins(1_000, "ujuno"),
+            min_validator_rewards: vec![Coin::new(200, "ujuno"), Coin::new(10, "uatom")],
+        }
+    );
+
+    let delegator = Addr::unchecked("delegator");
+    let pending_rewards = AllPendingRewards {
+        rewards: vec![
+            PendingReward {
+                validator: "vali1".to_string(),
+                amount: coins(150, "ujuno"),
+            },
+            PendingReward {
+                validator: "vali2".to_string(),
+                amount: coins(1_200, "ujuno"),
+            },
+        ],
+        total: coins(1_350, "ujuno"),
+    };
+
+    // validators below the per validator minimum are left unclaimed
+    assert_eq!(
+        apply_reward_thresholds(pending_rewards.clone(), &combined, &delegator).unwrap(),
+        AllPendingRewards {
+            rewards: vec![PendingReward {
+                validator: "vali2".to_string(),
+                amount: coins(1_200, "ujuno"),
+            }],
+            total: coins(1_200, "ujuno"),
+        }
+    );
+
+    // no thresholds claims everything
+    assert_eq!(
+        apply_reward_thresholds(
+            pending_rewards.clone(),
+            &RewardThresholds::default(),
+            &delegator
+        )
+        .unwrap(),
+        pending_rewards
+    );
+
+    // without thresholds dust below a whole unit isn't checked either
+    let dust_rewards = AllPendingRewards {
+        rewards: vec![PendingReward {
+            validator: "vali1".to_string(),
+            amount: coins(0, "ujuno"),
+        }],
+        total: coins(0, "ujuno"),
+    };
+    assert_eq!(
+        apply_reward_thresholds(
+            dust_rewards.clone(),
+            &RewardThresholds::default(),
+            &delegator
+        )
+        .unwrap(),
+        dust_rewards
+    );
+
+    // nothing left that meets the total minimum
+    assert!(matches!(
+        apply_reward_thresholds(
+            pending_rewards,
+            &RewardThresholds {
+                min_total_rewards: coins(2_000, "ujuno"),
+                min_validator_rewards: vec![],
+            },
+            &delegator
+        ),
+        Err(ContractError::RewardsBelowThreshold(addr)) if addr == delegator.as_str()
+    ));
+
+    // rewards in denoms without a listed minimum don't meet the thresholds on their own
+    let ujuno_thresholds = RewardThresholds {
+        min_total_rewards: coins(100, "ujuno"),
+        min_validator_rewards: coins(100, "ujuno"),
+    };
+    let dust_validator = PendingReward {
+        validator: "vali1".to_string(),
+        amount: coins(5, "ibc/dust"),
+    };
+    let juno_validator = PendingReward {
+        validator: "vali2".to_string(),
+        amount: vec![Coin::new(5, "ibc/dust"), Coin::new(150, "ujuno")],
+    };
+    assert_eq!(
+        apply_reward_thresholds(
+            AllPendingRewards {
+                rewards: vec![dust_validator.clone(), juno_validator.clone()],
+                total: vec![Coin::new(10, "ibc/dust"), Coin::new(150, "ujuno")],
+            },
+            &ujuno_thresholds,
+            &delegator
+        )
+        .unwrap()
+        .rewards,
+        vec![juno_validator]
+    );
+    assert!(matches!(
+        apply_reward_thresholds(
+            AllPendingRewards {
+                rewards: vec![dust_validator],
+                total: coins(5, "ibc/dust"),
+            },
+            &RewardThresholds {
+                min_total_rewards: coins(100, "ujuno"),
+                min_validator_rewards: vec![],
+            },
+            &delegator
+        ),
+        Err(ContractError::RewardsBelowThreshold(_))
+    ));
+}
+
+#[test]
+fn execution_cooldown() {
+    let mut deps = mock_dependencies();
+    let mut env = mock_env();
+    let delegator = Addr::unchecked("delegator");
+    let grantee = Addr::unchecked("grantee");
+
+    execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info(delegator.as_str(), &[]),
+        ExecuteMsg::Grant(AllowedWithdrawlSettings {
+            min_interval: Some(86_400),
+            ..withdrawl_settings(grantee.as_str(), 5)
+        }),
+    )
+    .unwrap();
+
+    // a grant that was never executed can be executed at any time
+    let grant = resolve_active_grant(deps.as_ref().storage, &env.block, &delegator, None).unwrap();
+    assert_eq!(grant.next_eligible_execution, None);
+
+    LAST_EXECUTIONS
+        .save(
+            deps.as_mut().storage,
+            (&delegator, &grantee),
+            &env.block.time,
+        )
+        .unwrap();
+    let next_eligible_execution = env.block.time.plus_seconds(86_400);
+    let grant = resolve_active_grant(deps.as_ref().storage, &env.block, &delegator, None).unwrap();
+    assert_eq!(grant.next_eligible_execution, Some(next_eligible_execution));
+
+    let execute_msg = ExecuteMsg::Execute(ExecuteSettings {
+        delegator: delegator.to_string(),
+        percentage: None,
+        grantee: None,
+        reward_thresholds: None,
+    });
+
+    // executing before the interval has passed is rejected
+    env.block.time = env.block.time.plus_seconds(3_600);
+    assert!(matches!(
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(grantee.as_str(), &[]),
+            execute_msg.clone(),
+        ),
+        Err(ContractError::ExecutionCooldown { next_eligible_execution: next, .. }) if next == next_eligible_execution
+    ));
+
+    // once the interval has passed the execution goes ahead, failing here only since there are no rewards
+    env.block.time = next_eligible_execution;
+    assert!(matches!(
+        execute(
+            deps.as_mut(),
+            env,
+            mock_info(grantee.as_str(), &[]),
+            execute_msg,
+        ),
+        Err(ContractError::NoPendingRewards(_))
+    ));
+
+    // revoking the grant clears its execution history
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(delegator.as_str(), &[]),
+        ExecuteMsg::Revoke(None),
+    )
+    .unwrap();
+    assert_eq!(
+        LAST_EXECUTIONS
+            .may_load(deps.as_ref().storage, (&delegator, &grantee))
+            .unwrap(),
+        None
+    );
+}
+
+/// answers the withdraw address stargate query on top of the mock querier,
+/// delegators without a custom withdraw address withdraw to themselves
+struct WithdrawAddressQuerier {
+    base: MockQuerier,
+    withdraw_addresses: HashMap<String, String>,
+}
+
+impl Querier for WithdrawAddressQuerier {
+    fn raw_query(&self, bin_request: &[u8]) -> QuerierResult {
+        match from_slice::<QueryRequest<Empty>>(bin_request) {
+            Ok(QueryRequest::Stargate { path, data })
+                if path == "/cosmos.distribution.v1beta1.Query/DelegatorWithdrawAddress" =>
+            {
+                let QueryDelegatorWithdrawAddressRequest { delegator_address } =
+                    QueryDelegatorWithdrawAddressRequest::decode(data.as_slice()).unwrap();
+                let withdraw_address = self
+                    .withdraw_addresses
+                    .get(&delegator_address)
+                    .cloned()
+                    .unwrap_or(delegator_address);
+                SystemResult::Ok(ContractResult::Ok(
+                    to_binary(&Binary::from(
+                        QueryDelegatorWithdrawAddressResponse { withdraw_address }.encode_to_vec(),
+                    ))
+                    .unwrap(),
+                ))
+            }
+            _ => self.base.raw_query(bin_request),
+        }
+    }
+}
+
+fn mock_dependencies_with_withdraw_addresses(
+    withdraw_addresses: &[(&Addr, &str)],
+) -> OwnedDeps<MockStorage, MockApi, WithdrawAddressQuerier> {
+    OwnedDeps {
+        storage: MockStorage::default(),
+        api: MockApi::default(),
+        querier: WithdrawAddressQuerier {
+            base: MockQuerier::default(),
+            withdraw_addresses: withdraw_addresses
+                .iter()
+                .map(|(delegator, withdraw_address)| {
+                    (delegator.to_string(), withdraw_address.to_string())
+                })
+                .collect(),
+        },
+        custom_query_type: PhantomData,
+    }
+}
+
+/// sets up a single validator that each of the delegators has the given ujuno rewards pending with
+fn mock_staking_rewards(querier: &mut MockQuerier, delegators: &[(&Addr, u128)]) {
+    querier.update_staking(
+        "ujuno",
+        &[Validator {
+            address: "vali1".to_string(),
+            commission: Decimal::percent(5),
+            max_commission: Decimal::percent(10),
+            max_change_rate: Decimal::percent(1),
+        }],
+        &delegators
+            .iter()
+            .map(|(delegator, rewards)| FullDelegation {
+                delegator: (*delegator).clone(),
+                validator: "vali1".to_string(),
+                amount: Coin::new(1_000_000, "ujuno"),
+                can_redelegate: Coin::new(1_000_000, "ujuno"),
+                accumulated_rewards: coins(*rewards, "ujuno"),
+            })
+            .collect::<Vec<_>>(),
+    );
+}
+
+#[test]
+fn reward_totals() {
+    let mut deps = mock_dependencies_with_withdraw_addresses(&[]);
+    let env = mock_env();
+    let delegator = Addr::unchecked("delegator");
+
+    mock_staking_rewards(&mut deps.querier.base, &[(&delegator, 1_000)]);
+
+    execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info(delegator.as_str(), &[]),
+        ExecuteMsg::Grant(withdrawl_settings("grantee", 10)),
+    )
+    .unwrap();
+
+    for _ in 0..2 {
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("grantee", &[]),
+            ExecuteMsg::Execute(ExecuteSettings {
+                delegator: delegator.to_string(),
+                percentage: None,
+                grantee: None,
+                reward_thresholds: None,
+            }),
+        )
+        .unwrap();
+    }
+
+    let query_totals = |msg: QueryMsg| -> RewardTotals {
+        from_binary(&query(deps.as_ref(), env.clone(), msg).unwrap()).unwrap()
+    };
+    let expected_totals = RewardTotals {
+        executions: 2,
+        delegator_rewards: coins(1_800, "ujuno"),
+        taxation_rewards: coins(200, "ujuno"),
+        last_execution: Some(env.block.time),
+    };
+
+    assert_eq!(
+        query_totals(QueryMsg::RewardTotalsByDelegator(delegator.to_string())),
+        expected_totals
+    );
+    assert_eq!(
+        query_totals(QueryMsg::RewardTotalsByGrantee("grantee".to_string())),
+        expected_totals
+    );
+    assert_eq!(
+        query_totals(QueryMsg::RewardTotalsByTaxationAddress(
+            "taxman".to_string()
+        )),
+        expected_totals
+    );
+
+    // addresses that were never part of an execution have empty totals
+    assert_eq!(
+        query_totals(QueryMsg::RewardTotalsByGrantee("someone".to_string())),
+        RewardTotals::default()
+    );
+
+    // empty coins don't get added to the totals
+    assert_eq!(
+        add_to_reward_totals(
+            RewardTotals::default(),
+            &coins(10, "ujuno"),
+            &coins(0, "ujuno"),
+            env.block.time
+        ),
+        RewardTotals {
+            executions: 1,
+            delegator_rewards: coins(10, "ujuno"),
+            taxation_rewards: vec![],
+            last_execution: Some(env.block.time),
+        }
+    );
+}
+
+#[test]
+fn execute_batch() {
+    let mut deps = mock_dependencies_with_withdraw_addresses(&[]);
+    let env = mock_env();
+    let delegator = Addr::unchecked("delegator");
+    let empty_delegator = Addr::unchecked("empty_delegator");
+    let ungranted_delegator = Addr::unchecked("ungranted_delegator");
+    let cooldown_delegator = Addr::unchecked("cooldown_delegator");
+    let other_grantee_delegator = Addr::unchecked("other_grantee_delegator");
+
+    mock_staking_rewards(
+        &mut deps.querier.base,
+        &[
+            (&delegator, 1_000),
+            (&empty_delegator, 0),
+            (&ungranted_delegator, 1_000),
+            (&cooldown_delegator, 1_000),
+            (&other_grantee_delegator, 1_000),
+        ],
+    );
+
+    for granter in [&delegator, &empty_delegator] {
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(granter.as_str(), &[]),
+            ExecuteMsg::Grant(withdrawl_settings("grantee", 10)),
+        )
+        .unwrap();
+    }
+    execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info(cooldown_delegator.as_str(), &[]),
+        ExecuteMsg::Grant(AllowedWithdrawlSettings {
+            min_interval: Some(86_400),
+            ..withdrawl_settings("grantee", 10)
+        }),
+    )
+    .unwrap();
+    LAST_EXECUTIONS
+        .save(
+            deps.as_mut().storage,
+            (&cooldown_delegator, &Addr::unchecked("grantee")),
+            &env.block.time,
+        )
+        .unwrap();
+    execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info(other_grantee_delegator.as_str(), &[]),
+        ExecuteMsg::Grant(withdrawl_settings("someone_else", 10)),
+    )
+    .unwrap();
+
+    let batch = [
+        &delegator,
+        &empty_delegator,
+        &ungranted_delegator,
+        &cooldown_delegator,
+        &other_grantee_delegator,
+        &delegator,
+    ]
+    .into_iter()
+    .map(|delegator| ExecuteSettings {
+        delegator: delegator.to_string(),
+        percentage: None,
+        grantee: None,
+        reward_thresholds: None,
+    })
+    .collect::<Vec<_>>();
+
+    let simulations: SimulateExecuteBatchResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            env.clone(),
+            QueryMsg::SimulateExecuteBatch {
+                sender: "grantee".to_string(),
+                batch: batch.clone(),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        simulations[0]
+            .simulation
+            .as_ref()
+            .unwrap()
+            .delegator_rewards,
+        coins(900, "ujuno")
+    );
+
+    let res = execute(
+        deps.as_mut(),
+        env,
+        mock_info("grantee", &[]),
+        ExecuteMsg::ExecuteBatch(batch),
+    )
+    .unwrap();
+
+    // only the delegator with rewards and a grant to the sender is executed, the rest are reported
+    let attributes = |key: &str| {
+        res.attributes
+            .iter()
+            .filter(|attr| attr.key.eq(key))
+            .map(|attr| attr.value.clone())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(attributes("executed"), vec![delegator.to_string()]);
+    assert_eq!(
+        attributes("skipped"),
+        vec![
+            format!(
+                "{empty_delegator}: {}",
+                ContractError::NoPendingRewards(empty_delegator.to_string())
+            ),
+            format!(
+                "{ungranted_delegator}: {}",
+                ContractError::NoActiveGrant(ungranted_delegator.to_string())
+            ),
+            format!(
+                "{cooldown_delegator}: {}",
+                ContractError::ExecutionCooldown {
+                    delegator: cooldown_delegator.to_string(),
+                    grantee: "grantee".to_string(),
+                    next_eligible_execution: mock_env().block.time.plus_seconds(86_400),
+                }
+            ),
+            format!(
+                "{other_grantee_delegator}: {}",
+                ContractError::NoActiveGrant(other_grantee_delegator.to_string())
+            ),
+            format!(
+                "{delegator}: {}",
+                ContractError::DuplicateBatchDelegator(delegator.to_string())
+            ),
+        ]
+    );
+    assert_eq!(attributes("num_executed"), vec!["1"]);
+    assert_eq!(attributes("num_skipped"), vec!["5"]);
+
+    // the simulation of the same batch on the same state agrees with the execution
+    assert_eq!(
+        simulations
+            .iter()
+            .filter(|simulation| simulation.simulation.is_some())
+            .map(|simulation| simulation.delegator.clone())
+            .collect::<Vec<_>>(),
+        attributes("executed")
+    );
+    assert_eq!(
+        simulations
+            .iter()
+            .filter_map(|simulation| simulation
+                .error
+                .as_ref()
+                .map(|err| format!("{}: {err}", simulation.delegator)))
+            .collect::<Vec<_>>(),
+        attributes("skipped")
+    );
+
+    // a single call back into the contract with the withdraw MsgExec and the sends to the taxation
+    // recipient and the delegator
+    assert_eq!(res.messages.len(), 1);
+    assert_eq!(batch_entry_msgs(&res.messages[0].msg).len(), 3);
+}
+
+/// the messages of the batch entry that the contract calls itself with
+fn batch_entry_msgs(msg: &CosmosMsg) -> Vec<CosmosMsg> {
+    match msg {
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr, msg, ..
+        }) if contract_addr.eq(mock_env().contract.address.as_str()) => {
+            match from_binary(msg).unwrap() {
+                ExecuteMsg::ExecuteBatchEntry { msgs, .. } => msgs,
+                _ => panic!("expected a batch entry"),
+            }
+        }
+        _ => panic!("expected a call to the contract"),
+    }
+}
+
+#[test]
+fn execute_batch_failed_delegator() {
+    let mut deps = mock_dependencies_with_withdraw_addresses(&[]);
+    let env = mock_env();
+    let delegator = Addr::unchecked("delegator");
+    let revoked_delegator = Addr::unchecked("revoked_delegator");
+    let grantee = Addr::unchecked("grantee");
+
+    mock_staking_rewards(
+        &mut deps.querier.base,
+        &[(&delegator, 1_000), (&revoked_delegator, 1_000)],
+    );
+    for granter in [&delegator, &revoked_delegator] {
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(granter.as_str(), &[]),
+            ExecuteMsg::Grant(withdrawl_settings(grantee.as_str(), 10)),
+        )
+        .unwrap();
+    }
+
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info(grantee.as_str(), &[]),
+        ExecuteMsg::ExecuteBatch(
+            [&delegator, &revoked_delegator]
+                .into_iter()
+                .map(|delegator| ExecuteSettings {
+                    delegator: delegator.to_string(),
+                    percentage: None,
+                    grantee: None,
+                    reward_thresholds: None,
+                })
+                .collect(),
+        ),
+    )
+    .unwrap();
+
+    // each delegator's messages are sent by one call back into the contract that only replies when it fails
+    assert_eq!(
+        res.messages
+            .iter()
+            .map(|sub_msg| (sub_msg.id, sub_msg.reply_on.clone()))
+            .collect::<Vec<_>>(),
+        vec![(0, ReplyOn::Error), (1, ReplyOn::Error)]
+    );
+    let entry_msgs = batch_entry_msgs(&res.messages[0].msg);
+    assert_eq!(entry_msgs.len(), 3);
+
+    // nothing is recorded until the messages went through
+    assert_eq!(
+        LAST_EXECUTIONS
+            .may_load(deps.as_ref().storage, (&delegator, &grantee))
+            .unwrap(),
+        None
+    );
+
+    // only the contract can send a batch entry
+    let entry = ExecuteMsg::ExecuteBatchEntry {
+        reply_id: 0,
+        msgs: entry_msgs.clone(),
+    };
+    let err = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info(grantee.as_str(), &[]),
+        entry.clone(),
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::Unauthorized {}));
+
+    // the first delegator's entry records the execution along with sending its messages
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info(env.contract.address.as_str(), &[]),
+        entry,
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages
+            .into_iter()
+            .map(|sub_msg| sub_msg.msg)
+            .collect::<Vec<_>>(),
+        entry_msgs
+    );
+
+    // the second delegator revoked the authz grant so their MsgExec fails, which reverts their whole entry
+    let res = reply(
+        deps.as_mut(),
+        env.clone(),
+        Reply {
+            id: 1,
+            result: SubMsgResult::Err("authorization not found".to_string()),
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res.attributes
+            .iter()
+            .find(|attr| attr.key == "skipped")
+            .unwrap()
+            .value,
+        format!("{revoked_delegator}: authorization not found")
+    );
+    assert!(PENDING_EXECUTIONS.is_empty(deps.as_ref().storage));
+
+    // only the first delegator's execution is recorded
+    assert_eq!(
+        LAST_EXECUTIONS
+            .may_load(deps.as_ref().storage, (&delegator, &grantee))
+            .unwrap(),
+        Some(env.block.time)
+    );
+    assert_eq!(
+        LAST_EXECUTIONS
+            .may_load(deps.as_ref().storage, (&revoked_delegator, &grantee))
+            .unwrap(),
+        None
+    );
+    let query_totals = |msg: QueryMsg| -> RewardTotals {
+        from_binary(&query(deps.as_ref(), env.clone(), msg).unwrap()).unwrap()
+    };
+    let expected_totals = RewardTotals {
+        executions: 1,
+        delegator_rewards: coins(900, "ujuno"),
+        taxation_rewards: coins(100, "ujuno"),
+        last_execution: Some(env.block.time),
+    };
+    assert_eq!(
+        query_totals(QueryMsg::RewardTotalsByGrantee(grantee.to_string())),
+        expected_totals
+    );
+    assert_eq!(
+        query_totals(QueryMsg::RewardTotalsByTaxationAddress(
+            "taxman".to_string()
+        )),
+        expected_totals
+    );
+    assert_eq!(
+        query_totals(QueryMsg::RewardTotalsByDelegator(
+            revoked_delegator.to_string()
+        )),
+        RewardTotals::default()
+    );
+}
+
+#[test]
+fn execute_restores_custom_withdraw_address() {
+    let delegator = Addr::unchecked("delegator");
+    let cold_wallet = Addr::unchecked("cold_wallet");
+    let env = mock_env();
+    let execute_msg = ExecuteMsg::Execute(ExecuteSettings {
+        delegator: delegator.to_string(),
+        percentage: None,
+        grantee: None,
+        reward_thresholds: None,
+    });
+
+    let mut deps = mock_dependencies_with_withdraw_addresses(&[(&delegator, cold_wallet.as_str())]);
+    mock_staking_rewards(&mut deps.querier.base, &[(&delegator, 1_000)]);
+    execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info(delegator.as_str(), &[]),
+        ExecuteMsg::Grant(withdrawl_settings("grantee", 10)),
+    )
+    .unwrap();
+
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("grantee", &[]),
+        execute_msg.clone(),
+    )
+    .unwrap();
+
+    // the withdraw address is set back to the cold wallet, which also gets the delegator's share
+    assert_eq!(
+        res.messages
+            .into_iter()
+            .map(|sub_msg| sub_msg.msg)
+            .collect::<Vec<_>>(),
+        vec![
+            create_withdraw_rewards_exec_msg(
+                &delegator,
+                &env.contract.address,
+                &cold_wallet,
+                &[PendingReward {
+                    amount: coins(1_000, "ujuno"),
+                    validator: "vali1".to_string(),
+                }],
+            )
+            .unwrap(),
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "taxman".to_string(),
+                amount: coins(100, "ujuno"),
+            }),
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: cold_wallet.to_string(),
+                amount: coins(900, "ujuno"),
+            }),
+        ]
+    );
+
+    // without knowing the withdraw address the execution is aborted instead of resetting it
+    let mut deps = mock_dependencies();
+    mock_staking_rewards(&mut deps.querier, &[(&delegator, 1_000)]);
+    execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info(delegator.as_str(), &[]),
+        ExecuteMsg::Grant(withdrawl_settings("grantee", 10)),
+    )
+    .unwrap();
+    assert!(matches!(
+        execute(deps.as_mut(), env, mock_info("grantee", &[]), execute_msg),
+        Err(ContractError::Std(_))
+    ));
+}
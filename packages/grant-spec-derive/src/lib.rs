@@ -0,0 +1,326 @@
+use proc_macro::TokenStream;
+use proc_macro2::Span;
+use quote::{format_ident, quote};
+use syn::{parse_macro_input, parse_quote, DeriveInput, Fields, ItemEnum, LitStr, Type, Variant};
+
+/// Adds the uniform grant discovery queries to a contract's `QueryMsg`:
+/// - `GrantSpec { granter, grantee, expiration, grant_data }` returning `Vec<GrantRequirement>`
+/// - `RevokeSpec { granter, grantee, grant_data }` returning `Vec<RevokeRequirement>`
+/// - `DescribeGrantSpec { granter, grantee, expiration, grant_data }` returning `Vec<GrantDescription>`
+///
+/// The argument is the settings type that `QueryMsg` uses as its `Grantable::GrantSettings`.
+/// The attribute has to be placed above `#[cw_serde]` and `#[derive(QueryResponses)]` so that
+/// they see the added variants.
+///
+/// It also generates `QueryMsg::grant_spec_query(self, &env)` which answers these queries
+/// by calling into the `Grantable` implementation, so the contract only has to dispatch them.
+/// The contract still implements `Grantable::query_grants` itself since the grants are specific
+/// to it, the revokes are derived from those unless `query_revokes` is overridden:
+///
+/// ```ignore
+/// q @ (QueryMsg::GrantSpec { .. }
+///     | QueryMsg::RevokeSpec { .. }
+///     | QueryMsg::DescribeGrantSpec { .. }) => q.grant_spec_query(&env),
+/// ```
+#[proc_macro_attribute]
+pub fn grant_spec_queries(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let settings = parse_macro_input!(attr as Type);
+    let mut query_msg = parse_macro_input!(item as ItemEnum);
+
+    // the returns attribute is only understood when the enum derives QueryResponses
+    let derives_query_responses = match derives(&query_msg.attrs, "QueryResponses") {
+        Ok(derives_query_responses) => derives_query_responses,
+        Err(err) => return err.to_compile_error().into(),
+    };
+    // without any variants of its own the enum only has the grant spec queries to answer
+    let other_queries = (!query_msg.variants.is_empty()).then(|| {
+        quote! {
+            _ => Err(::cosmwasm_std::StdError::generic_err(
+                "Only GrantSpec, RevokeSpec and DescribeGrantSpec queries can be answered from the grant spec",
+            )),
+        }
+    });
+    let (grant_returns, revoke_returns, describe_returns) = if derives_query_responses {
+        (
+            quote!(#[returns(::std::vec::Vec<::cw_grant_spec::grants::GrantRequirement>)]),
+            quote!(#[returns(::std::vec::Vec<::cw_grant_spec::grants::RevokeRequirement>)]),
+            quote!(#[returns(::std::vec::Vec<::cw_grant_spec::describe::GrantDescription>)]),
+        )
+    } else {
+        (quote!(), quote!(), quote!())
+    };
+
+    let grant_spec: Variant = parse_quote! {
+        /// Returns the grants the granter needs to give before the contract can be used with the given settings
+        #grant_returns
+        GrantSpec {
+            granter: ::cosmwasm_std::Addr,
+            grantee: ::cosmwasm_std::Addr,
+            expiration: ::cw_grant_spec::grants::GrantExpiration,
+            grant_data: #settings,
+        }
+    };
+    let revoke_spec: Variant = parse_quote! {
+        /// Returns the revokes the granter needs to sign to remove the grants for the given settings
+        #revoke_returns
+        RevokeSpec {
+            granter: ::cosmwasm_std::Addr,
+            grantee: ::cosmwasm_std::Addr,
+            grant_data: #settings,
+        }
+    };
+    let describe_grant_spec: Variant = parse_quote! {
+        /// Returns human readable descriptions of the grants from `GrantSpec` for confirmation screens
+        #describe_returns
+        DescribeGrantSpec {
+            granter: ::cosmwasm_std::Addr,
+            grantee: ::cosmwasm_std::Addr,
+            expiration: ::cw_grant_spec::grants::GrantExpiration,
+            grant_data: #settings,
+        }
+    };
+    query_msg.variants.push(grant_spec);
+    query_msg.variants.push(revoke_spec);
+    query_msg.variants.push(describe_grant_spec);
+
+    let name = &query_msg.ident;
+    let (impl_generics, ty_generics, where_clause) = query_msg.generics.split_for_impl();
+
+    quote! {
+        #query_msg
+
+        impl #impl_generics #name #ty_generics #where_clause {
+            /// Answers the `GrantSpec`, `RevokeSpec` and `DescribeGrantSpec` queries using the `Grantable` implementation
+            pub fn grant_spec_query(
+                self,
+                env: &::cosmwasm_std::Env,
+            ) -> ::cosmwasm_std::StdResult<::cosmwasm_std::Binary> {
+                match self {
+                    #name::GrantSpec {
+                        granter,
+                        grantee,
+                        expiration,
+                        grant_data,
+                    } => ::cosmwasm_std::to_binary(
+                        &<Self as ::cw_grant_spec::grantable_trait::Grantable>::query_grants(
+                            ::cw_grant_spec::grantable_trait::GrantStructure {
+                                granter,
+                                grantee,
+                                expiration,
+                                grant_contract: env.contract.address.clone(),
+                                grant_data,
+                            },
+                            env.block.time,
+                        )?,
+                    ),
+                    #name::RevokeSpec {
+                        granter,
+                        grantee,
+                        grant_data,
+                    } => ::cosmwasm_std::to_binary(
+                        &<Self as ::cw_grant_spec::grantable_trait::Grantable>::query_revokes(
+                            ::cw_grant_spec::grantable_trait::GrantStructure {
+                                granter,
+                                grantee,
+                                // revokes don't carry an expiration
+                                expiration: ::cw_grant_spec::grants::GrantExpiration::Never,
+                                grant_contract: env.contract.address.clone(),
+                                grant_data,
+                            },
+                        )?,
+                    ),
+                    #name::DescribeGrantSpec {
+                        granter,
+                        grantee,
+                        expiration,
+                        grant_data,
+                    } => ::cosmwasm_std::to_binary(
+                        &::cw_grant_spec::describe::describe_grant_reqs(
+                            <Self as ::cw_grant_spec::grantable_trait::Grantable>::query_grants(
+                                ::cw_grant_spec::grantable_trait::GrantStructure {
+                                    granter,
+                                    grantee,
+                                    expiration,
+                                    grant_contract: env.contract.address.clone(),
+                                    grant_data,
+                                },
+                                env.block.time,
+                            )?,
+                        )?,
+                    ),
+                    #other_queries
+                }
+            }
+        }
+    }
+    .into()
+}
+
+/// Implements `MessageKeys` for a `cw_serde` execute message and generates a `{Name}Key` enum
+/// with a variant for every message that can be matched by an `AcceptedMessageKeysFilter`.
+///
+/// The keys are the names serde deserializes the variants from, so they follow the container's
+/// `#[serde(rename_all = "...")]` rule (`snake_case` for `cw_serde`, which has to be placed above
+/// the derive) along with any `#[serde(rename = "...")]` on a variant. Unit variants are
+/// serialized as plain strings instead of json objects so they are left out.
+///
+/// ```ignore
+/// GrantRequirement::default_contract_exec_auth(base, contract, vec![ExecuteMsgKey::Execute], None)
+/// ```
+#[proc_macro_derive(MessageKeys, attributes(serde))]
+pub fn derive_message_keys(item: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(item as DeriveInput);
+    let syn::Data::Enum(data) = &input.data else {
+        return syn::Error::new_spanned(&input.ident, "MessageKeys can only be derived for enums")
+            .to_compile_error()
+            .into();
+    };
+
+    let rename_all = match serde_name(&input.attrs, "rename_all") {
+        Ok(rename_all) => rename_all,
+        Err(err) => return err.to_compile_error().into(),
+    };
+
+    let mut key_variants = vec![];
+    let mut keys = vec![];
+    for variant in &data.variants {
+        if matches!(variant.fields, Fields::Unit) {
+            continue;
+        }
+        let key = match serde_name(&variant.attrs, "rename") {
+            Ok(Some(rename)) => rename.value(),
+            Ok(None) => match &rename_all {
+                Some(rule) => match apply_rename_rule(rule, &variant.ident.to_string()) {
+                    Ok(key) => key,
+                    Err(err) => return err.to_compile_error().into(),
+                },
+                None => variant.ident.to_string(),
+            },
+            Err(err) => return err.to_compile_error().into(),
+        };
+
+        key_variants.push(variant.ident.clone());
+        keys.push(LitStr::new(&key, Span::call_site()));
+    }
+
+    let vis = &input.vis;
+    let name = &input.ident;
+    let key_name = format_ident!("{}Key", name);
+    let key_doc = format!("The top level keys of the `{name}` messages");
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    quote! {
+        #[doc = #key_doc]
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+        #vis enum #key_name {
+            #(#key_variants,)*
+        }
+
+        impl #key_name {
+            pub const fn as_str(&self) -> &'static str {
+                // matching on the value rather than the reference also works without any keys
+                match *self {
+                    #(#key_name::#key_variants => #keys,)*
+                }
+            }
+        }
+
+        impl ::std::convert::From<#key_name> for ::std::string::String {
+            fn from(key: #key_name) -> Self {
+                key.as_str().to_string()
+            }
+        }
+
+        impl #impl_generics ::cw_grant_spec::grants::MessageKeys for #name #ty_generics #where_clause {
+            type Key = #key_name;
+
+            fn message_keys() -> ::std::vec::Vec<Self::Key> {
+                ::std::vec![#(#key_name::#key_variants,)*]
+            }
+        }
+    }
+    .into()
+}
+
+/// Whether one of the `#[derive(...)]` attributes derives the given trait, by any path to it
+fn derives(attrs: &[syn::Attribute], name: &str) -> syn::Result<bool> {
+    let mut derived = false;
+    for attr in attrs.iter().filter(|attr| attr.path().is_ident("derive")) {
+        attr.parse_nested_meta(|meta| {
+            if meta
+                .path
+                .segments
+                .last()
+                .is_some_and(|segment| segment.ident == name)
+            {
+                derived = true;
+            }
+            Ok(())
+        })?;
+    }
+
+    Ok(derived)
+}
+
+/// Returns the name a serde attribute such as `rename` or `rename_all` gives for deserializing,
+/// from either its `key = "..."` or its `key(serialize = "...", deserialize = "...")` form
+fn serde_name(attrs: &[syn::Attribute], key: &str) -> syn::Result<Option<LitStr>> {
+    let mut name = None;
+    for attr in attrs.iter().filter(|attr| attr.path().is_ident("serde")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident(key) && meta.input.peek(syn::Token![=]) {
+                name = Some(meta.value()?.parse::<LitStr>()?);
+            } else if meta.path.is_ident(key) {
+                meta.parse_nested_meta(|meta| {
+                    let value = meta.value()?.parse::<LitStr>()?;
+                    if meta.path.is_ident("deserialize") {
+                        name = Some(value);
+                    }
+                    Ok(())
+                })?;
+            } else if meta.input.peek(syn::Token![=]) {
+                // skip the values of other serde attributes
+                meta.value()?.parse::<syn::Expr>()?;
+            } else if meta.input.peek(syn::token::Paren) {
+                let _skipped;
+                syn::parenthesized!(_skipped in meta.input);
+            }
+            Ok(())
+        })?;
+    }
+
+    Ok(name)
+}
+
+/// Renames a variant the same way serde's `rename_all` rules do
+fn apply_rename_rule(rule: &LitStr, variant: &str) -> syn::Result<String> {
+    Ok(match rule.value().as_str() {
+        "lowercase" => variant.to_ascii_lowercase(),
+        "UPPERCASE" => variant.to_ascii_uppercase(),
+        "PascalCase" => variant.to_string(),
+        "camelCase" => variant[..1].to_ascii_lowercase() + &variant[1..],
+        "snake_case" => snake_case(variant),
+        "SCREAMING_SNAKE_CASE" => snake_case(variant).to_ascii_uppercase(),
+        "kebab-case" => snake_case(variant).replace('_', "-"),
+        "SCREAMING-KEBAB-CASE" => snake_case(variant).replace('_', "-").to_ascii_uppercase(),
+        _ => {
+            return Err(syn::Error::new_spanned(
+                rule,
+                "unknown serde rename_all rule",
+            ))
+        }
+    })
+}
+
+/// Converts a variant name the same way serde's `snake_case` rule does
+fn snake_case(name: &str) -> String {
+    let mut snake = String::new();
+    for (i, ch) in name.char_indices() {
+        if i > 0 && ch.is_uppercase() {
+            snake.push('_');
+        }
+        snake.push(ch.to_ascii_lowercase());
+    }
+
+    snake
+}
@@ -0,0 +1,237 @@
+use cosmwasm_std::{Coin, Timestamp};
+
+use crate::{
+    errors::GrantSpecError,
+    grantable_trait::dedupe_grant_reqs,
+    grants::{
+        AuthorizationType, ContractExecutionAuthorizationFilter,
+        ContractExecutionAuthorizationLimit, ContractExecutionSetting, ExistingGrant,
+        GrantExpiration, GrantRequirement, StakeAuthorizationPolicy, StakeAuthorizationValidators,
+        TransferAuthorizationSetting,
+    },
+    helpers::{coins_cover, is_subset, message_keys},
+};
+
+/// Returns the requirements that are not yet satisfied by the granter's existing grants.
+///
+/// The requirements are deduped first so that several requirements aimed at the same grant are
+/// checked against the existing grant as a whole.
+/// `ContractExec` and `FeeAllowanceSpec` requirements can't be checked against authz grants so
+/// they are always returned.
+pub fn missing_grant_reqs(
+    requirements: Vec<GrantRequirement>,
+    existing_grants: &[ExistingGrant],
+) -> Result<Vec<GrantRequirement>, GrantSpecError> {
+    Ok(dedupe_grant_reqs(requirements)?
+        .into_iter()
+        .filter(|requirement| !is_grant_req_covered(requirement, existing_grants))
+        .collect())
+}
+
+/// Checks whether any of the existing grants already gives at least the access the requirement asks for
+pub fn is_grant_req_covered(
+    requirement: &GrantRequirement,
+    existing_grants: &[ExistingGrant],
+) -> bool {
+    match requirement {
+        GrantRequirement::GrantSpec {
+            grant_type,
+            granter,
+            grantee,
+            expiration,
+        } => existing_grants.iter().any(|existing| {
+            existing.granter.eq(granter)
+                && existing.grantee.eq(grantee)
+                && expiration_covers(&existing.expiration, expiration)
+                && existing.grant_type.covers(grant_type)
+        }),
+        GrantRequirement::ContractExec { .. } | GrantRequirement::FeeAllowanceSpec { .. } => false,
+    }
+}
+
+/// grants without an expiration never expire,
+/// relative expirations can only be covered by those as long as they're unresolved
+fn expiration_covers(existing: &Option<Timestamp>, required: &GrantExpiration) -> bool {
+    match required {
+        GrantExpiration::AtTime(required) => existing.is_none_or(|existing| existing.ge(required)),
+        GrantExpiration::Never | GrantExpiration::SecondsFromNow(_) => existing.is_none(),
+    }
+}
+
+impl AuthorizationType {
+    /// Checks whether this authorization allows at least everything that `required` allows
+    pub fn covers(&self, required: &AuthorizationType) -> bool {
+        match (self, required) {
+            // a generic authorization allows anything done with its message type
+            (AuthorizationType::GenericAuthorization { msg }, required) => {
+                msg.eq(&required.msg_type_url())
+            }
+            (
+                AuthorizationType::SendAuthorization {
+                    spend_limit,
+                    allow_list,
+                },
+                AuthorizationType::SendAuthorization {
+                    spend_limit: required_spend_limit,
+                    allow_list: required_allow_list,
+                },
+            ) => {
+                optional_list_covers(spend_limit, required_spend_limit, coins_cover)
+                    && optional_list_covers(allow_list, required_allow_list, is_subset)
+            }
+            (
+                AuthorizationType::StakeAuthorization {
+                    max_tokens,
+                    authorization_type,
+                    validators,
+                },
+                AuthorizationType::StakeAuthorization {
+                    max_tokens: required_max_tokens,
+                    authorization_type: required_authorization_type,
+                    validators: required_validators,
+                },
+            ) => {
+                authorization_type.eq(required_authorization_type)
+                    && max_tokens_covers(max_tokens, required_max_tokens)
+                    && stake_policy_covers(validators, required_validators)
+            }
+            (
+                AuthorizationType::ContractExecutionAuthorization(settings),
+                AuthorizationType::ContractExecutionAuthorization(required_settings),
+            ) => required_settings.iter().all(|required_setting| {
+                settings
+                    .iter()
+                    .any(|setting| setting.covers(required_setting))
+            }),
+            (
+                AuthorizationType::TransferAuthorization(settings),
+                AuthorizationType::TransferAuthorization(required_settings),
+            ) => required_settings.iter().all(|required_setting| {
+                settings
+                    .iter()
+                    .any(|setting| setting.covers(required_setting))
+            }),
+            _ => false,
+        }
+    }
+}
+
+/// None is unrestricted so it covers everything while a list only covers lists it contains
+fn optional_list_covers<T>(
+    available: &Option<Vec<T>>,
+    required: &Option<Vec<T>>,
+    list_covers: fn(&[T], &[T]) -> bool,
+) -> bool {
+    match (available, required) {
+        (None, _) => true,
+        (Some(_), None) => false,
+        (Some(available), Some(required)) => list_covers(available, required),
+    }
+}
+
+fn max_tokens_covers(available: &Option<Coin>, required: &Option<Coin>) -> bool {
+    match (available, required) {
+        (None, _) => true,
+        (Some(_), None) => false,
+        (Some(available), Some(required)) => {
+            available.denom.eq(&required.denom) && available.amount.ge(&required.amount)
+        }
+    }
+}
+
+fn stake_policy_covers(
+    available: &Option<StakeAuthorizationPolicy>,
+    required: &Option<StakeAuthorizationPolicy>,
+) -> bool {
+    use StakeAuthorizationPolicy::{AllowList, DenyList};
+
+    match (available, required) {
+        (None, _) => true,
+        (
+            Some(AllowList(StakeAuthorizationValidators { address: allowed })),
+            Some(AllowList(StakeAuthorizationValidators { address: required })),
+        ) => is_subset(allowed, required),
+        // none of the required validators may be denied
+        (
+            Some(DenyList(StakeAuthorizationValidators { address: denied })),
+            Some(AllowList(StakeAuthorizationValidators { address: required })),
+        ) => !required.iter().any(|validator| denied.contains(validator)),
+        // everything that is denied must also be denied by the requirement
+        (
+            Some(DenyList(StakeAuthorizationValidators { address: denied })),
+            Some(DenyList(StakeAuthorizationValidators {
+                address: required_denied,
+            })),
+        ) => is_subset(required_denied, denied),
+        (Some(DenyList(StakeAuthorizationValidators { address: denied })), None) => {
+            denied.is_empty()
+        }
+        (Some(AllowList(_)), _) => false,
+    }
+}
+
+impl ContractExecutionAuthorizationLimit {
+    /// Checks whether this limit allows at least as many calls and funds as `required`
+    pub fn covers(&self, required: &ContractExecutionAuthorizationLimit) -> bool {
+        let (calls, funds) = self.calls_and_funds();
+        let (required_calls, required_funds) = required.calls_and_funds();
+
+        let calls_covered = match (calls, required_calls) {
+            (None, _) => true,
+            (Some(_), None) => false,
+            (Some(calls), Some(required_calls)) => calls.ge(&required_calls),
+        };
+
+        calls_covered && coins_cover(&funds, &required_funds)
+    }
+}
+
+impl ContractExecutionAuthorizationFilter {
+    /// Checks whether every message accepted by `required` is also accepted by this filter
+    pub fn covers(&self, required: &ContractExecutionAuthorizationFilter) -> bool {
+        use ContractExecutionAuthorizationFilter::{
+            AcceptedMessageKeysFilter, AcceptedMessagesFilter, AllowAllMessagesFilter,
+        };
+
+        match (self, required) {
+            (AllowAllMessagesFilter, _) => true,
+            (AcceptedMessageKeysFilter { keys }, AcceptedMessageKeysFilter { keys: required }) => {
+                is_subset(keys, required)
+            }
+            // wasmd only accepts a message with a single top level key that is in the allowed keys
+            (AcceptedMessageKeysFilter { keys }, AcceptedMessagesFilter { messages }) => messages
+                .iter()
+                .all(|msg| match message_keys(msg).as_deref() {
+                    Some([key]) => keys.contains(key),
+                    _ => false,
+                }),
+            (
+                AcceptedMessagesFilter { messages },
+                AcceptedMessagesFilter { messages: required },
+            ) => is_subset(messages, required),
+            _ => false,
+        }
+    }
+}
+
+impl ContractExecutionSetting {
+    /// Checks whether this setting allows at least what `required` allows on the same contract
+    pub fn covers(&self, required: &ContractExecutionSetting) -> bool {
+        self.contract_addr.eq(&required.contract_addr)
+            && self.limit.covers(&required.limit)
+            && self.filter.covers(&required.filter)
+    }
+}
+
+impl TransferAuthorizationSetting {
+    /// Checks whether this setting allows at least what `required` allows on the same channel
+    pub fn covers(&self, required: &TransferAuthorizationSetting) -> bool {
+        self.source_port.eq(&required.source_port)
+            && self.source_channel.eq(&required.source_channel)
+            && coins_cover(&self.spend_limit, &required.spend_limit)
+            // an empty allow list permits any receiver
+            && (self.allow_list.is_empty()
+                || (!required.allow_list.is_empty()
+                    && is_subset(&self.allow_list, &required.allow_list)))
+    }
+}
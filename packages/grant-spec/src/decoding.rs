@@ -0,0 +1,319 @@
+use std::str::FromStr;
+
+use cosmos_sdk_proto::{
+    cosmos::{
+        authz::v1beta1::{
+            GenericAuthorization, Grant, GrantAuthorization, QueryGranteeGrantsResponse,
+            QueryGranterGrantsResponse, QueryGrantsResponse,
+        },
+        base::v1beta1::Coin as ProtoCoin,
+        staking::v1beta1::{
+            stake_authorization::{Policy, Validators},
+            AuthorizationType as ProtoStakeAuthorizationType, StakeAuthorization,
+        },
+    },
+    traits::Message,
+    Any,
+};
+use cosmwasm_std::{Addr, Binary, Coin, Timestamp, Uint128};
+
+use crate::{
+    errors::GrantSpecError,
+    grants::{
+        AuthorizationType, ContractExecutionAuthorizationFilter,
+        ContractExecutionAuthorizationLimit, ContractExecutionSetting, ExistingGrant,
+        StakeAuthorizationPolicy, StakeAuthorizationType, StakeAuthorizationValidators,
+        TransferAuthorizationSetting,
+    },
+    proto::{
+        AcceptedMessageKeysFilter, AcceptedMessagesFilter, Allocation, CombinedLimit,
+        ContractExecutionAuthorization, ContractGrant, MaxCallsLimit, MaxFundsLimit,
+        SendAuthorization, TransferAuthorization, ACCEPTED_MESSAGES_FILTER_TYPE_URL,
+        ACCEPTED_MESSAGE_KEYS_FILTER_TYPE_URL, ALLOW_ALL_MESSAGES_FILTER_TYPE_URL,
+        COMBINED_LIMIT_TYPE_URL, CONTRACT_EXECUTION_AUTHORIZATION_TYPE_URL,
+        GENERIC_AUTHORIZATION_TYPE_URL, MAX_CALLS_LIMIT_TYPE_URL, MAX_FUNDS_LIMIT_TYPE_URL,
+        SEND_AUTHORIZATION_TYPE_URL, STAKE_AUTHORIZATION_TYPE_URL, TRANSFER_AUTHORIZATION_TYPE_URL,
+    },
+};
+
+pub(crate) fn coin_from_proto(
+    ProtoCoin { denom, amount }: &ProtoCoin,
+) -> Result<Coin, GrantSpecError> {
+    Ok(Coin {
+        denom: denom.to_string(),
+        amount: Uint128::from_str(amount)?,
+    })
+}
+
+pub(crate) fn coins_from_proto(coins: &[ProtoCoin]) -> Result<Vec<Coin>, GrantSpecError> {
+    coins.iter().map(coin_from_proto).collect()
+}
+
+pub(crate) fn timestamp_from_proto(
+    prost_types::Timestamp { seconds, nanos }: &prost_types::Timestamp,
+) -> Timestamp {
+    Timestamp::from_seconds((*seconds).max(0) as u64).plus_nanos((*nanos).max(0) as u64)
+}
+
+/// Empty lists are how protobuf represents an unset repeated field
+fn non_empty<T>(items: Vec<T>) -> Option<Vec<T>> {
+    if items.is_empty() {
+        None
+    } else {
+        Some(items)
+    }
+}
+
+impl TryFrom<i32> for StakeAuthorizationType {
+    type Error = GrantSpecError;
+
+    fn try_from(authorization_type: i32) -> Result<Self, Self::Error> {
+        match ProtoStakeAuthorizationType::try_from(authorization_type) {
+            Ok(ProtoStakeAuthorizationType::Unspecified) => Ok(StakeAuthorizationType::Unspecified),
+            Ok(ProtoStakeAuthorizationType::Delegate) => Ok(StakeAuthorizationType::Delegate),
+            Ok(ProtoStakeAuthorizationType::Undelegate) => Ok(StakeAuthorizationType::Undelegate),
+            Ok(ProtoStakeAuthorizationType::Redelegate) => Ok(StakeAuthorizationType::Redelegate),
+            Err(_) => Err(GrantSpecError::UnsupportedStakeAuthorizationType(
+                authorization_type,
+            )),
+        }
+    }
+}
+
+impl ContractExecutionAuthorizationLimit {
+    /// Decodes one of the wasmd limit types
+    pub fn from_any(Any { type_url, value }: &Any) -> Result<Self, GrantSpecError> {
+        match type_url.as_str() {
+            MAX_CALLS_LIMIT_TYPE_URL => {
+                let MaxCallsLimit { remaining } = MaxCallsLimit::decode(value.as_slice())?;
+                Ok(ContractExecutionAuthorizationLimit::MaxCallsLimit { remaining })
+            }
+            MAX_FUNDS_LIMIT_TYPE_URL => {
+                let MaxFundsLimit { amounts } = MaxFundsLimit::decode(value.as_slice())?;
+                Ok(ContractExecutionAuthorizationLimit::MaxFundsLimit {
+                    amounts: coins_from_proto(&amounts)?,
+                })
+            }
+            COMBINED_LIMIT_TYPE_URL => {
+                let CombinedLimit {
+                    calls_remaining,
+                    amounts,
+                } = CombinedLimit::decode(value.as_slice())?;
+                Ok(ContractExecutionAuthorizationLimit::CombinedLimit {
+                    calls_remaining,
+                    amounts: coins_from_proto(&amounts)?,
+                })
+            }
+            _ => Err(GrantSpecError::UnsupportedContractExecutionLimit(
+                type_url.to_string(),
+            )),
+        }
+    }
+}
+
+impl ContractExecutionAuthorizationFilter {
+    /// Decodes one of the wasmd filter types
+    pub fn from_any(Any { type_url, value }: &Any) -> Result<Self, GrantSpecError> {
+        match type_url.as_str() {
+            ALLOW_ALL_MESSAGES_FILTER_TYPE_URL => {
+                Ok(ContractExecutionAuthorizationFilter::AllowAllMessagesFilter)
+            }
+            ACCEPTED_MESSAGE_KEYS_FILTER_TYPE_URL => {
+                let AcceptedMessageKeysFilter { keys } =
+                    AcceptedMessageKeysFilter::decode(value.as_slice())?;
+                Ok(ContractExecutionAuthorizationFilter::AcceptedMessageKeysFilter { keys })
+            }
+            ACCEPTED_MESSAGES_FILTER_TYPE_URL => {
+                let AcceptedMessagesFilter { messages } =
+                    AcceptedMessagesFilter::decode(value.as_slice())?;
+                Ok(
+                    ContractExecutionAuthorizationFilter::AcceptedMessagesFilter {
+                        messages: messages.into_iter().map(Binary::from).collect(),
+                    },
+                )
+            }
+            _ => Err(GrantSpecError::UnsupportedContractExecutionFilter(
+                type_url.to_string(),
+            )),
+        }
+    }
+}
+
+impl AuthorizationType {
+    /// Decodes the authorization held in a `Grant`.
+    ///
+    /// Empty spend limits and allow lists are treated as unset so that decoding mirrors `to_any`.
+    pub fn from_any(Any { type_url, value }: &Any) -> Result<Self, GrantSpecError> {
+        match type_url.as_str() {
+            GENERIC_AUTHORIZATION_TYPE_URL => {
+                let GenericAuthorization { msg } = GenericAuthorization::decode(value.as_slice())?;
+                Ok(AuthorizationType::GenericAuthorization { msg })
+            }
+            SEND_AUTHORIZATION_TYPE_URL => {
+                let SendAuthorization {
+                    spend_limit,
+                    allow_list,
+                } = SendAuthorization::decode(value.as_slice())?;
+                Ok(AuthorizationType::SendAuthorization {
+                    spend_limit: non_empty(coins_from_proto(&spend_limit)?),
+                    allow_list: non_empty(allow_list.into_iter().map(Addr::unchecked).collect()),
+                })
+            }
+            STAKE_AUTHORIZATION_TYPE_URL => {
+                let StakeAuthorization {
+                    max_tokens,
+                    authorization_type,
+                    validators,
+                } = StakeAuthorization::decode(value.as_slice())?;
+                Ok(AuthorizationType::StakeAuthorization {
+                    max_tokens: max_tokens.as_ref().map(coin_from_proto).transpose()?,
+                    authorization_type: authorization_type.try_into()?,
+                    validators: validators.map(|policy| match policy {
+                        Policy::AllowList(Validators { address }) => {
+                            StakeAuthorizationPolicy::AllowList(StakeAuthorizationValidators {
+                                address,
+                            })
+                        }
+                        Policy::DenyList(Validators { address }) => {
+                            StakeAuthorizationPolicy::DenyList(StakeAuthorizationValidators {
+                                address,
+                            })
+                        }
+                    }),
+                })
+            }
+            CONTRACT_EXECUTION_AUTHORIZATION_TYPE_URL => {
+                let ContractExecutionAuthorization { grants } =
+                    ContractExecutionAuthorization::decode(value.as_slice())?;
+                Ok(AuthorizationType::ContractExecutionAuthorization(
+                    grants
+                        .into_iter()
+                        .map(
+                            |ContractGrant {
+                                 contract,
+                                 limit,
+                                 filter,
+                             }| match (limit, filter) {
+                                (Some(limit), Some(filter)) => Ok(ContractExecutionSetting {
+                                    contract_addr: Addr::unchecked(contract),
+                                    limit: ContractExecutionAuthorizationLimit::from_any(&limit)?,
+                                    filter: ContractExecutionAuthorizationFilter::from_any(
+                                        &filter,
+                                    )?,
+                                }),
+                                _ => Err(GrantSpecError::IncompleteContractGrant(contract)),
+                            },
+                        )
+                        .collect::<Result<Vec<_>, GrantSpecError>>()?,
+                ))
+            }
+            TRANSFER_AUTHORIZATION_TYPE_URL => {
+                let TransferAuthorization { allocations } =
+                    TransferAuthorization::decode(value.as_slice())?;
+                Ok(AuthorizationType::TransferAuthorization(
+                    allocations
+                        .into_iter()
+                        .map(
+                            |Allocation {
+                                 source_port,
+                                 source_channel,
+                                 spend_limit,
+                                 allow_list,
+                             }| {
+                                Ok(TransferAuthorizationSetting {
+                                    source_port,
+                                    source_channel,
+                                    spend_limit: coins_from_proto(&spend_limit)?,
+                                    allow_list,
+                                })
+                            },
+                        )
+                        .collect::<Result<Vec<_>, GrantSpecError>>()?,
+                ))
+            }
+            _ => Err(GrantSpecError::UnsupportedAuthorization(
+                type_url.to_string(),
+            )),
+        }
+    }
+}
+
+impl ExistingGrant {
+    /// Decodes a grant from a `Grants` query. That response doesn't include the granter
+    /// and grantee so they need to be the ones that were queried for.
+    pub fn from_grant(
+        granter: &Addr,
+        grantee: &Addr,
+        Grant {
+            authorization,
+            expiration,
+        }: &Grant,
+    ) -> Result<Self, GrantSpecError> {
+        Ok(ExistingGrant {
+            grant_type: AuthorizationType::from_any(
+                authorization
+                    .as_ref()
+                    .ok_or(GrantSpecError::MissingAuthorization)?,
+            )?,
+            granter: granter.clone(),
+            grantee: grantee.clone(),
+            expiration: expiration.as_ref().map(timestamp_from_proto),
+        })
+    }
+
+    /// Decodes a grant from a `GranterGrants` or `GranteeGrants` query
+    pub fn from_grant_authorization(
+        GrantAuthorization {
+            granter,
+            grantee,
+            authorization,
+            expiration,
+        }: &GrantAuthorization,
+    ) -> Result<Self, GrantSpecError> {
+        Ok(ExistingGrant {
+            grant_type: AuthorizationType::from_any(
+                authorization
+                    .as_ref()
+                    .ok_or(GrantSpecError::MissingAuthorization)?,
+            )?,
+            granter: Addr::unchecked(granter),
+            grantee: Addr::unchecked(grantee),
+            expiration: expiration.as_ref().map(timestamp_from_proto),
+        })
+    }
+}
+
+/// Decodes all of the grants in a `/cosmos.authz.v1beta1.Query/Grants` response
+pub fn decode_grants_response(
+    granter: &Addr,
+    grantee: &Addr,
+    response: &QueryGrantsResponse,
+) -> Result<Vec<ExistingGrant>, GrantSpecError> {
+    response
+        .grants
+        .iter()
+        .map(|grant| ExistingGrant::from_grant(granter, grantee, grant))
+        .collect()
+}
+
+/// Decodes all of the grants in a `/cosmos.authz.v1beta1.Query/GranterGrants` response
+pub fn decode_granter_grants_response(
+    response: &QueryGranterGrantsResponse,
+) -> Result<Vec<ExistingGrant>, GrantSpecError> {
+    response
+        .grants
+        .iter()
+        .map(ExistingGrant::from_grant_authorization)
+        .collect()
+}
+
+/// Decodes all of the grants in a `/cosmos.authz.v1beta1.Query/GranteeGrants` response
+pub fn decode_grantee_grants_response(
+    response: &QueryGranteeGrantsResponse,
+) -> Result<Vec<ExistingGrant>, GrantSpecError> {
+    response
+        .grants
+        .iter()
+        .map(ExistingGrant::from_grant_authorization)
+        .collect()
+}
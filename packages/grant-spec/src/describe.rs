@@ -0,0 +1,538 @@
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{Addr, Binary, Coin, Timestamp};
+
+use crate::{
+    errors::GrantSpecError,
+    grantable_trait::dedupe_grant_reqs,
+    grants::{
+        AllowanceType, AuthorizationType, ContractExecutionAuthorizationFilter,
+        ContractExecutionSetting, GrantExpiration, GrantRequirement, StakeAuthorizationPolicy,
+        StakeAuthorizationType, StakeAuthorizationValidators, TransferAuthorizationSetting,
+    },
+    helpers::message_keys,
+};
+
+/// A single human readable line describing what a requirement allows
+#[cw_serde]
+pub struct GrantDescription {
+    /// identifies the sentence so that a UI can render its own localized version of it
+    pub key: String,
+    /// the values that are filled into the sentence
+    pub params: Vec<DescriptionParam>,
+    /// the rendered english sentence
+    pub text: String,
+}
+
+#[cw_serde]
+pub struct DescriptionParam {
+    pub name: String,
+    pub value: DescriptionValue,
+}
+
+/// Typed values so that a UI can format addresses, amounts and dates itself
+#[cw_serde]
+pub enum DescriptionValue {
+    Address(String),
+    Addresses(Vec<String>),
+    Coins(Vec<Coin>),
+    Count(u64),
+    /// a length of time in seconds
+    Duration(u64),
+    Expiration(GrantExpiration),
+    Text(String),
+    Texts(Vec<String>),
+}
+
+impl GrantDescription {
+    fn new(key: &str, text: String) -> Self {
+        GrantDescription {
+            key: key.to_string(),
+            params: vec![],
+            text,
+        }
+    }
+
+    fn param(mut self, name: &str, value: DescriptionValue) -> Self {
+        self.params.push(DescriptionParam {
+            name: name.to_string(),
+            value,
+        });
+        self
+    }
+
+    fn with_expiration(self, expiration: &GrantExpiration) -> Self {
+        let text = match expiration {
+            GrantExpiration::Never => format!("{} with no expiration", self.text),
+            GrantExpiration::AtTime(timestamp) => {
+                format!("{} until {}", self.text, format_date(timestamp))
+            }
+            GrantExpiration::SecondsFromNow(seconds) => {
+                format!("{} for {}", self.text, format_duration(*seconds))
+            }
+        };
+
+        GrantDescription { text, ..self }
+            .param("expiration", DescriptionValue::Expiration(*expiration))
+    }
+}
+
+/// Dedupes the requirements and describes the messages the granter will be asked to sign
+pub fn describe_grant_reqs(
+    grants: Vec<GrantRequirement>,
+) -> Result<Vec<GrantDescription>, GrantSpecError> {
+    Ok(dedupe_grant_reqs(grants)?
+        .iter()
+        .flat_map(GrantRequirement::describe)
+        .collect())
+}
+
+/// Renders the descriptions as plain text, one line per description
+pub fn descriptions_to_text(descriptions: &[GrantDescription]) -> String {
+    descriptions
+        .iter()
+        .map(|description| description.text.as_str())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+impl GrantRequirement {
+    /// Describes the requirement, contract execution authorizations get a line per contract
+    pub fn describe(&self) -> Vec<GrantDescription> {
+        match self {
+            GrantRequirement::GrantSpec {
+                grant_type,
+                grantee,
+                expiration,
+                ..
+            } => grant_type
+                .describe(grantee)
+                .into_iter()
+                .map(|description| description.with_expiration(expiration))
+                .collect(),
+            GrantRequirement::FeeAllowanceSpec {
+                allowance,
+                grantee,
+                expiration,
+                ..
+            } => vec![allowance.describe(grantee).with_expiration(expiration)],
+            GrantRequirement::ContractExec {
+                contract_addr, msg, ..
+            } => vec![describe_contract_exec(contract_addr, msg)],
+        }
+    }
+}
+
+impl AuthorizationType {
+    /// Describes what the grantee is allowed to do, without the expiration of the grant
+    pub fn describe(&self, grantee: &Addr) -> Vec<GrantDescription> {
+        match self {
+            AuthorizationType::GenericAuthorization { msg } => {
+                let text = match msg_action(msg) {
+                    Some(action) => format!("Allows {grantee} to {action}"),
+                    None => format!("Allows {grantee} to use {msg} on your behalf"),
+                };
+
+                vec![GrantDescription::new("authz.generic", text)
+                    .param("grantee", address(grantee))
+                    .param("msg_type_url", DescriptionValue::Text(msg.to_string()))]
+            }
+            AuthorizationType::SendAuthorization {
+                spend_limit,
+                allow_list,
+            } => {
+                let text = format!(
+                    "Allows {grantee} to send {}{}",
+                    spend_limit
+                        .as_ref()
+                        .map_or("any amount of your tokens".to_string(), |spend_limit| {
+                            format!("up to {}", format_coins(spend_limit))
+                        }),
+                    allow_list
+                        .as_ref()
+                        .map_or(" to any address".to_string(), |allow_list| {
+                            format!(" to {}", count_of(allow_list.len(), "address", "addresses"))
+                        })
+                );
+
+                let mut description =
+                    GrantDescription::new("authz.send", text).param("grantee", address(grantee));
+                if let Some(spend_limit) = spend_limit {
+                    description = description
+                        .param("spend_limit", DescriptionValue::Coins(spend_limit.clone()));
+                }
+                if let Some(allow_list) = allow_list {
+                    description = description.param("allow_list", addresses(allow_list));
+                }
+
+                vec![description]
+            }
+            AuthorizationType::StakeAuthorization {
+                max_tokens,
+                authorization_type,
+                validators,
+            } => {
+                let action = match authorization_type {
+                    StakeAuthorizationType::Undelegate => "undelegate",
+                    StakeAuthorizationType::Redelegate => "redelegate",
+                    StakeAuthorizationType::Unspecified | StakeAuthorizationType::Delegate => {
+                        "delegate"
+                    }
+                };
+                let validators_text = match validators {
+                    Some(StakeAuthorizationPolicy::AllowList(StakeAuthorizationValidators {
+                        address,
+                    })) => format!(" with only {}", address.join(", ")),
+                    Some(StakeAuthorizationPolicy::DenyList(StakeAuthorizationValidators {
+                        address,
+                    })) => format!(" with any validator except {}", address.join(", ")),
+                    None => " with any validator".to_string(),
+                };
+                let text = format!(
+                    "Allows {grantee} to {action} {}{validators_text}",
+                    max_tokens.as_ref().map_or(
+                        "any amount of your tokens".to_string(),
+                        |max_tokens| {
+                            format!("up to {}", format_coins(std::slice::from_ref(max_tokens)))
+                        }
+                    ),
+                );
+
+                let mut description = GrantDescription::new(&format!("authz.stake.{action}"), text)
+                    .param("grantee", address(grantee));
+                if let Some(max_tokens) = max_tokens {
+                    description = description.param(
+                        "max_tokens",
+                        DescriptionValue::Coins(vec![max_tokens.clone()]),
+                    );
+                }
+                description = match validators {
+                    Some(StakeAuthorizationPolicy::AllowList(StakeAuthorizationValidators {
+                        address,
+                    })) => description
+                        .param("allow_list", DescriptionValue::Addresses(address.clone())),
+                    Some(StakeAuthorizationPolicy::DenyList(StakeAuthorizationValidators {
+                        address,
+                    })) => {
+                        description.param("deny_list", DescriptionValue::Addresses(address.clone()))
+                    }
+                    None => description,
+                };
+
+                vec![description]
+            }
+            AuthorizationType::ContractExecutionAuthorization(settings) => settings
+                .iter()
+                .map(|setting| setting.describe(grantee))
+                .collect(),
+            AuthorizationType::TransferAuthorization(settings) => settings
+                .iter()
+                .map(|setting| setting.describe(grantee))
+                .collect(),
+        }
+    }
+}
+
+impl ContractExecutionSetting {
+    pub fn describe(&self, grantee: &Addr) -> GrantDescription {
+        let (calls, funds) = self.limit.calls_and_funds();
+
+        let messages_text = match &self.filter {
+            ContractExecutionAuthorizationFilter::AllowAllMessagesFilter => {
+                "any message".to_string()
+            }
+            ContractExecutionAuthorizationFilter::AcceptedMessageKeysFilter { keys } => {
+                format!("{} messages", keys.join(", "))
+            }
+            ContractExecutionAuthorizationFilter::AcceptedMessagesFilter { messages } => {
+                count_of(messages.len(), "specific message", "specific messages")
+            }
+        };
+        let calls_text = match calls {
+            Some(calls) if calls.ne(&u64::MAX) => {
+                format!(" up to {}", count_of(calls as usize, "time", "times"))
+            }
+            _ => "".to_string(),
+        };
+        let funds_text = if funds.is_empty() {
+            "".to_string()
+        } else {
+            format!(", sending up to {}", format_coins(&funds))
+        };
+        let text = format!(
+            "Allows {grantee} to execute {messages_text} on contract {}{calls_text}{funds_text}",
+            self.contract_addr
+        );
+
+        let mut description = GrantDescription::new("authz.contract_execution", text)
+            .param("grantee", address(grantee))
+            .param("contract", address(&self.contract_addr));
+        match &self.filter {
+            ContractExecutionAuthorizationFilter::AllowAllMessagesFilter => {}
+            ContractExecutionAuthorizationFilter::AcceptedMessageKeysFilter { keys } => {
+                description = description.param("keys", DescriptionValue::Texts(keys.clone()));
+            }
+            ContractExecutionAuthorizationFilter::AcceptedMessagesFilter { messages } => {
+                description = description.param(
+                    "messages",
+                    DescriptionValue::Texts(
+                        messages
+                            .iter()
+                            .map(|msg| String::from_utf8_lossy(msg.as_slice()).to_string())
+                            .collect(),
+                    ),
+                );
+            }
+        }
+        if let Some(calls) = calls.filter(|calls| calls.ne(&u64::MAX)) {
+            description = description.param("calls", DescriptionValue::Count(calls));
+        }
+        if !funds.is_empty() {
+            description = description.param("funds", DescriptionValue::Coins(funds));
+        }
+
+        description
+    }
+}
+
+impl TransferAuthorizationSetting {
+    pub fn describe(&self, grantee: &Addr) -> GrantDescription {
+        let receivers_text = if self.allow_list.is_empty() {
+            "any address".to_string()
+        } else {
+            count_of(self.allow_list.len(), "address", "addresses")
+        };
+        let text = format!(
+            "Allows {grantee} to send up to {} over {}/{} to {receivers_text}",
+            format_coins(&self.spend_limit),
+            self.source_port,
+            self.source_channel
+        );
+
+        let mut description = GrantDescription::new("authz.transfer", text)
+            .param("grantee", address(grantee))
+            .param(
+                "source_port",
+                DescriptionValue::Text(self.source_port.clone()),
+            )
+            .param(
+                "source_channel",
+                DescriptionValue::Text(self.source_channel.clone()),
+            )
+            .param(
+                "spend_limit",
+                DescriptionValue::Coins(self.spend_limit.clone()),
+            );
+        if !self.allow_list.is_empty() {
+            description = description.param(
+                "allow_list",
+                DescriptionValue::Addresses(self.allow_list.clone()),
+            );
+        }
+
+        description
+    }
+}
+
+impl AllowanceType {
+    /// Describes what fees the grantee may pay, without the expiration of the allowance
+    pub fn describe(&self, grantee: &Addr) -> GrantDescription {
+        let (allowance, allowed_messages) = match self {
+            AllowanceType::AllowedMsgAllowance {
+                allowance,
+                allowed_messages,
+            } => (allowance.as_ref(), Some(allowed_messages)),
+            allowance => (allowance, None),
+        };
+
+        let (spend_limit, period) = match allowance {
+            AllowanceType::BasicAllowance { spend_limit } => (spend_limit, None),
+            AllowanceType::PeriodicAllowance {
+                spend_limit,
+                period,
+                period_spend_limit,
+            } => (spend_limit, Some((period, period_spend_limit))),
+            // allowed message allowances can't be nested
+            AllowanceType::AllowedMsgAllowance { .. } => (&None, None),
+        };
+
+        let text = format!(
+            "Allows {grantee} to pay fees from your account{}{}{}",
+            spend_limit.as_ref().map_or("".to_string(), |spend_limit| {
+                format!(" with up to {}", format_coins(spend_limit))
+            }),
+            period.map_or("".to_string(), |(period, period_spend_limit)| {
+                format!(
+                    ", at most {} every {}",
+                    format_coins(period_spend_limit),
+                    format_duration(*period)
+                )
+            }),
+            allowed_messages.map_or("".to_string(), |allowed_messages| {
+                format!(" for {} transactions", allowed_messages.join(", "))
+            })
+        );
+
+        let mut description =
+            GrantDescription::new("feegrant.allowance", text).param("grantee", address(grantee));
+        if let Some(spend_limit) = spend_limit {
+            description =
+                description.param("spend_limit", DescriptionValue::Coins(spend_limit.clone()));
+        }
+        if let Some((period, period_spend_limit)) = period {
+            description = description
+                .param("period", DescriptionValue::Duration(*period))
+                .param(
+                    "period_spend_limit",
+                    DescriptionValue::Coins(period_spend_limit.clone()),
+                );
+        }
+        if let Some(allowed_messages) = allowed_messages {
+            description = description.param(
+                "allowed_messages",
+                DescriptionValue::Texts(allowed_messages.clone()),
+            );
+        }
+
+        description
+    }
+}
+
+fn describe_contract_exec(contract_addr: &Addr, msg: &Binary) -> GrantDescription {
+    let action = message_keys(msg)
+        .filter(|keys| !keys.is_empty())
+        .map(|keys| keys.join(", "))
+        .unwrap_or_else(|| String::from_utf8_lossy(msg.as_slice()).to_string());
+
+    GrantDescription::new(
+        "contract_exec",
+        format!("Executes {action} on contract {contract_addr}"),
+    )
+    .param("contract", address(contract_addr))
+    .param("action", DescriptionValue::Text(action))
+}
+
+/// The action behind the most common messages that are granted with a generic authorization
+fn msg_action(msg_type_url: &str) -> Option<&'static str> {
+    match msg_type_url {
+        "/cosmos.distribution.v1beta1.MsgWithdrawDelegatorReward" => {
+            Some("withdraw your staking rewards")
+        }
+        "/cosmos.distribution.v1beta1.MsgSetWithdrawAddress" => {
+            Some("change the address your staking rewards are withdrawn to")
+        }
+        "/cosmos.distribution.v1beta1.MsgWithdrawValidatorCommission" => {
+            Some("withdraw your validator commission")
+        }
+        "/cosmos.staking.v1beta1.MsgDelegate" => Some("delegate your tokens"),
+        "/cosmos.staking.v1beta1.MsgUndelegate" => Some("undelegate your tokens"),
+        "/cosmos.staking.v1beta1.MsgBeginRedelegate" => Some("redelegate your tokens"),
+        "/cosmos.bank.v1beta1.MsgSend" => Some("send any of your tokens"),
+        "/cosmos.gov.v1beta1.MsgVote" | "/cosmos.gov.v1.MsgVote" => {
+            Some("vote on governance proposals")
+        }
+        "/cosmwasm.wasm.v1.MsgExecuteContract" => Some("execute any contract"),
+        "/ibc.applications.transfer.v1.MsgTransfer" => Some("send any of your tokens over IBC"),
+        _ => None,
+    }
+}
+
+fn address(addr: &Addr) -> DescriptionValue {
+    DescriptionValue::Address(addr.to_string())
+}
+
+fn addresses(addrs: &[Addr]) -> DescriptionValue {
+    DescriptionValue::Addresses(addrs.iter().map(Addr::to_string).collect())
+}
+
+fn count_of(count: usize, singular: &str, plural: &str) -> String {
+    if count == 1 {
+        format!("{count} {singular}")
+    } else {
+        format!("{count} {plural}")
+    }
+}
+
+/// How a base denom is displayed, such as `ujuno` as `JUNO` with an exponent of 6,
+/// usually taken from the chain's denom metadata
+#[cw_serde]
+pub struct DenomDisplay {
+    pub denom: String,
+    pub display: String,
+    pub exponent: u32,
+}
+
+/// Formats coins as their raw amount and base denom, such as `50000000 ujuno`.
+/// The decimals of a denom can't be known without its metadata, see `format_coins_with_metadata`
+pub fn format_coins(coins: &[Coin]) -> String {
+    format_coins_with_metadata(coins, &[])
+}
+
+/// Formats coins in their display denom where metadata is given for it, `50000000ujuno` becoming
+/// `50 JUNO` for a display of `JUNO` with an exponent of 6. Other denoms are left as they are
+pub fn format_coins_with_metadata(coins: &[Coin], metadata: &[DenomDisplay]) -> String {
+    coins
+        .iter()
+        .map(|coin| format_coin(coin, metadata))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn format_coin(Coin { denom, amount }: &Coin, metadata: &[DenomDisplay]) -> String {
+    // limits that are set to the max are effectively unlimited
+    if amount.u128() == u128::MAX {
+        return format!("any amount of {denom}");
+    }
+
+    let (display, exponent) = match metadata.iter().find(|display| display.denom.eq(denom)) {
+        Some(DenomDisplay {
+            display, exponent, ..
+        }) => (display, *exponent),
+        None => return format!("{amount} {denom}"),
+    };
+
+    let unit = 10u128.pow(exponent);
+    let whole = amount.u128() / unit;
+    let fraction = amount.u128() % unit;
+    if fraction == 0 {
+        format!("{whole} {display}")
+    } else {
+        let fraction = format!("{fraction:0width$}", width = exponent as usize);
+        format!("{whole}.{} {display}", fraction.trim_end_matches('0'))
+    }
+}
+
+/// Formats a timestamp as a `YYYY-MM-DD` date in UTC
+pub fn format_date(timestamp: &Timestamp) -> String {
+    let (year, month, day) = civil_from_days((timestamp.seconds() / 86_400) as i64);
+    format!("{year:04}-{month:02}-{day:02}")
+}
+
+fn format_duration(seconds: u64) -> String {
+    match seconds {
+        s if s % 86_400 == 0 => count_of((s / 86_400) as usize, "day", "days"),
+        s if s % 3_600 == 0 => count_of((s / 3_600) as usize, "hour", "hours"),
+        s if s % 60 == 0 => count_of((s / 60) as usize, "minute", "minutes"),
+        s => count_of(s as usize, "second", "seconds"),
+    }
+}
+
+/// Converts days since the unix epoch into a (year, month, day) date,
+/// see http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * month_index + 2) / 5 + 1) as u32;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    } as u32;
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    (year, month, day)
+}
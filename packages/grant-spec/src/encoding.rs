@@ -0,0 +1,438 @@
+use cosmos_sdk_proto::{
+    cosmos::{
+        authz::v1beta1::{GenericAuthorization, Grant, MsgGrant, MsgRevoke},
+        base::v1beta1::Coin as ProtoCoin,
+        feegrant::v1beta1::{
+            AllowedMsgAllowance, BasicAllowance, MsgGrantAllowance, MsgRevokeAllowance,
+            PeriodicAllowance,
+        },
+        staking::v1beta1::{
+            stake_authorization::{Policy, Validators},
+            AuthorizationType as ProtoStakeAuthorizationType, StakeAuthorization,
+        },
+    },
+    cosmwasm::wasm::v1::MsgExecuteContract,
+    traits::Message,
+    Any,
+};
+use cosmwasm_std::{Binary, Coin, CosmosMsg, Timestamp};
+use itertools::Itertools;
+
+use crate::{
+    errors::GrantSpecError,
+    grantable_trait::dedupe_grant_reqs,
+    grants::{
+        AllowanceType, AuthorizationType, ContractExecutionAuthorizationFilter,
+        ContractExecutionAuthorizationLimit, ContractExecutionSetting, GrantRequirement,
+        RevokeRequirement, StakeAuthorizationPolicy, StakeAuthorizationType,
+        StakeAuthorizationValidators, TransferAuthorizationSetting,
+    },
+    proto::{
+        AcceptedMessageKeysFilter, AcceptedMessagesFilter, Allocation, AllowAllMessagesFilter,
+        CombinedLimit, ContractExecutionAuthorization, ContractGrant, MaxCallsLimit, MaxFundsLimit,
+        SendAuthorization, TransferAuthorization, ACCEPTED_MESSAGES_FILTER_TYPE_URL,
+        ACCEPTED_MESSAGE_KEYS_FILTER_TYPE_URL, ALLOWED_MSG_ALLOWANCE_TYPE_URL,
+        ALLOW_ALL_MESSAGES_FILTER_TYPE_URL, BASIC_ALLOWANCE_TYPE_URL, COMBINED_LIMIT_TYPE_URL,
+        CONTRACT_EXECUTION_AUTHORIZATION_TYPE_URL, GENERIC_AUTHORIZATION_TYPE_URL,
+        MAX_CALLS_LIMIT_TYPE_URL, MAX_FUNDS_LIMIT_TYPE_URL, MSG_EXECUTE_CONTRACT_TYPE_URL,
+        MSG_GRANT_ALLOWANCE_TYPE_URL, MSG_GRANT_TYPE_URL, MSG_REVOKE_ALLOWANCE_TYPE_URL,
+        MSG_REVOKE_TYPE_URL, PERIODIC_ALLOWANCE_TYPE_URL, SEND_AUTHORIZATION_TYPE_URL,
+        STAKE_AUTHORIZATION_TYPE_URL, TRANSFER_AUTHORIZATION_TYPE_URL,
+    },
+};
+
+/// Wraps an encoded protobuf message in an Any with the given type url
+pub(crate) fn encode_any<M: Message>(type_url: &str, msg: &M) -> Any {
+    Any {
+        type_url: type_url.to_string(),
+        value: msg.encode_to_vec(),
+    }
+}
+
+pub(crate) fn proto_coin(Coin { denom, amount }: &Coin) -> ProtoCoin {
+    ProtoCoin {
+        denom: denom.to_string(),
+        amount: amount.to_string(),
+    }
+}
+
+pub(crate) fn proto_coins(coins: &[Coin]) -> Vec<ProtoCoin> {
+    coins.iter().map(proto_coin).collect()
+}
+
+pub(crate) fn proto_timestamp(timestamp: &Timestamp) -> prost_types::Timestamp {
+    prost_types::Timestamp {
+        seconds: timestamp.seconds() as i64,
+        nanos: timestamp.subsec_nanos() as i32,
+    }
+}
+
+impl From<&StakeAuthorizationType> for ProtoStakeAuthorizationType {
+    fn from(authorization_type: &StakeAuthorizationType) -> Self {
+        match authorization_type {
+            StakeAuthorizationType::Unspecified => ProtoStakeAuthorizationType::Unspecified,
+            StakeAuthorizationType::Delegate => ProtoStakeAuthorizationType::Delegate,
+            StakeAuthorizationType::Undelegate => ProtoStakeAuthorizationType::Undelegate,
+            StakeAuthorizationType::Redelegate => ProtoStakeAuthorizationType::Redelegate,
+        }
+    }
+}
+
+impl ContractExecutionAuthorizationLimit {
+    /// Encodes the limit as the wasmd limit type it represents
+    pub fn to_any(&self) -> Any {
+        match self {
+            ContractExecutionAuthorizationLimit::MaxCallsLimit { remaining } => encode_any(
+                MAX_CALLS_LIMIT_TYPE_URL,
+                &MaxCallsLimit {
+                    remaining: *remaining,
+                },
+            ),
+            ContractExecutionAuthorizationLimit::MaxFundsLimit { amounts } => encode_any(
+                MAX_FUNDS_LIMIT_TYPE_URL,
+                &MaxFundsLimit {
+                    amounts: proto_coins(amounts),
+                },
+            ),
+            ContractExecutionAuthorizationLimit::CombinedLimit {
+                calls_remaining,
+                amounts,
+            } => encode_any(
+                COMBINED_LIMIT_TYPE_URL,
+                &CombinedLimit {
+                    calls_remaining: *calls_remaining,
+                    amounts: proto_coins(amounts),
+                },
+            ),
+        }
+    }
+}
+
+impl ContractExecutionAuthorizationFilter {
+    /// Encodes the filter as the wasmd filter type it represents
+    pub fn to_any(&self) -> Any {
+        match self {
+            ContractExecutionAuthorizationFilter::AllowAllMessagesFilter => encode_any(
+                ALLOW_ALL_MESSAGES_FILTER_TYPE_URL,
+                &AllowAllMessagesFilter {},
+            ),
+            ContractExecutionAuthorizationFilter::AcceptedMessageKeysFilter { keys } => encode_any(
+                ACCEPTED_MESSAGE_KEYS_FILTER_TYPE_URL,
+                &AcceptedMessageKeysFilter { keys: keys.clone() },
+            ),
+            ContractExecutionAuthorizationFilter::AcceptedMessagesFilter { messages } => {
+                encode_any(
+                    ACCEPTED_MESSAGES_FILTER_TYPE_URL,
+                    &AcceptedMessagesFilter {
+                        messages: messages.iter().map(|msg| msg.to_vec()).collect(),
+                    },
+                )
+            }
+        }
+    }
+}
+
+impl AuthorizationType {
+    /// Encodes the authorization as the Any that goes into a MsgGrant's `Grant`.
+    ///
+    /// A SendAuthorization with neither a spend limit nor an allow list cannot be expressed
+    /// natively so it is encoded as a GenericAuthorization for MsgSend instead.
+    /// An allow list without a spend limit has no such equivalent and errors since the chain
+    /// rejects a SendAuthorization with an empty spend limit.
+    pub fn to_any(&self) -> Result<Any, GrantSpecError> {
+        Ok(match self {
+            AuthorizationType::GenericAuthorization { msg } => encode_any(
+                GENERIC_AUTHORIZATION_TYPE_URL,
+                &GenericAuthorization { msg: msg.clone() },
+            ),
+            AuthorizationType::SendAuthorization {
+                spend_limit: None,
+                allow_list: None,
+            } => encode_any(
+                GENERIC_AUTHORIZATION_TYPE_URL,
+                &GenericAuthorization {
+                    msg: self.msg_type_url(),
+                },
+            ),
+            AuthorizationType::SendAuthorization {
+                spend_limit: None,
+                allow_list: Some(_),
+            } => return Err(GrantSpecError::AllowListWithoutSpendLimit),
+            AuthorizationType::SendAuthorization {
+                spend_limit,
+                allow_list,
+            } => encode_any(
+                SEND_AUTHORIZATION_TYPE_URL,
+                &SendAuthorization {
+                    spend_limit: spend_limit.as_deref().map(proto_coins).unwrap_or_default(),
+                    allow_list: allow_list
+                        .iter()
+                        .flatten()
+                        .map(|addr| addr.to_string())
+                        .collect(),
+                },
+            ),
+            AuthorizationType::StakeAuthorization {
+                max_tokens,
+                authorization_type,
+                validators,
+            } => encode_any(
+                STAKE_AUTHORIZATION_TYPE_URL,
+                &StakeAuthorization {
+                    max_tokens: max_tokens.as_ref().map(proto_coin),
+                    authorization_type: ProtoStakeAuthorizationType::from(authorization_type)
+                        .into(),
+                    validators: validators.as_ref().map(|policy| match policy {
+                        StakeAuthorizationPolicy::AllowList(StakeAuthorizationValidators {
+                            address,
+                        }) => Policy::AllowList(Validators {
+                            address: address.clone(),
+                        }),
+                        StakeAuthorizationPolicy::DenyList(StakeAuthorizationValidators {
+                            address,
+                        }) => Policy::DenyList(Validators {
+                            address: address.clone(),
+                        }),
+                    }),
+                },
+            ),
+            AuthorizationType::ContractExecutionAuthorization(settings) => encode_any(
+                CONTRACT_EXECUTION_AUTHORIZATION_TYPE_URL,
+                &ContractExecutionAuthorization {
+                    grants: settings
+                        .iter()
+                        .map(
+                            |ContractExecutionSetting {
+                                 contract_addr,
+                                 limit,
+                                 filter,
+                             }| ContractGrant {
+                                contract: contract_addr.to_string(),
+                                limit: Some(limit.to_any()),
+                                filter: Some(filter.to_any()),
+                            },
+                        )
+                        .collect(),
+                },
+            ),
+            AuthorizationType::TransferAuthorization(settings) => encode_any(
+                TRANSFER_AUTHORIZATION_TYPE_URL,
+                &TransferAuthorization {
+                    allocations: settings
+                        .iter()
+                        .map(
+                            |TransferAuthorizationSetting {
+                                 source_port,
+                                 source_channel,
+                                 spend_limit,
+                                 allow_list,
+                             }| Allocation {
+                                source_port: source_port.clone(),
+                                source_channel: source_channel.clone(),
+                                spend_limit: proto_coins(spend_limit),
+                                allow_list: allow_list.clone(),
+                            },
+                        )
+                        .collect(),
+                },
+            ),
+        })
+    }
+}
+
+impl AllowanceType {
+    /// Encodes the allowance as the feegrant allowance type it represents.
+    /// The expiration is set on the basic allowance that every allowance is built on,
+    /// None leaving the allowance without an expiration.
+    pub fn to_any(&self, expiration: Option<&Timestamp>) -> Any {
+        let basic_allowance = |spend_limit: &Option<Vec<Coin>>| BasicAllowance {
+            spend_limit: spend_limit.as_deref().map(proto_coins).unwrap_or_default(),
+            expiration: expiration.map(proto_timestamp),
+        };
+
+        match self {
+            AllowanceType::BasicAllowance { spend_limit } => {
+                encode_any(BASIC_ALLOWANCE_TYPE_URL, &basic_allowance(spend_limit))
+            }
+            // the period starts once the allowance is first used which also fills up what can be spent
+            AllowanceType::PeriodicAllowance {
+                spend_limit,
+                period,
+                period_spend_limit,
+            } => encode_any(
+                PERIODIC_ALLOWANCE_TYPE_URL,
+                &PeriodicAllowance {
+                    basic: Some(basic_allowance(spend_limit)),
+                    period: Some(prost_types::Duration {
+                        seconds: *period as i64,
+                        nanos: 0,
+                    }),
+                    period_spend_limit: proto_coins(period_spend_limit),
+                    period_can_spend: proto_coins(period_spend_limit),
+                    period_reset: None,
+                },
+            ),
+            AllowanceType::AllowedMsgAllowance {
+                allowance,
+                allowed_messages,
+            } => encode_any(
+                ALLOWED_MSG_ALLOWANCE_TYPE_URL,
+                &AllowedMsgAllowance {
+                    allowance: Some(allowance.to_any(expiration)),
+                    allowed_messages: allowed_messages.clone(),
+                },
+            ),
+        }
+    }
+}
+
+impl GrantRequirement {
+    /// Encodes the requirement as the message the granter needs to sign.
+    /// Grant specs become a `MsgGrant`, fee allowances a `MsgGrantAllowance`
+    /// and contract executions a `MsgExecuteContract`.
+    /// Relative expirations have to be resolved beforehand since there is no block time to resolve them against.
+    pub fn to_any(&self) -> Result<Any, GrantSpecError> {
+        Ok(match self {
+            GrantRequirement::GrantSpec {
+                grant_type,
+                granter,
+                grantee,
+                expiration,
+            } => encode_any(
+                MSG_GRANT_TYPE_URL,
+                &MsgGrant {
+                    granter: granter.to_string(),
+                    grantee: grantee.to_string(),
+                    grant: Some(Grant {
+                        authorization: Some(grant_type.to_any()?),
+                        expiration: expiration.timestamp()?.as_ref().map(proto_timestamp),
+                    }),
+                },
+            ),
+            GrantRequirement::ContractExec {
+                contract_addr,
+                msg,
+                sender,
+            } => encode_any(
+                MSG_EXECUTE_CONTRACT_TYPE_URL,
+                &MsgExecuteContract {
+                    sender: sender.to_string(),
+                    contract: contract_addr.to_string(),
+                    msg: msg.to_vec(),
+                    funds: vec![],
+                },
+            ),
+            GrantRequirement::FeeAllowanceSpec {
+                allowance,
+                granter,
+                grantee,
+                expiration,
+            } => encode_any(
+                MSG_GRANT_ALLOWANCE_TYPE_URL,
+                &MsgGrantAllowance {
+                    granter: granter.to_string(),
+                    grantee: grantee.to_string(),
+                    allowance: Some(allowance.to_any(expiration.timestamp()?.as_ref())),
+                },
+            ),
+        })
+    }
+
+    /// Encodes the requirement as a stargate message
+    pub fn to_cosmos_msg(&self) -> Result<CosmosMsg, GrantSpecError> {
+        let Any { type_url, value } = self.to_any()?;
+
+        Ok(CosmosMsg::Stargate {
+            type_url,
+            value: Binary::from(value),
+        })
+    }
+}
+
+/// Dedupes the given requirements and encodes each of them so that the result is the full
+/// list of messages the granter needs to sign
+pub fn grant_reqs_to_anys(grants: Vec<GrantRequirement>) -> Result<Vec<Any>, GrantSpecError> {
+    dedupe_grant_reqs(grants)?
+        .iter()
+        .map(GrantRequirement::to_any)
+        .collect()
+}
+
+/// Dedupes the given requirements and encodes each of them as a stargate message
+pub fn grant_reqs_to_msgs(grants: Vec<GrantRequirement>) -> Result<Vec<CosmosMsg>, GrantSpecError> {
+    dedupe_grant_reqs(grants)?
+        .iter()
+        .map(GrantRequirement::to_cosmos_msg)
+        .collect()
+}
+
+impl RevokeRequirement {
+    /// Encodes the requirement as the message the granter needs to sign.
+    /// Revoke specs become a `MsgRevoke`, fee allowance revokes a `MsgRevokeAllowance`
+    /// and contract executions a `MsgExecuteContract`.
+    pub fn to_any(&self) -> Any {
+        match self {
+            RevokeRequirement::RevokeSpec {
+                grant_type,
+                granter,
+                grantee,
+            } => encode_any(
+                MSG_REVOKE_TYPE_URL,
+                &MsgRevoke {
+                    granter: granter.to_string(),
+                    grantee: grantee.to_string(),
+                    msg_type_url: grant_type.to_string(),
+                },
+            ),
+            RevokeRequirement::ContractExec {
+                contract_addr,
+                msg,
+                sender,
+            } => encode_any(
+                MSG_EXECUTE_CONTRACT_TYPE_URL,
+                &MsgExecuteContract {
+                    sender: sender.to_string(),
+                    contract: contract_addr.to_string(),
+                    msg: msg.to_vec(),
+                    funds: vec![],
+                },
+            ),
+            RevokeRequirement::RevokeFeeAllowance { granter, grantee } => encode_any(
+                MSG_REVOKE_ALLOWANCE_TYPE_URL,
+                &MsgRevokeAllowance {
+                    granter: granter.to_string(),
+                    grantee: grantee.to_string(),
+                },
+            ),
+        }
+    }
+
+    /// Encodes the requirement as a stargate message
+    pub fn to_cosmos_msg(&self) -> CosmosMsg {
+        let Any { type_url, value } = self.to_any();
+
+        CosmosMsg::Stargate {
+            type_url,
+            value: Binary::from(value),
+        }
+    }
+}
+
+/// Encodes each of the given revoke requirements, in order, skipping any duplicates so that
+/// the result is the full list of messages the granter needs to sign
+pub fn revoke_reqs_to_anys(revokes: Vec<RevokeRequirement>) -> Vec<Any> {
+    revokes
+        .iter()
+        .unique()
+        .map(RevokeRequirement::to_any)
+        .collect()
+}
+
+/// Encodes each of the given revoke requirements as a stargate message, skipping any duplicates
+pub fn revoke_reqs_to_msgs(revokes: Vec<RevokeRequirement>) -> Vec<CosmosMsg> {
+    revokes
+        .iter()
+        .unique()
+        .map(RevokeRequirement::to_cosmos_msg)
+        .collect()
+}
@@ -0,0 +1,115 @@
+use cosmos_sdk_proto::prost::DecodeError;
+use cosmwasm_std::{StdError, Timestamp};
+use thiserror::Error;
+
+#[derive(Error, Debug, PartialEq)]
+pub enum GrantSpecError {
+    #[error("{0}")]
+    Std(#[from] StdError),
+
+    #[error("Decode Error: {0}")]
+    Decode(#[from] DecodeError),
+
+    #[error("Unsupported authorization type: {0}")]
+    UnsupportedAuthorization(String),
+
+    #[error("Unsupported contract execution limit type: {0}")]
+    UnsupportedContractExecutionLimit(String),
+
+    #[error("Unsupported contract execution filter type: {0}")]
+    UnsupportedContractExecutionFilter(String),
+
+    #[error("Unsupported stake authorization type: {0}")]
+    UnsupportedStakeAuthorizationType(i32),
+
+    #[error("Grant is missing its authorization")]
+    MissingAuthorization,
+
+    #[error("Contract grant for {0} is missing its limit or filter")]
+    IncompleteContractGrant(String),
+
+    #[error("Cannot merge stake authorization caps with different denoms: {a} and {b}")]
+    MismatchedStakeDenoms { a: String, b: String },
+
+    #[error(
+        "Cannot merge the message filters for contract {0}, its messages are not json objects"
+    )]
+    IncompatibleContractFilters(String),
+
+    #[error("Merging the requirements leaves no {0} that all of them allow")]
+    EmptyMergeIntersection(String),
+
+    #[error(
+        "Expiration of {0} seconds from now has to be resolved against the current time first"
+    )]
+    UnresolvedExpiration(u64),
+
+    #[error("Expiration {expiration} is not after the current time {now}")]
+    ExpirationInPast {
+        expiration: Timestamp,
+        now: Timestamp,
+    },
+
+    #[error("Granter and grantee are both {0}")]
+    GranterIsGrantee(String),
+
+    #[error("Generic authorization is missing its message type url")]
+    EmptyMsgTypeUrl,
+
+    #[error("Spend limit is set but doesn't contain any coins")]
+    EmptySpendLimit,
+
+    #[error("Limit for {0} has an amount of zero")]
+    ZeroAmount(String),
+
+    #[error("Allow list is set but doesn't contain any addresses")]
+    EmptyAllowList,
+
+    #[error("Send authorization with an allow list needs a spend limit")]
+    AllowListWithoutSpendLimit,
+
+    #[error("Stake authorization type must be specified")]
+    UnspecifiedStakeAuthorizationType,
+
+    #[error("Stake authorization validator list is empty")]
+    EmptyStakeValidators,
+
+    #[error("Contract execution authorization doesn't contain any contracts")]
+    EmptyContractExecutionSettings,
+
+    #[error("Contract {0} is listed more than once")]
+    DuplicateContract(String),
+
+    #[error("Calls limit for contract {0} is zero")]
+    ZeroCallsLimit(String),
+
+    #[error("Funds limit for contract {0} doesn't allow any funds")]
+    ZeroFundsLimit(String),
+
+    #[error("Message filter for contract {0} doesn't accept any messages")]
+    EmptyMessageFilter(String),
+
+    #[error("Transfer authorization doesn't contain any channels")]
+    EmptyTransferAllocations,
+
+    #[error("Channel {port}/{channel} is listed more than once")]
+    DuplicateTransferChannel { port: String, channel: String },
+
+    #[error("Fee allowance period must be longer than zero")]
+    ZeroAllowancePeriod,
+
+    #[error("Fee allowance doesn't allow any messages")]
+    EmptyAllowedMessages,
+
+    #[error("A transaction has to be allowed to contain at least one message")]
+    ZeroMaxMsgsPerTx,
+}
+
+impl From<GrantSpecError> for StdError {
+    fn from(err: GrantSpecError) -> Self {
+        match err {
+            GrantSpecError::Std(std_err) => std_err,
+            err => StdError::generic_err(err.to_string()),
+        }
+    }
+}
@@ -1,11 +1,19 @@
-use crate::grants::{
-    AuthorizationType, ContractExecutionAuthorizationFilter, ContractExecutionAuthorizationLimit,
-    ContractExecutionSetting, GrantRequirement, RevokeRequirement, StakeAuthorizationPolicy,
-    StakeAuthorizationType, StakeAuthorizationValidators,
+use crate::{
+    errors::GrantSpecError,
+    grants::{
+        AllowanceType, AuthorizationType, ContractExecutionAuthorizationFilter,
+        ContractExecutionAuthorizationLimit, ContractExecutionSetting, GrantExpiration,
+        GrantRequirement, RevokeRequirement, StakeAuthorizationPolicy,
+        StakeAuthorizationValidators, TransferAuthorizationSetting,
+    },
+    helpers::{is_subset, message_keys},
 };
-use cosmwasm_std::{Addr, Coin, StdResult, Timestamp, Uint128};
+use cosmwasm_std::{Addr, Binary, Coin, StdResult, Timestamp, Uint128};
 use itertools::Itertools;
-use std::{collections::HashMap, u64};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    hash::Hash,
+};
 
 pub trait Grantable {
     type GrantSettings;
@@ -15,392 +23,629 @@ pub trait Grantable {
         current_timestamp: Timestamp,
     ) -> StdResult<Vec<GrantRequirement>>;
 
+    /// Revokes everything that `query_grants` requires. The grant's contract executions are
+    /// replaced by the message from `revoke_msg`, or left out if there is none
     fn query_revokes(
         grant: GrantStructure<Self::GrantSettings>,
-    ) -> StdResult<Vec<RevokeRequirement>>;
+    ) -> StdResult<Vec<RevokeRequirement>> {
+        let revoke_msg = Self::revoke_msg(&grant)?;
+
+        Ok(Self::query_grants(grant, Timestamp::default())?
+            .into_iter()
+            .filter_map(|grant_req| match grant_req {
+                GrantRequirement::ContractExec {
+                    contract_addr,
+                    sender,
+                    ..
+                } => revoke_msg
+                    .clone()
+                    .map(|msg| RevokeRequirement::ContractExec {
+                        contract_addr,
+                        msg,
+                        sender,
+                    }),
+                grant_req => Some(grant_req.into()),
+            })
+            .collect())
+    }
+
+    /// The message the granter sends to the grant contract to undo the grant's contract executions
+    fn revoke_msg(_grant: &GrantStructure<Self::GrantSettings>) -> StdResult<Option<Binary>> {
+        Ok(None)
+    }
 }
 
 #[derive(Clone, Debug, PartialEq)]
 pub struct GrantStructure<T> {
     pub granter: Addr,
     pub grantee: Addr,
-    pub expiration: Timestamp,
+    pub expiration: GrantExpiration,
     pub grant_contract: Addr,
     pub grant_data: T,
 }
 
-type StakeAuthKey = (Addr, Addr, StakeAuthorizationType);
+/// How the `max_tokens` caps of two stake authorizations for the same grant are merged
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum MaxTokensMerge {
+    /// Both caps are added together so that both requirements can be fulfilled at once
+    #[default]
+    Sum,
+    /// The larger of the two caps is kept for requirements that draw from the same tokens
+    Max,
+}
+
+/// How the limits of two requirements that end up as the same grant are merged
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum MergeStrategy {
+    /// Maximum compatibility: the merged grant allows everything either requirement allows,
+    /// so a limit or list that only one side sets is dropped and lists are combined
+    #[default]
+    Union,
+    /// Least privilege: the merged grant only allows what both requirements allow,
+    /// so a limit or list that either side sets is kept and lists are narrowed to their common entries
+    Intersection,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DedupeSettings {
+    pub strategy: MergeStrategy,
+    /// only used by the union strategy, the intersection always keeps the smaller cap
+    pub stake_max_tokens: MaxTokensMerge,
+}
+
+/// Requirements with the same key end up as a single message on chain
+/// so they have to be merged into one requirement.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum DedupeKey {
+    /// authz only keeps a single grant per (granter, grantee, msg type url)
+    Grant(Addr, Addr, String),
+    /// feegrant only keeps a single allowance per (granter, grantee)
+    FeeAllowance(Addr, Addr),
+}
+
+impl DedupeKey {
+    fn from_grant_req(grant: &GrantRequirement) -> Option<Self> {
+        match grant {
+            GrantRequirement::GrantSpec {
+                grant_type,
+                granter,
+                grantee,
+                ..
+            } => Some(DedupeKey::Grant(
+                granter.clone(),
+                grantee.clone(),
+                grant_type.msg_type_url(),
+            )),
+            GrantRequirement::FeeAllowanceSpec {
+                granter, grantee, ..
+            } => Some(DedupeKey::FeeAllowance(granter.clone(), grantee.clone())),
+            GrantRequirement::ContractExec { .. } => None,
+        }
+    }
+}
+
+/// Dedupes the requirements using the default `DedupeSettings`
+pub fn dedupe_grant_reqs(
+    grants: Vec<GrantRequirement>,
+) -> Result<Vec<GrantRequirement>, GrantSpecError> {
+    dedupe_grant_reqs_with_settings(grants, DedupeSettings::default())
+}
 
-pub fn dedupe_grant_reqs(grants: Vec<GrantRequirement>) -> Vec<GrantRequirement> {
-    let mut stake_authorizations = vec![];
-    let mut generic_authorizations = vec![];
-    let mut send_authorizations = vec![];
-    let mut contract_execute_authorizations = vec![];
-    let mut transfer_authorizations = vec![];
+/// Merges the requirements that end up as the same message on chain.
+///
+/// The output is deterministic:
+/// - grants and fee allowances come first in the order in which their key first appears
+/// - `ContractExec` requirements follow in the order they were given since they usually depend
+///   on the grants being in place already
+///
+/// Exact duplicates are only counted once so composing the same spec twice doesn't double any limits.
+pub fn dedupe_grant_reqs_with_settings(
+    grants: Vec<GrantRequirement>,
+    settings: DedupeSettings,
+) -> Result<Vec<GrantRequirement>, GrantSpecError> {
+    let mut seen_grants = BTreeMap::<DedupeKey, Vec<GrantRequirement>>::new();
+    let mut seen_contract_executions = BTreeSet::<(Addr, Addr, Binary)>::new();
+    let mut keyed_grants = vec![];
     let mut contract_executions = vec![];
 
-    grants.into_iter().for_each(|grant| match grant {
-        GrantRequirement::GrantSpec {
-            grant_type: AuthorizationType::StakeAuthorization { .. },
-            ..
-        } => {
-            // only add if it's unique
-            if !stake_authorizations.contains(&grant) {
-                stake_authorizations.push(grant)
+    for grant in grants {
+        match DedupeKey::from_grant_req(&grant) {
+            Some(key) => {
+                let seen = seen_grants.entry(key.clone()).or_default();
+                if !seen.contains(&grant) {
+                    seen.push(grant.clone());
+                    keyed_grants.push((key, normalize_grant_req(grant, &settings)?));
+                }
             }
-        }
-        GrantRequirement::GrantSpec {
-            grant_type: AuthorizationType::GenericAuthorization { .. },
-            ..
-        } => {
-            // only add if it's unique
-            if !generic_authorizations.contains(&grant) {
-                generic_authorizations.push(grant)
+            None => {
+                if let GrantRequirement::ContractExec {
+                    contract_addr,
+                    msg,
+                    sender,
+                } = &grant
+                {
+                    if seen_contract_executions.insert((
+                        contract_addr.clone(),
+                        sender.clone(),
+                        msg.clone(),
+                    )) {
+                        contract_executions.push(grant);
+                    }
+                }
             }
         }
+    }
+
+    let mut deduped = try_merge_by_key(keyed_grants, |(a_key, a), (_, b)| {
+        Ok::<_, GrantSpecError>((a_key, combine_grant_reqs(a, b, &settings)?))
+    })?
+    .into_iter()
+    .map(|(_, grant)| grant)
+    .collect::<Vec<GrantRequirement>>();
+
+    deduped.extend(contract_executions);
+
+    Ok(deduped)
+}
+
+/// Merges the settings that a single requirement already lists more than once
+fn normalize_grant_req(
+    grant: GrantRequirement,
+    settings: &DedupeSettings,
+) -> Result<GrantRequirement, GrantSpecError> {
+    Ok(match grant {
         GrantRequirement::GrantSpec {
-            grant_type: AuthorizationType::SendAuthorization { .. },
-            ..
-        } => {
-            // only add if it's unique
-            if !send_authorizations.contains(&grant) {
-                send_authorizations.push(grant)
-            }
-        }
+            grant_type: AuthorizationType::ContractExecutionAuthorization(contract_settings),
+            granter,
+            grantee,
+            expiration,
+        } => GrantRequirement::GrantSpec {
+            grant_type: combine_contract_execute_auths(
+                vec![],
+                contract_settings,
+                settings.strategy,
+            )?,
+            granter,
+            grantee,
+            expiration,
+        },
         GrantRequirement::GrantSpec {
-            grant_type: AuthorizationType::ContractExecutionAuthorization(_),
-            ..
-        } => {
-            // only add if it's unique
-            if !contract_execute_authorizations.contains(&grant) {
-                contract_execute_authorizations.push(grant)
+            grant_type: AuthorizationType::TransferAuthorization(transfer_settings),
+            granter,
+            grantee,
+            expiration,
+        } => GrantRequirement::GrantSpec {
+            grant_type: combine_transfer_auths(vec![], transfer_settings, settings.strategy)?,
+            granter,
+            grantee,
+            expiration,
+        },
+        grant => grant,
+    })
+}
+
+/// Merges the items that share a key while keeping the order in which each key first appears
+fn try_merge_by_key<K: Ord + Clone, T, E>(
+    items: Vec<(K, T)>,
+    mut merge: impl FnMut((K, T), (K, T)) -> Result<(K, T), E>,
+) -> Result<Vec<(K, T)>, E> {
+    let mut positions = BTreeMap::<K, usize>::new();
+    let mut merged = Vec::<Option<(K, T)>>::with_capacity(items.len());
+
+    for (key, item) in items {
+        match positions.get(&key) {
+            Some(&i) => {
+                // the slot is always filled again right after so it's never empty once we're done
+                if let Some(existing) = merged[i].take() {
+                    merged[i] = Some(merge(existing, (key, item))?);
+                }
             }
-        }
-        GrantRequirement::GrantSpec {
-            grant_type: AuthorizationType::TransferAuthorization { .. },
-            ..
-        } => {
-            // TODO: update transfer logic
-            if !transfer_authorizations.contains(&grant) {
-                transfer_authorizations.push(grant)
+            None => {
+                positions.insert(key.clone(), merged.len());
+                merged.push(Some((key, item)));
             }
         }
-        GrantRequirement::ContractExec { .. } => contract_executions.push(grant),
-    });
-
-    // these are only stake authorizations
-    // we need to concatenate them with the other stake authorizations when they are
-    // aimed at the same grant (granter, grantee, stake authorization type)
-    // TODO: this is dumb and should use a hashmap instead
-    let stake_authorizations = stake_authorizations
-        .iter()
-        .fold(
-            Vec::<(StakeAuthKey, (AuthorizationType, Timestamp))>::new(),
-            |mut all_stake_grants, stake_grant| {
-                if let GrantRequirement::GrantSpec {
-                    grant_type:
-                        AuthorizationType::StakeAuthorization {
-                            max_tokens,
-                            authorization_type,
-                            validators,
-                        },
-                    granter,
-                    grantee,
-                    expiration,
-                } = stake_grant.clone()
-                {
-                    let matching_index = all_stake_grants.iter().find_position(|(key, _)| {
-                        key.clone().eq(&(
-                            granter.clone(),
-                            grantee.clone(),
-                            authorization_type.clone(),
-                        ))
-                    });
-                    if let Some((i, _)) = matching_index {
-                        // if the grant already exists, we need to update the max_tokens and validators
-
-                        // take whichever expiration is later
-                        let new_expiration = all_stake_grants[i].1 .1.max(expiration);
-
-                        let new_validators = combine_stake_auth_policies(
-                            if let AuthorizationType::StakeAuthorization {
-                                validators: a_validators,
-                                ..
-                            } = all_stake_grants[i].1 .0.clone()
-                            {
-                                a_validators
-                            } else {
-                                None
-                            },
-                            validators,
-                        );
-
-                        all_stake_grants[i].1 = (
-                            AuthorizationType::StakeAuthorization {
-                                // TODO: more intelligent max_tokens merging
-                                // for now just set no max
-                                max_tokens: None,
-                                authorization_type,
-                                validators: new_validators,
-                            },
-                            // expiration: new_expiration,
-                            new_expiration,
-                        );
-                    } else {
-                        // if the grant doesn't exist, we need to add it
-                        all_stake_grants.push((
-                            (granter.clone(), grantee.clone(), authorization_type.clone()),
-                            (
-                                AuthorizationType::StakeAuthorization {
-                                    max_tokens,
-                                    authorization_type,
-                                    validators,
-                                },
-                                expiration,
-                            ),
-                        ));
-                    }
-                }
+    }
+
+    Ok(merged.into_iter().flatten().collect())
+}
 
-                all_stake_grants
+/// Merges two requirements that share the same `DedupeKey`
+fn combine_grant_reqs(
+    a: GrantRequirement,
+    b: GrantRequirement,
+    settings: &DedupeSettings,
+) -> Result<GrantRequirement, GrantSpecError> {
+    match (a, b) {
+        (
+            GrantRequirement::GrantSpec {
+                grant_type: a_grant_type,
+                granter,
+                grantee,
+                expiration: a_expiration,
             },
-        )
-        .iter()
-        .map(
-            |((granter, grantee, ..), (grant_type, expiration))| GrantRequirement::GrantSpec {
-                grant_type: grant_type.clone(),
-                granter: granter.clone(),
-                grantee: grantee.clone(),
-                expiration: *expiration,
+            GrantRequirement::GrantSpec {
+                grant_type: b_grant_type,
+                expiration: b_expiration,
+                ..
             },
-        )
-        .collect::<Vec<GrantRequirement>>();
-
-    // these are only send authorizations
-    // we need to concatenate them with the other send authorizations when they are
-    // aimed at the same grant (granter, grantee)
-    // TODO: this is dumb and should use a hashmap instead
-    let send_authorizations = send_authorizations
-        .iter()
-        .fold(
-            Vec::<((Addr, Addr), (AuthorizationType, Timestamp))>::new(),
-            |mut all_send_grants, send_grant| {
-                if let GrantRequirement::GrantSpec {
-                    grant_type:
-                        AuthorizationType::SendAuthorization {
-                            spend_limit: b_send_limit,
-                            allow_list: b_allow_list,
-                        },
-                    granter,
-                    grantee,
-                    expiration,
-                } = send_grant.clone()
-                {
-                    let matching_index = all_send_grants.iter().find_position(|(key, _)| {
-                        key.clone().eq(&(granter.clone(), grantee.clone()))
-                    });
-                    if let Some((i, _)) = matching_index {
-                        // if the grant already exists, we need to update the max_tokens and validators
-
-                        // take whichever expiration is later
-                        let new_expiration = all_send_grants[i].1 .1.max(expiration);
-
-                        let new_grant_type = if let AuthorizationType::SendAuthorization {
-                            spend_limit: a_spend_limit,
-                            allow_list: a_allow_list,
-                        } = all_send_grants[i].1 .0.clone()
-                        {
-                            combine_send_auths(
-                                a_spend_limit,
-                                a_allow_list,
-                                b_send_limit,
-                                b_allow_list,
-                            )
-                        } else {
-                            panic!("This should never happen")
-                        };
-
-                        all_send_grants[i].1 = (
-                            new_grant_type,
-                            // expiration: new_expiration,
-                            new_expiration,
-                        );
-                    } else {
-                        // if the grant doesn't exist, we need to add it
-                        all_send_grants.push((
-                            (granter.clone(), grantee.clone()),
-                            (
-                                AuthorizationType::SendAuthorization {
-                                    spend_limit: b_send_limit,
-                                    allow_list: b_allow_list,
-                                },
-                                expiration,
-                            ),
-                        ));
-                    }
-                }
-
-                all_send_grants
+        ) => Ok(GrantRequirement::GrantSpec {
+            grant_type: combine_authorizations(a_grant_type, b_grant_type, settings)?,
+            granter,
+            grantee,
+            // take whichever expiration is later
+            expiration: a_expiration.later(b_expiration)?,
+        }),
+        (
+            GrantRequirement::FeeAllowanceSpec {
+                allowance: a_allowance,
+                granter,
+                grantee,
+                expiration: a_expiration,
             },
-        )
-        .iter()
-        .map(
-            |((granter, grantee), (grant_type, expiration))| GrantRequirement::GrantSpec {
-                grant_type: grant_type.clone(),
-                granter: granter.clone(),
-                grantee: grantee.clone(),
-                expiration: *expiration,
+            GrantRequirement::FeeAllowanceSpec {
+                allowance: b_allowance,
+                expiration: b_expiration,
+                ..
             },
-        )
-        .collect::<Vec<GrantRequirement>>();
-
-    // these are only contract execute authorizations
-    // we need to concatenate them with the other contract execute authorizations when they are
-    // aimed at the same grant (granter, grantee)
-    // let contract_execute_authorizations =
-    let contract_execute_authorizations = contract_execute_authorizations
-        .iter()
-        .fold(
-            Vec::<((Addr, Addr), (AuthorizationType, Timestamp))>::new(),
-            |mut all_send_grants, send_grant| {
-                if let GrantRequirement::GrantSpec {
-                    grant_type:
-                        AuthorizationType::ContractExecutionAuthorization(
-                            additional_contract_execution_settings,
-                        ),
-                    granter,
-                    grantee,
-                    expiration: additional_timestamp,
-                } = send_grant.clone()
-                {
-                    let matching_index = all_send_grants
-                        .iter()
-                        .find_position(|(key, _)| key.0.eq(&granter) && key.1.eq(&grantee)); //key.clone().eq(&(granter.clone(), grantee.clone())));
-                    if let Some((
-                        i,
-                        (
-                            _,
-                            (
-                                AuthorizationType::ContractExecutionAuthorization(
-                                    existing_contract_execution_settings,
-                                ),
-                                existing_timestamp,
-                            ),
-                        ),
-                    )) = matching_index
-                    {
-                        // if the grant already exists, we need to update the max_tokens and validators
-
-                        // take whichever expiration is later
-                        let new_expiration = (*existing_timestamp).max(additional_timestamp);
-
-                        all_send_grants[i].1 = (
-                            AuthorizationType::ContractExecutionAuthorization(
-                                [
-                                    additional_contract_execution_settings,
-                                    existing_contract_execution_settings.clone(),
-                                ]
-                                .concat(),
-                            ),
-                            // expiration: new_expiration,
-                            new_expiration,
-                        );
-                    } else {
-                        // if the grant doesn't exist, we need to add it
-                        all_send_grants.push((
-                            (granter.clone(), grantee.clone()),
-                            (
-                                AuthorizationType::ContractExecutionAuthorization(
-                                    additional_contract_execution_settings,
-                                ),
-                                additional_timestamp,
-                            ),
-                        ));
-                    }
-                }
+        ) => Ok(GrantRequirement::FeeAllowanceSpec {
+            allowance: combine_allowances(a_allowance, b_allowance, settings.strategy)?,
+            granter,
+            grantee,
+            expiration: a_expiration.later(b_expiration)?,
+        }),
+        // requirements with the same key are always of the same kind
+        (a, _) => Ok(a),
+    }
+}
 
-                all_send_grants
+/// Merges two authorizations for the same message type
+fn combine_authorizations(
+    a: AuthorizationType,
+    b: AuthorizationType,
+    settings: &DedupeSettings,
+) -> Result<AuthorizationType, GrantSpecError> {
+    match (a, b) {
+        // a generic authorization already allows everything for its message type
+        (generic @ AuthorizationType::GenericAuthorization { .. }, _)
+        | (_, generic @ AuthorizationType::GenericAuthorization { .. }) => Ok(generic),
+        (
+            AuthorizationType::SendAuthorization {
+                spend_limit: a_spend_limit,
+                allow_list: a_allow_list,
             },
-        )
-        .into_iter()
-        .map(
-            |((granter, grantee), (grant_type, expiration))| GrantRequirement::GrantSpec {
-                grant_type,
-                granter,
-                grantee,
-                expiration,
+            AuthorizationType::SendAuthorization {
+                spend_limit: b_spend_limit,
+                allow_list: b_allow_list,
             },
-        )
-        .collect::<Vec<GrantRequirement>>();
-
-    [
-        contract_execute_authorizations,
-        generic_authorizations,
-        send_authorizations,
-        stake_authorizations,
-        contract_executions,
-        transfer_authorizations,
-    ]
-    .concat()
+        ) => Ok(AuthorizationType::SendAuthorization {
+            spend_limit: combine_spend_limits(a_spend_limit, b_spend_limit, settings.strategy)?,
+            allow_list: combine_optional_lists(
+                a_allow_list,
+                b_allow_list,
+                settings.strategy,
+                "allowed receivers",
+            )?,
+        }),
+        (
+            AuthorizationType::StakeAuthorization {
+                max_tokens: a_max_tokens,
+                authorization_type,
+                validators: a_validators,
+            },
+            AuthorizationType::StakeAuthorization {
+                max_tokens: b_max_tokens,
+                validators: b_validators,
+                ..
+            },
+        ) => Ok(AuthorizationType::StakeAuthorization {
+            max_tokens: combine_max_tokens(a_max_tokens, b_max_tokens, settings)?,
+            authorization_type,
+            validators: combine_stake_auth_policies(a_validators, b_validators, settings.strategy)?,
+        }),
+        (
+            AuthorizationType::ContractExecutionAuthorization(a_settings),
+            AuthorizationType::ContractExecutionAuthorization(b_settings),
+        ) => combine_contract_execute_auths(a_settings, b_settings, settings.strategy),
+        (
+            AuthorizationType::TransferAuthorization(a_settings),
+            AuthorizationType::TransferAuthorization(b_settings),
+        ) => combine_transfer_auths(a_settings, b_settings, settings.strategy),
+        // authorizations for the same message type are always of the same kind
+        (a, _) => Ok(a),
+    }
 }
 
-fn combine_send_auths(
-    a_spend_limit: Option<Vec<Coin>>,
-    a_allow_list: Option<Vec<Addr>>,
-    b_spend_limit: Option<Vec<Coin>>,
-    b_allow_list: Option<Vec<Addr>>,
-) -> AuthorizationType {
-    println!(
-        "a_allow_list: {:?}, b_allow_list: {:?}",
-        a_allow_list, b_allow_list,
-    );
-    let spend_limit = match (a_spend_limit, b_spend_limit) {
-        (Some(a), Some(b)) => Some(concat_coins(a, b)),
-        // if one has a spend limit but not the other than the combined send auth can't have a spend limit
-        _ => None,
-    };
+/// Merges two spend limits where None is unlimited
+fn combine_spend_limits(
+    a: Option<Vec<Coin>>,
+    b: Option<Vec<Coin>>,
+    strategy: MergeStrategy,
+) -> Result<Option<Vec<Coin>>, GrantSpecError> {
+    Ok(match (strategy, a, b) {
+        (MergeStrategy::Union, Some(a), Some(b)) => Some(concat_coins(a, b)),
+        // if one has a spend limit but not the other than the combined one can't have a spend limit
+        (MergeStrategy::Union, _, _) => None,
+        (MergeStrategy::Intersection, Some(a), Some(b)) => {
+            Some(non_empty(intersect_coins(a, b), "spend limit")?)
+        }
+        (MergeStrategy::Intersection, a, b) => a.or(b),
+    })
+}
 
-    let allow_list = match (a_allow_list, b_allow_list) {
-        (Some(a), Some(b)) => Some([a, b].concat().iter().unique().cloned().collect()),
-        // if one has an allowlist and the other doesnt, then the combined send auth can't have an allowlist
-        _ => None,
-    };
+/// Merges two lists where None allows anything
+fn combine_optional_lists<T: Clone + Eq + Hash>(
+    a: Option<Vec<T>>,
+    b: Option<Vec<T>>,
+    strategy: MergeStrategy,
+    what: &str,
+) -> Result<Option<Vec<T>>, GrantSpecError> {
+    Ok(match (strategy, a, b) {
+        (MergeStrategy::Union, Some(a), Some(b)) => Some(union_lists(a, b)),
+        // if one has a list and the other doesn't, then the combined one can't have a list
+        (MergeStrategy::Union, _, _) => None,
+        (MergeStrategy::Intersection, Some(a), Some(b)) => {
+            Some(non_empty(intersect_lists(a, b), what)?)
+        }
+        (MergeStrategy::Intersection, a, b) => a.or(b),
+    })
+}
+
+fn union_lists<T: Clone + Eq + Hash>(a: Vec<T>, b: Vec<T>) -> Vec<T> {
+    [a, b].concat().into_iter().unique().collect()
+}
+
+fn intersect_lists<T: Clone + Eq + Hash>(a: Vec<T>, b: Vec<T>) -> Vec<T> {
+    a.into_iter()
+        .unique()
+        .filter(|item| b.contains(item))
+        .collect()
+}
+
+fn difference_lists<T: Clone + Eq + Hash>(a: Vec<T>, b: Vec<T>) -> Vec<T> {
+    a.into_iter()
+        .unique()
+        .filter(|item| !b.contains(item))
+        .collect()
+}
 
-    AuthorizationType::SendAuthorization {
-        spend_limit,
-        allow_list,
+/// An intersection that leaves nothing would no longer grant anything at all
+fn non_empty<T>(items: Vec<T>, what: &str) -> Result<Vec<T>, GrantSpecError> {
+    if items.is_empty() {
+        Err(GrantSpecError::EmptyMergeIntersection(what.to_string()))
+    } else {
+        Ok(items)
     }
 }
 
+/// Merges two fee allowances according to the strategy.
+/// The union keeps the shorter period with the summed period limits and drops a period or
+/// message restriction that only one side has, the intersection keeps the longer period with
+/// the smaller period limits and keeps every restriction.
+fn combine_allowances(
+    a: AllowanceType,
+    b: AllowanceType,
+    strategy: MergeStrategy,
+) -> Result<AllowanceType, GrantSpecError> {
+    Ok(match (a, b) {
+        (
+            AllowanceType::AllowedMsgAllowance {
+                allowance: a_allowance,
+                allowed_messages: a_messages,
+            },
+            AllowanceType::AllowedMsgAllowance {
+                allowance: b_allowance,
+                allowed_messages: b_messages,
+            },
+        ) => AllowanceType::AllowedMsgAllowance {
+            allowance: Box::new(combine_allowances(*a_allowance, *b_allowance, strategy)?),
+            allowed_messages: match strategy {
+                MergeStrategy::Union => union_lists(a_messages, b_messages),
+                MergeStrategy::Intersection => {
+                    non_empty(intersect_lists(a_messages, b_messages), "allowed messages")?
+                }
+            },
+        },
+        (
+            AllowanceType::AllowedMsgAllowance {
+                allowance,
+                allowed_messages,
+            },
+            other,
+        )
+        | (
+            other,
+            AllowanceType::AllowedMsgAllowance {
+                allowance,
+                allowed_messages,
+            },
+        ) => match strategy {
+            MergeStrategy::Union => combine_allowances(*allowance, other, strategy)?,
+            MergeStrategy::Intersection => AllowanceType::AllowedMsgAllowance {
+                allowance: Box::new(combine_allowances(*allowance, other, strategy)?),
+                allowed_messages,
+            },
+        },
+        (
+            AllowanceType::PeriodicAllowance {
+                spend_limit: a_spend_limit,
+                period: a_period,
+                period_spend_limit: a_period_spend_limit,
+            },
+            AllowanceType::PeriodicAllowance {
+                spend_limit: b_spend_limit,
+                period: b_period,
+                period_spend_limit: b_period_spend_limit,
+            },
+        ) => AllowanceType::PeriodicAllowance {
+            spend_limit: combine_spend_limits(a_spend_limit, b_spend_limit, strategy)?,
+            period: match strategy {
+                MergeStrategy::Union => a_period.min(b_period),
+                MergeStrategy::Intersection => a_period.max(b_period),
+            },
+            period_spend_limit: match strategy {
+                MergeStrategy::Union => concat_coins(a_period_spend_limit, b_period_spend_limit),
+                MergeStrategy::Intersection => non_empty(
+                    intersect_coins(a_period_spend_limit, b_period_spend_limit),
+                    "period spend limit",
+                )?,
+            },
+        },
+        (
+            AllowanceType::PeriodicAllowance {
+                spend_limit: a_spend_limit,
+                period,
+                period_spend_limit,
+            },
+            AllowanceType::BasicAllowance {
+                spend_limit: b_spend_limit,
+            },
+        )
+        | (
+            AllowanceType::BasicAllowance {
+                spend_limit: b_spend_limit,
+            },
+            AllowanceType::PeriodicAllowance {
+                spend_limit: a_spend_limit,
+                period,
+                period_spend_limit,
+            },
+        ) if strategy.eq(&MergeStrategy::Intersection) => AllowanceType::PeriodicAllowance {
+            spend_limit: combine_spend_limits(a_spend_limit, b_spend_limit, strategy)?,
+            period,
+            period_spend_limit,
+        },
+        (
+            AllowanceType::BasicAllowance {
+                spend_limit: a_spend_limit,
+            }
+            | AllowanceType::PeriodicAllowance {
+                spend_limit: a_spend_limit,
+                ..
+            },
+            AllowanceType::BasicAllowance {
+                spend_limit: b_spend_limit,
+            }
+            | AllowanceType::PeriodicAllowance {
+                spend_limit: b_spend_limit,
+                ..
+            },
+        ) => AllowanceType::BasicAllowance {
+            spend_limit: combine_spend_limits(a_spend_limit, b_spend_limit, strategy)?,
+        },
+    })
+}
+
 /// Combines to lists of coins into a single list of coins
 /// If there are duplicate denoms, the amounts are summed
 pub fn concat_coins(a: Vec<Coin>, b: Vec<Coin>) -> Vec<Coin> {
     [a, b]
         .concat()
         .into_iter()
-        .fold(HashMap::<String, Uint128>::new(), |mut acc, coin| {
-            let denom = coin.denom.clone();
-            let amount = coin.amount;
-            acc.entry(denom)
+        .fold(BTreeMap::<String, Uint128>::new(), |mut acc, coin| {
+            acc.entry(coin.denom)
                 .and_modify(|existing_amount| {
-                    *existing_amount = existing_amount.saturating_add(amount);
+                    *existing_amount = existing_amount.saturating_add(coin.amount);
                 })
-                .or_insert(amount);
+                .or_insert(coin.amount);
 
             acc
         })
+        // the map is ordered so the coins come out sorted by denom
         .into_iter()
         .map(|(denom, amount)| Coin { denom, amount })
-        .sorted_by(|a, b| a.denom.cmp(&b.denom))
         .collect()
 }
 
+/// Keeps only the denoms that both lists contain with the smaller of the two amounts
+pub fn intersect_coins(a: Vec<Coin>, b: Vec<Coin>) -> Vec<Coin> {
+    let b = concat_coins(b, vec![]);
+
+    concat_coins(a, vec![])
+        .into_iter()
+        .filter_map(|a_coin| {
+            b.iter()
+                .find(|b_coin| b_coin.denom.eq(&a_coin.denom))
+                .map(|b_coin| Coin {
+                    amount: a_coin.amount.min(b_coin.amount),
+                    denom: a_coin.denom,
+                })
+        })
+        .collect()
+}
+
+/// Merges the token caps of two stake authorizations.
+/// With the union a grant without a cap stays uncapped while two caps are combined according
+/// to `stake_max_tokens`, with the intersection any cap is kept and the smaller one wins.
+fn combine_max_tokens(
+    a: Option<Coin>,
+    b: Option<Coin>,
+    settings: &DedupeSettings,
+) -> Result<Option<Coin>, GrantSpecError> {
+    match (a, b) {
+        (Some(a), Some(b)) if a.denom.ne(&b.denom) => Err(GrantSpecError::MismatchedStakeDenoms {
+            a: a.denom,
+            b: b.denom,
+        }),
+        (Some(a), Some(b)) => Ok(Some(Coin {
+            amount: match (settings.strategy, settings.stake_max_tokens) {
+                (MergeStrategy::Union, MaxTokensMerge::Sum) => a.amount.saturating_add(b.amount),
+                (MergeStrategy::Union, MaxTokensMerge::Max) => a.amount.max(b.amount),
+                (MergeStrategy::Intersection, _) => a.amount.min(b.amount),
+            },
+            denom: a.denom,
+        })),
+        (a, b) if settings.strategy.eq(&MergeStrategy::Intersection) => Ok(a.or(b)),
+        _ => Ok(None),
+    }
+}
+
 fn combine_stake_auth_policies(
     a: Option<StakeAuthorizationPolicy>,
     b: Option<StakeAuthorizationPolicy>,
-) -> Option<StakeAuthorizationPolicy> {
-    match (a, b) {
+    strategy: MergeStrategy,
+) -> Result<Option<StakeAuthorizationPolicy>, GrantSpecError> {
+    Ok(match (strategy, a, b) {
+        // the allow list is handled the same no matter which side it is on
+        (
+            strategy,
+            deny_list @ Some(StakeAuthorizationPolicy::DenyList(_)),
+            allow_list @ Some(StakeAuthorizationPolicy::AllowList(_)),
+        ) => return combine_stake_auth_policies(allow_list, deny_list, strategy),
+        // with the union a validator stays denied only if the allow list doesn't allow it
         (
+            MergeStrategy::Union,
+            Some(StakeAuthorizationPolicy::AllowList(StakeAuthorizationValidators {
+                address: allow_list,
+            })),
+            Some(StakeAuthorizationPolicy::DenyList(StakeAuthorizationValidators {
+                address: deny_list,
+            })),
+        ) => {
+            let address = difference_lists(deny_list, allow_list);
+            (!address.is_empty()).then_some(StakeAuthorizationPolicy::DenyList(
+                StakeAuthorizationValidators { address },
+            ))
+        }
+        // with the intersection only the allowed validators that aren't denied remain
+        (
+            MergeStrategy::Intersection,
+            Some(StakeAuthorizationPolicy::AllowList(StakeAuthorizationValidators {
+                address: allow_list,
+            })),
+            Some(StakeAuthorizationPolicy::DenyList(StakeAuthorizationValidators {
+                address: deny_list,
+            })),
+        ) => Some(StakeAuthorizationPolicy::AllowList(
+            StakeAuthorizationValidators {
+                address: non_empty(
+                    difference_lists(allow_list, deny_list),
+                    "allowed validators",
+                )?,
+            },
+        )),
+        (
+            MergeStrategy::Union,
             Some(StakeAuthorizationPolicy::AllowList(StakeAuthorizationValidators {
                 address: a_allow_list,
             })),
@@ -409,146 +654,156 @@ fn combine_stake_auth_policies(
             })),
         ) => Some(StakeAuthorizationPolicy::AllowList(
             StakeAuthorizationValidators {
-                address: [a_allow_list, b_allow_list]
-                    .concat()
-                    .into_iter()
-                    .unique()
-                    .collect(),
+                address: union_lists(a_allow_list, b_allow_list),
             },
         )),
+        // a validator stays denied only if both sides deny it
         (
+            MergeStrategy::Union,
             Some(StakeAuthorizationPolicy::DenyList(StakeAuthorizationValidators {
                 address: a_deny_list,
             })),
             Some(StakeAuthorizationPolicy::DenyList(StakeAuthorizationValidators {
                 address: b_deny_list,
             })),
-        ) => Some(StakeAuthorizationPolicy::DenyList(
+        ) => {
+            let address = intersect_lists(a_deny_list, b_deny_list);
+            (!address.is_empty()).then_some(StakeAuthorizationPolicy::DenyList(
+                StakeAuthorizationValidators { address },
+            ))
+        }
+        // without a policy on one side any validator is allowed
+        (MergeStrategy::Union, _, _) => None,
+        (
+            MergeStrategy::Intersection,
+            Some(StakeAuthorizationPolicy::AllowList(StakeAuthorizationValidators {
+                address: a_allow_list,
+            })),
+            Some(StakeAuthorizationPolicy::AllowList(StakeAuthorizationValidators {
+                address: b_allow_list,
+            })),
+        ) => Some(StakeAuthorizationPolicy::AllowList(
             StakeAuthorizationValidators {
-                address: [a_deny_list, b_deny_list].concat(),
+                address: non_empty(
+                    intersect_lists(a_allow_list, b_allow_list),
+                    "allowed validators",
+                )?,
             },
         )),
         (
-            Some(StakeAuthorizationPolicy::AllowList(allow_list)),
-            Some(StakeAuthorizationPolicy::DenyList(_)),
-        ) => Some(StakeAuthorizationPolicy::AllowList(allow_list)),
-        (
-            Some(StakeAuthorizationPolicy::DenyList(_)),
-            Some(StakeAuthorizationPolicy::AllowList(allow_list)),
-        ) => Some(StakeAuthorizationPolicy::AllowList(allow_list)),
-        (Some(a), None) => Some(a),
-        (None, Some(b)) => Some(b),
-        (None, None) => None,
-    }
+            MergeStrategy::Intersection,
+            Some(StakeAuthorizationPolicy::DenyList(StakeAuthorizationValidators {
+                address: a_deny_list,
+            })),
+            Some(StakeAuthorizationPolicy::DenyList(StakeAuthorizationValidators {
+                address: b_deny_list,
+            })),
+        ) => Some(StakeAuthorizationPolicy::DenyList(
+            StakeAuthorizationValidators {
+                address: union_lists(a_deny_list, b_deny_list),
+            },
+        )),
+        (MergeStrategy::Intersection, a, b) => a.or(b),
+    })
 }
 
+/// Merges contract execution settings so that each contract is only listed once,
+/// the wasm module rejects authorizations with duplicate contract entries.
+/// Settings are kept in the order their contract first appears and the strategy only applies
+/// to the settings of contracts that are listed on both sides.
 fn combine_contract_execute_auths(
     a_auths: Vec<ContractExecutionSetting>,
     b_auths: Vec<ContractExecutionSetting>,
-) -> AuthorizationType {
-    // let new_auth_settings = [a_auths, b_auths]
-    //     .concat()
-    //     .into_iter()
-    //     .fold(
-    //         HashMap::<Addr, Vec<ContractExecutionSetting>>::new(),
-    //         |mut all_settings, current_setting| {
-    //             if let Some(existing_setting) = all_settings.get(&current_setting.contract_addr) {
-    //                 let new_setting = existing_setting
-    //                     .iter()
-    //                     .flat_map(|existing| {
-    //                         combine_contract_execution_settings(
-    //                             existing.clone(),
-    //                             current_setting.clone(),
-    //                         )
-    //                     })
-    //                     .collect();
-    //                 all_settings.insert(current_setting.contract_addr.clone(), new_setting);
-    //                 all_settings
-    //             } else {
-    //                 all_settings
-    //                     .insert(current_setting.contract_addr.clone(), vec![current_setting]);
-    //                 all_settings
-    //             }
-    //         },
-    //     )
-    //     .values()
-    //     .flatten()
-    //     .cloned()
-    //     .collect::<Vec<ContractExecutionSetting>>();
-
-    // GrantType::ContractExecutionAuthorization(new_auth_settings)
-    AuthorizationType::ContractExecutionAuthorization([a_auths, b_auths].concat())
+    strategy: MergeStrategy,
+) -> Result<AuthorizationType, GrantSpecError> {
+    let new_auth_settings = try_merge_by_key(
+        [a_auths, b_auths]
+            .concat()
+            .into_iter()
+            .map(|setting| (setting.contract_addr.clone(), setting))
+            .collect(),
+        |(contract_addr, a), (_, b)| {
+            Ok::<_, GrantSpecError>((
+                contract_addr,
+                combine_contract_execution_settings(a, b, strategy)?,
+            ))
+        },
+    )?
+    .into_iter()
+    .map(|(_, setting)| setting)
+    .collect();
+
+    Ok(AuthorizationType::ContractExecutionAuthorization(
+        new_auth_settings,
+    ))
 }
 
-// both settings are expected to have the same contract_addr otherwise there's nothing to
+// both settings are expected to have the same contract_addr otherwise there's nothing to merge
 fn combine_contract_execution_settings(
     a: ContractExecutionSetting,
     b: ContractExecutionSetting,
-) -> Vec<ContractExecutionSetting> {
-    let (a_limits, a_amounts) = match a.limit.clone() {
-        ContractExecutionAuthorizationLimit::CombinedLimit {
-            calls_remaining,
-            amounts,
-        } => (calls_remaining, amounts),
-        ContractExecutionAuthorizationLimit::MaxCallsLimit { remaining } => (remaining, vec![]),
-        ContractExecutionAuthorizationLimit::MaxFundsLimit { amounts } => (u64::MIN, amounts),
-    };
-
-    let (b_limits, b_amounts) = match b.limit.clone() {
-        ContractExecutionAuthorizationLimit::CombinedLimit {
-            calls_remaining,
-            amounts,
-        } => (calls_remaining, amounts),
-        ContractExecutionAuthorizationLimit::MaxCallsLimit { remaining } => (remaining, vec![]),
-        ContractExecutionAuthorizationLimit::MaxFundsLimit { amounts } => (u64::MIN, amounts),
+    strategy: MergeStrategy,
+) -> Result<ContractExecutionSetting, GrantSpecError> {
+    let (a_calls, a_amounts) = a.limit.calls_and_funds();
+    let (b_calls, b_amounts) = b.limit.calls_and_funds();
+
+    let (calls, funds) = match strategy {
+        MergeStrategy::Union => (
+            // a limit without calls (MaxFundsLimit) allows unlimited calls so the sum is unlimited as well
+            a_calls
+                .zip(b_calls)
+                .map(|(a_calls, b_calls)| a_calls.saturating_add(b_calls)),
+            concat_coins(a_amounts, b_amounts),
+        ),
+        MergeStrategy::Intersection => (
+            match (a_calls, b_calls) {
+                (Some(a_calls), Some(b_calls)) => Some(a_calls.min(b_calls)),
+                (a_calls, b_calls) => a_calls.or(b_calls),
+            },
+            // a limit without funds doesn't allow sending any so neither does the merged one
+            intersect_coins(a_amounts, b_amounts),
+        ),
     };
 
-    let limit = match (a_limits, a_amounts, b_limits, b_amounts) {
-        (u64::MIN, a_amounts, u64::MIN, b_amounts) => {
-            ContractExecutionAuthorizationLimit::MaxFundsLimit {
-                amounts: [a_amounts, b_amounts].concat(),
-            }
-        }
-        (a_limits, funds_a, b_limits, funds_b) if funds_a.is_empty() && funds_b.is_empty() => {
-            ContractExecutionAuthorizationLimit::MaxCallsLimit {
-                remaining: a_limits.saturating_add(b_limits),
-            }
-        }
-        (a_limits, a_amounts, b_limits, b_amounts) => {
-            ContractExecutionAuthorizationLimit::CombinedLimit {
-                calls_remaining: a_limits.saturating_add(b_limits),
-                amounts: [a_amounts, b_amounts].concat(),
-            }
+    let filter = match strategy {
+        MergeStrategy::Union => union_contract_filters(a.filter, b.filter, &a.contract_addr)?,
+        MergeStrategy::Intersection => {
+            intersect_contract_filters(a.filter, b.filter, &a.contract_addr)?
         }
     };
 
-    let filters = match (a.filter.clone(), b.filter) {
+    Ok(ContractExecutionSetting {
+        contract_addr: a.contract_addr,
+        limit: ContractExecutionAuthorizationLimit::from_calls_and_funds(calls, funds),
+        filter,
+    })
+}
+
+fn union_contract_filters(
+    a: ContractExecutionAuthorizationFilter,
+    b: ContractExecutionAuthorizationFilter,
+    contract_addr: &Addr,
+) -> Result<ContractExecutionAuthorizationFilter, GrantSpecError> {
+    Ok(match (a, b) {
         (ContractExecutionAuthorizationFilter::AllowAllMessagesFilter, _)
         | (_, ContractExecutionAuthorizationFilter::AllowAllMessagesFilter) => {
-            vec![ContractExecutionAuthorizationFilter::AllowAllMessagesFilter]
+            ContractExecutionAuthorizationFilter::AllowAllMessagesFilter
         }
         (
             ContractExecutionAuthorizationFilter::AcceptedMessageKeysFilter { keys },
             ContractExecutionAuthorizationFilter::AcceptedMessageKeysFilter { keys: keys_b },
-        ) => {
-            vec![
-                ContractExecutionAuthorizationFilter::AcceptedMessageKeysFilter {
-                    keys: [keys, keys_b].concat(),
-                },
-            ]
-        }
+        ) => ContractExecutionAuthorizationFilter::AcceptedMessageKeysFilter {
+            keys: union_lists(keys, keys_b),
+        },
         (
             ContractExecutionAuthorizationFilter::AcceptedMessagesFilter { messages },
             ContractExecutionAuthorizationFilter::AcceptedMessagesFilter {
                 messages: messages_b,
             },
-        ) => {
-            vec![
-                ContractExecutionAuthorizationFilter::AcceptedMessagesFilter {
-                    messages: [messages, messages_b].concat(),
-                },
-            ]
-        }
+        ) => ContractExecutionAuthorizationFilter::AcceptedMessagesFilter {
+            messages: union_lists(messages, messages_b),
+        },
+        // a contract can only have a single filter so the raw messages are widened to their keys
         (
             ContractExecutionAuthorizationFilter::AcceptedMessageKeysFilter { keys },
             ContractExecutionAuthorizationFilter::AcceptedMessagesFilter { messages },
@@ -557,19 +812,128 @@ fn combine_contract_execution_settings(
             ContractExecutionAuthorizationFilter::AcceptedMessagesFilter { messages },
             ContractExecutionAuthorizationFilter::AcceptedMessageKeysFilter { keys },
         ) => {
-            vec![
-                ContractExecutionAuthorizationFilter::AcceptedMessageKeysFilter { keys },
-                ContractExecutionAuthorizationFilter::AcceptedMessagesFilter { messages },
-            ]
+            let message_keys = messages
+                .iter()
+                .map(message_keys)
+                .collect::<Option<Vec<Vec<String>>>>()
+                .ok_or_else(|| {
+                    GrantSpecError::IncompatibleContractFilters(contract_addr.to_string())
+                })?;
+
+            ContractExecutionAuthorizationFilter::AcceptedMessageKeysFilter {
+                keys: union_lists(keys, message_keys.concat()),
+            }
         }
+    })
+}
+
+fn intersect_contract_filters(
+    a: ContractExecutionAuthorizationFilter,
+    b: ContractExecutionAuthorizationFilter,
+    contract_addr: &Addr,
+) -> Result<ContractExecutionAuthorizationFilter, GrantSpecError> {
+    let filter = match (a, b) {
+        (ContractExecutionAuthorizationFilter::AllowAllMessagesFilter, filter)
+        | (filter, ContractExecutionAuthorizationFilter::AllowAllMessagesFilter) => filter,
+        (
+            ContractExecutionAuthorizationFilter::AcceptedMessageKeysFilter { keys },
+            ContractExecutionAuthorizationFilter::AcceptedMessageKeysFilter { keys: keys_b },
+        ) => ContractExecutionAuthorizationFilter::AcceptedMessageKeysFilter {
+            keys: intersect_lists(keys, keys_b),
+        },
+        (
+            ContractExecutionAuthorizationFilter::AcceptedMessagesFilter { messages },
+            ContractExecutionAuthorizationFilter::AcceptedMessagesFilter {
+                messages: messages_b,
+            },
+        ) => ContractExecutionAuthorizationFilter::AcceptedMessagesFilter {
+            messages: intersect_lists(messages, messages_b),
+        },
+        // only the raw messages that the keys allow as well are kept
+        (
+            ContractExecutionAuthorizationFilter::AcceptedMessageKeysFilter { keys },
+            ContractExecutionAuthorizationFilter::AcceptedMessagesFilter { messages },
+        )
+        | (
+            ContractExecutionAuthorizationFilter::AcceptedMessagesFilter { messages },
+            ContractExecutionAuthorizationFilter::AcceptedMessageKeysFilter { keys },
+        ) => ContractExecutionAuthorizationFilter::AcceptedMessagesFilter {
+            messages: messages
+                .into_iter()
+                .filter(|msg| message_keys(msg).is_some_and(|msg_keys| is_subset(&keys, &msg_keys)))
+                .collect(),
+        },
     };
 
-    filters
-        .into_iter()
-        .map(|filter| ContractExecutionSetting {
-            contract_addr: a.contract_addr.clone(),
-            limit: limit.clone(),
-            filter,
-        })
-        .collect()
+    match &filter {
+        ContractExecutionAuthorizationFilter::AcceptedMessageKeysFilter { keys: items }
+            if items.is_empty() =>
+        {
+            Err(GrantSpecError::EmptyMergeIntersection(format!(
+                "messages for contract {contract_addr}"
+            )))
+        }
+        ContractExecutionAuthorizationFilter::AcceptedMessagesFilter { messages }
+            if messages.is_empty() =>
+        {
+            Err(GrantSpecError::EmptyMergeIntersection(format!(
+                "messages for contract {contract_addr}"
+            )))
+        }
+        _ => Ok(filter),
+    }
+}
+
+/// Merges transfer settings so that each (source_port, source_channel) is only listed once.
+/// Settings are kept in the order their channel first appears.
+fn combine_transfer_auths(
+    a_auths: Vec<TransferAuthorizationSetting>,
+    b_auths: Vec<TransferAuthorizationSetting>,
+    strategy: MergeStrategy,
+) -> Result<AuthorizationType, GrantSpecError> {
+    // an empty allow list permits any receiver
+    let allow_list = |list: Vec<String>| (!list.is_empty()).then_some(list);
+
+    let new_auth_settings = try_merge_by_key(
+        [a_auths, b_auths]
+            .concat()
+            .into_iter()
+            .map(|setting| {
+                (
+                    (setting.source_port.clone(), setting.source_channel.clone()),
+                    setting,
+                )
+            })
+            .collect(),
+        |(channel, existing_setting), (_, current_setting)| {
+            let spend_limit = combine_spend_limits(
+                Some(existing_setting.spend_limit),
+                Some(current_setting.spend_limit),
+                strategy,
+            )?
+            .unwrap_or_default();
+
+            let allow_list = combine_optional_lists(
+                allow_list(existing_setting.allow_list),
+                allow_list(current_setting.allow_list),
+                strategy,
+                "allowed receivers",
+            )?
+            .unwrap_or_default();
+
+            Ok::<_, GrantSpecError>((
+                channel,
+                TransferAuthorizationSetting {
+                    spend_limit,
+                    allow_list,
+                    ..existing_setting
+                },
+            ))
+        },
+    )?
+    .into_iter()
+    .map(|(_, setting)| setting)
+    .collect();
+
+    Ok(AuthorizationType::TransferAuthorization(new_auth_settings))
 }
@@ -1,5 +1,8 @@
 use cosmwasm_schema::cw_serde;
-use cosmwasm_std::{coin, coins, Addr, Binary, Coin, CustomMsg, Timestamp};
+use cosmwasm_std::{coin, coins, to_binary, Addr, Binary, Coin, CustomMsg, StdResult, Timestamp};
+use serde::{Deserialize, Serialize};
+
+use crate::{errors::GrantSpecError, grantable_trait::concat_coins};
 
 // use withdraw_rewards_tax_grant::msg::GrantsSpecData as WithdrawTaxGrantsSpecData;
 
@@ -10,13 +13,20 @@ pub enum GrantRequirement {
         grant_type: AuthorizationType,
         granter: Addr,
         grantee: Addr,
-        expiration: Timestamp,
+        expiration: GrantExpiration,
     },
     ContractExec {
         contract_addr: Addr,
         msg: Binary,
         sender: Addr,
     },
+    /// A feegrant allowance so that the grantee can pay fees out of the granter's account
+    FeeAllowanceSpec {
+        allowance: AllowanceType,
+        granter: Addr,
+        grantee: Addr,
+        expiration: GrantExpiration,
+    },
 }
 
 impl From<GrantRequirement> for RevokeRequirement {
@@ -41,11 +51,43 @@ impl From<GrantRequirement> for RevokeRequirement {
                 msg,
                 sender,
             },
+            GrantRequirement::FeeAllowanceSpec {
+                granter, grantee, ..
+            } => RevokeRequirement::RevokeFeeAllowance { granter, grantee },
         }
     }
 }
 
 impl GrantRequirement {
+    /// Resolves a relative expiration against the current block time
+    pub fn resolve_expiration(self, now: Timestamp) -> Self {
+        match self {
+            GrantRequirement::GrantSpec {
+                grant_type,
+                granter,
+                grantee,
+                expiration,
+            } => GrantRequirement::GrantSpec {
+                grant_type,
+                granter,
+                grantee,
+                expiration: expiration.resolve(now),
+            },
+            GrantRequirement::FeeAllowanceSpec {
+                allowance,
+                granter,
+                grantee,
+                expiration,
+            } => GrantRequirement::FeeAllowanceSpec {
+                allowance,
+                granter,
+                grantee,
+                expiration: expiration.resolve(now),
+            },
+            contract_exec @ GrantRequirement::ContractExec { .. } => contract_exec,
+        }
+    }
+
     pub fn generic_auth(
         GrantBase {
             granter,
@@ -63,6 +105,36 @@ impl GrantRequirement {
             expiration,
         }
     }
+    pub fn transfer_auth(
+        GrantBase {
+            granter,
+            grantee,
+            expiration,
+        }: GrantBase,
+        settings: Vec<TransferAuthorizationSetting>,
+    ) -> Self {
+        GrantRequirement::GrantSpec {
+            grant_type: AuthorizationType::TransferAuthorization(settings),
+            granter,
+            grantee,
+            expiration,
+        }
+    }
+    pub fn fee_allowance(
+        GrantBase {
+            granter,
+            grantee,
+            expiration,
+        }: GrantBase,
+        allowance: AllowanceType,
+    ) -> Self {
+        GrantRequirement::FeeAllowanceSpec {
+            allowance,
+            granter,
+            grantee,
+            expiration,
+        }
+    }
     pub fn default_contract_exec_auth(
         base: GrantBase,
         contract_addr: Addr,
@@ -90,6 +162,57 @@ impl GrantRequirement {
         }
     }
 
+    /// A contract execution authorization for the given contracts,
+    /// see `ContractExecutionSetting::builder` for putting together each contract's setting
+    pub fn contract_exec_auth(base: GrantBase, settings: Vec<ContractExecutionSetting>) -> Self {
+        GrantRequirement::GrantSpec {
+            grant_type: AuthorizationType::ContractExecutionAuthorization(settings),
+            granter: base.granter,
+            grantee: base.grantee,
+            expiration: base.expiration,
+        }
+    }
+
+    /// Allows executing exactly the given messages on the contract,
+    /// failing if one of the messages can't be serialized
+    pub fn try_contract_exec_messages_auth<T: Serialize>(
+        base: GrantBase,
+        contract_addr: Addr,
+        messages: Vec<T>,
+        limit_denom: Option<&str>,
+    ) -> StdResult<Self> {
+        let setting = ContractExecutionSetting::builder(contract_addr).messages(&messages)?;
+
+        Ok(Self::contract_exec_auth(
+            base,
+            vec![match limit_denom {
+                Some(limit_denom) => setting.any_amount_of(vec![limit_denom]),
+                None => setting,
+            }
+            .build()],
+        ))
+    }
+
+    /// Allows executing exactly the given messages on the contract while sending along any
+    /// amount of the allowed denoms, failing if one of the messages can't be serialized
+    pub fn try_contract_exec_messages_multidenoms_auth<T: Serialize>(
+        base: GrantBase,
+        contract_addr: Addr,
+        messages: Vec<T>,
+        allowed_denoms: Vec<&str>,
+    ) -> StdResult<Self> {
+        Ok(Self::contract_exec_auth(
+            base,
+            vec![ContractExecutionSetting::builder(contract_addr)
+                .messages(&messages)?
+                .any_amount_of(allowed_denoms)
+                .build()],
+        ))
+    }
+
+    #[deprecated(
+        note = "panics on messages that can't be serialized, use try_contract_exec_messages_auth"
+    )]
     pub fn contract_exec_messages_auth<T>(
         base: GrantBase,
         contract_addr: Addr,
@@ -99,31 +222,12 @@ impl GrantRequirement {
     where
         T: CustomMsg,
     {
-        GrantRequirement::GrantSpec {
-            grant_type: AuthorizationType::ContractExecutionAuthorization(vec![
-                ContractExecutionSetting {
-                    contract_addr,
-                    limit: limit_denom.map_or(
-                        ContractExecutionAuthorizationLimit::default(),
-                        |limit_denom| {
-                            ContractExecutionAuthorizationLimit::single_fund_limit(limit_denom)
-                        },
-                    ),
-                    filter: ContractExecutionAuthorizationFilter::AcceptedMessagesFilter {
-                        messages: messages
-                            .iter()
-                            // normally unwrap would be a nogo but it should be alright here since this is only used for queries
-                            .map(|m| cosmwasm_std::to_binary(m).unwrap())
-                            .collect(),
-                    },
-                },
-            ]),
-            granter: base.granter,
-            grantee: base.grantee,
-            expiration: base.expiration,
-        }
+        Self::try_contract_exec_messages_auth(base, contract_addr, messages, limit_denom).unwrap()
     }
 
+    #[deprecated(
+        note = "panics on messages that can't be serialized, use try_contract_exec_messages_multidenoms_auth"
+    )]
     pub fn contract_exec_messages_mutlidenoms_auth<T>(
         base: GrantBase,
         contract_addr: Addr,
@@ -133,31 +237,18 @@ impl GrantRequirement {
     where
         T: CustomMsg,
     {
-        GrantRequirement::GrantSpec {
-            grant_type: AuthorizationType::ContractExecutionAuthorization(vec![
-                ContractExecutionSetting {
-                    contract_addr,
-                    limit: ContractExecutionAuthorizationLimit::multiple_fund_limits(
-                        allowed_denoms,
-                    ),
-                    filter: ContractExecutionAuthorizationFilter::AcceptedMessagesFilter {
-                        messages: messages
-                            .iter()
-                            // normally unwrap would be a nogo but it should be alright here since this is only used for queries
-                            .map(|m| cosmwasm_std::to_binary(m).unwrap())
-                            .collect(),
-                    },
-                },
-            ]),
-            granter: base.granter,
-            grantee: base.grantee,
-            expiration: base.expiration,
-        }
+        Self::try_contract_exec_messages_multidenoms_auth(
+            base,
+            contract_addr,
+            messages,
+            allowed_denoms,
+        )
+        .unwrap()
     }
 }
 
 #[cw_serde]
-#[derive(Eq)]
+#[derive(Eq, Hash)]
 pub enum RevokeRequirement {
     RevokeSpec {
         grant_type: String,
@@ -169,6 +260,10 @@ pub enum RevokeRequirement {
         msg: Binary,
         sender: Addr,
     },
+    RevokeFeeAllowance {
+        granter: Addr,
+        grantee: Addr,
+    },
 }
 
 #[cw_serde]
@@ -196,9 +291,16 @@ impl AuthorizationType {
             AuthorizationType::SendAuthorization { .. } => {
                 "/cosmos.bank.v1beta1.MsgSend".to_string()
             }
-            AuthorizationType::StakeAuthorization { .. } => {
-                "/cosmos.staking.v1beta1.MsgDelegate".to_string()
+            AuthorizationType::StakeAuthorization {
+                authorization_type, ..
+            } => match authorization_type {
+                StakeAuthorizationType::Undelegate => "/cosmos.staking.v1beta1.MsgUndelegate",
+                StakeAuthorizationType::Redelegate => "/cosmos.staking.v1beta1.MsgBeginRedelegate",
+                StakeAuthorizationType::Unspecified | StakeAuthorizationType::Delegate => {
+                    "/cosmos.staking.v1beta1.MsgDelegate"
+                }
             }
+            .to_string(),
             AuthorizationType::ContractExecutionAuthorization { .. } => {
                 "/cosmwasm.wasm.v1.MsgExecuteContract".to_string()
             }
@@ -212,12 +314,40 @@ impl AuthorizationType {
 #[cw_serde]
 #[derive(Eq, Default)]
 pub struct TransferAuthorizationSetting {
-    source_port: String,
-    source_channel: String,
-    // spend limitation on the channel
-    spend_limit: Vec<Coin>,
-    // allow list of receivers, an empty allow list permits any receiver address
-    allow_list: Vec<String>,
+    /// the port on which the packet will be sent
+    pub source_port: String,
+    /// the channel by which the packet will be sent
+    pub source_channel: String,
+    /// spend limitation on the channel
+    pub spend_limit: Vec<Coin>,
+    /// allow list of receivers, an empty allow list permits any receiver address
+    pub allow_list: Vec<String>,
+}
+impl TransferAuthorizationSetting {
+    /// Allows sending up to `spend_limit` over the given port and channel to any receiver
+    pub fn new(
+        source_port: impl Into<String>,
+        source_channel: impl Into<String>,
+        spend_limit: Vec<Coin>,
+    ) -> Self {
+        TransferAuthorizationSetting {
+            source_port: source_port.into(),
+            source_channel: source_channel.into(),
+            spend_limit,
+            allow_list: vec![],
+        }
+    }
+
+    /// Same as `new` on the default ics20 "transfer" port
+    pub fn transfer_channel(source_channel: impl Into<String>, spend_limit: Vec<Coin>) -> Self {
+        Self::new("transfer", source_channel, spend_limit)
+    }
+
+    /// Restricts the receivers on the channel to the given addresses
+    pub fn with_allow_list(mut self, allow_list: Vec<impl Into<String>>) -> Self {
+        self.allow_list = allow_list.into_iter().map(|a| a.into()).collect();
+        self
+    }
 }
 
 #[cw_serde]
@@ -263,6 +393,85 @@ pub enum StakeAuthorizationPolicy {
     DenyList(StakeAuthorizationValidators),
 }
 
+impl ContractExecutionSetting {
+    /// Starts a setting for the contract that allows unlimited calls of any message without funds
+    pub fn builder(contract_addr: Addr) -> ContractExecutionSettingBuilder {
+        ContractExecutionSettingBuilder {
+            contract_addr,
+            calls: None,
+            funds: vec![],
+            filter: ContractExecutionAuthorizationFilter::AllowAllMessagesFilter,
+        }
+    }
+}
+
+/// Puts together a `ContractExecutionSetting` from any combination of a calls limit,
+/// fund limits and a message filter. The limit type is picked from the limits that are set.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ContractExecutionSettingBuilder {
+    contract_addr: Addr,
+    calls: Option<u64>,
+    funds: Vec<Coin>,
+    filter: ContractExecutionAuthorizationFilter,
+}
+
+impl ContractExecutionSettingBuilder {
+    /// Limits how many times the contract may be executed
+    pub fn max_calls(mut self, calls: u64) -> Self {
+        self.calls = Some(calls);
+        self
+    }
+
+    /// Allows sending up to the given funds along, amounts for the same denom are added up
+    pub fn max_funds(mut self, funds: Vec<Coin>) -> Self {
+        self.funds = concat_coins(self.funds, funds);
+        self
+    }
+
+    /// Allows sending any amount of the given denoms along
+    pub fn any_amount_of(self, denoms: Vec<impl Into<String>>) -> Self {
+        self.max_funds(denoms.into_iter().map(|d| coin(u128::MAX, d)).collect())
+    }
+
+    /// Allows any message, replacing the current filter
+    pub fn allow_all_messages(mut self) -> Self {
+        self.filter = ContractExecutionAuthorizationFilter::AllowAllMessagesFilter;
+        self
+    }
+
+    /// Only allows messages with the given top level keys, replacing the current filter
+    pub fn message_keys(mut self, keys: Vec<impl Into<String>>) -> Self {
+        self.filter = ContractExecutionAuthorizationFilter::AcceptedMessageKeysFilter {
+            keys: keys.into_iter().map(Into::into).collect(),
+        };
+        self
+    }
+
+    /// Only allows the messages of `T` that have a top level key, replacing the current filter
+    pub fn all_message_keys<T: MessageKeys>(mut self) -> Self {
+        self.filter = ContractExecutionAuthorizationFilter::all_message_keys::<T>();
+        self
+    }
+
+    /// Only allows exactly the given messages, replacing the current filter
+    pub fn messages<T: Serialize>(mut self, messages: &[T]) -> StdResult<Self> {
+        self.filter = ContractExecutionAuthorizationFilter::AcceptedMessagesFilter {
+            messages: messages.iter().map(to_binary).collect::<StdResult<_>>()?,
+        };
+        Ok(self)
+    }
+
+    pub fn build(self) -> ContractExecutionSetting {
+        ContractExecutionSetting {
+            contract_addr: self.contract_addr,
+            limit: ContractExecutionAuthorizationLimit::from_calls_and_funds(
+                self.calls, self.funds,
+            ),
+            filter: self.filter,
+        }
+    }
+}
+
 #[cw_serde]
 #[derive(Eq)]
 pub enum ContractExecutionAuthorizationLimit {
@@ -289,6 +498,41 @@ impl Default for ContractExecutionAuthorizationLimit {
     }
 }
 impl ContractExecutionAuthorizationLimit {
+    /// The number of calls allowed (None when unlimited) and the funds that may be sent along.
+    /// A MaxCallsLimit doesn't allow any funds to be sent while a MaxFundsLimit doesn't limit calls.
+    pub fn calls_and_funds(&self) -> (Option<u64>, Vec<Coin>) {
+        match self {
+            ContractExecutionAuthorizationLimit::MaxCallsLimit { remaining } => {
+                (Some(*remaining), vec![])
+            }
+            ContractExecutionAuthorizationLimit::MaxFundsLimit { amounts } => {
+                (None, amounts.clone())
+            }
+            ContractExecutionAuthorizationLimit::CombinedLimit {
+                calls_remaining,
+                amounts,
+            } => (Some(*calls_remaining), amounts.clone()),
+        }
+    }
+
+    /// The inverse of `calls_and_funds`, picking the limit type that enforces exactly those bounds
+    pub fn from_calls_and_funds(calls: Option<u64>, funds: Vec<Coin>) -> Self {
+        match (calls, funds) {
+            (Some(remaining), funds) if funds.is_empty() => {
+                ContractExecutionAuthorizationLimit::MaxCallsLimit { remaining }
+            }
+            (Some(calls_remaining), amounts) => {
+                ContractExecutionAuthorizationLimit::CombinedLimit {
+                    calls_remaining,
+                    amounts,
+                }
+            }
+            // the wasm module rejects a MaxFundsLimit without any amounts
+            (None, funds) if funds.is_empty() => ContractExecutionAuthorizationLimit::default(),
+            (None, amounts) => ContractExecutionAuthorizationLimit::MaxFundsLimit { amounts },
+        }
+    }
+
     pub fn single_fund_limit(denom: impl Into<String>) -> Self {
         self::ContractExecutionAuthorizationLimit::MaxFundsLimit {
             amounts: coins(u128::MAX, denom),
@@ -323,9 +567,183 @@ pub enum ContractExecutionAuthorizationFilter {
     },
 }
 
+impl ContractExecutionAuthorizationFilter {
+    /// Accepts every message of `T` that has a top level key
+    pub fn all_message_keys<T: MessageKeys>() -> Self {
+        ContractExecutionAuthorizationFilter::AcceptedMessageKeysFilter {
+            keys: T::message_keys().into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+/// The top level keys a contract's messages are sent with,
+/// implemented with `#[derive(MessageKeys)]` below `#[cw_serde]`
+pub trait MessageKeys {
+    /// the generated `{Msg}Key` enum that has a variant for every message with a key
+    type Key: Into<String>;
+
+    fn message_keys() -> Vec<Self::Key>;
+}
+
+/// The feegrant allowances a granter can give to a grantee.
+/// The expiration of the allowance is taken from the requirement it is part of.
+#[cw_serde]
+#[derive(Eq)]
+pub enum AllowanceType {
+    /// BasicAllowance allows the grantee to pay fees until the spend limit is used up
+    BasicAllowance {
+        /// the maximum amount of tokens that can be spent, None means there is no limit
+        spend_limit: Option<Vec<Coin>>,
+    },
+    /// PeriodicAllowance extends a basic allowance with a limit that is reset every period
+    PeriodicAllowance {
+        /// the maximum amount of tokens that can be spent over the lifetime of the allowance
+        spend_limit: Option<Vec<Coin>>,
+        /// the length of a period in seconds
+        period: u64,
+        /// the maximum amount of tokens that can be spent within a period
+        period_spend_limit: Vec<Coin>,
+    },
+    /// AllowedMsgAllowance restricts another allowance to fees of transactions that only
+    /// contain the allowed messages
+    AllowedMsgAllowance {
+        allowance: Box<AllowanceType>,
+        /// the type urls of the allowed messages
+        allowed_messages: Vec<String>,
+    },
+}
+impl AllowanceType {
+    pub fn basic(spend_limit: Option<Vec<Coin>>) -> Self {
+        AllowanceType::BasicAllowance { spend_limit }
+    }
+
+    /// Restricts the allowance to fees for transactions made up of the given message types
+    pub fn only_messages(self, allowed_messages: Vec<impl Into<String>>) -> Self {
+        AllowanceType::AllowedMsgAllowance {
+            allowance: Box::new(self),
+            allowed_messages: allowed_messages.into_iter().map(|m| m.into()).collect(),
+        }
+    }
+}
+
+/// A grant that already exists on chain as reported by the authz module's grant queries
+#[cw_serde]
+#[derive(Eq)]
+pub struct ExistingGrant {
+    pub grant_type: AuthorizationType,
+    pub granter: Addr,
+    pub grantee: Addr,
+    /// grants without an expiration never expire
+    pub expiration: Option<Timestamp>,
+}
+
 #[cw_serde]
 pub struct GrantBase {
     pub granter: Addr,
     pub grantee: Addr,
-    pub expiration: Timestamp,
+    pub expiration: GrantExpiration,
+}
+
+/// When a grant expires.
+/// Relative expirations are resolved against the block time in `Grantable::query_grants`.
+///
+/// Expirations used to be a plain timestamp, which is still accepted as `AtTime` so that
+/// messages built against older versions keep working. They are always serialized in the new form.
+#[cw_serde]
+#[derive(Copy, Eq)]
+#[serde(from = "GrantExpirationCompat")]
+pub enum GrantExpiration {
+    /// authz and feegrant both allow leaving out the expiration so the grant lasts until it is revoked
+    Never,
+    /// the grant expires at the given block time
+    AtTime(Timestamp),
+    /// the grant expires this many seconds after the block time it is resolved at
+    SecondsFromNow(u64),
+}
+
+/// Either form that an expiration can be deserialized from
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum GrantExpirationCompat {
+    Expiration(GrantExpirationVariants),
+    Timestamp(Timestamp),
+}
+
+/// Mirrors `GrantExpiration` to deserialize its current form without recursing into the compat form
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "snake_case")]
+enum GrantExpirationVariants {
+    Never,
+    AtTime(Timestamp),
+    SecondsFromNow(u64),
+}
+
+impl From<GrantExpirationCompat> for GrantExpiration {
+    fn from(expiration: GrantExpirationCompat) -> Self {
+        match expiration {
+            GrantExpirationCompat::Expiration(GrantExpirationVariants::Never) => {
+                GrantExpiration::Never
+            }
+            GrantExpirationCompat::Expiration(GrantExpirationVariants::AtTime(timestamp))
+            | GrantExpirationCompat::Timestamp(timestamp) => GrantExpiration::AtTime(timestamp),
+            GrantExpirationCompat::Expiration(GrantExpirationVariants::SecondsFromNow(seconds)) => {
+                GrantExpiration::SecondsFromNow(seconds)
+            }
+        }
+    }
+}
+
+impl From<Timestamp> for GrantExpiration {
+    fn from(timestamp: Timestamp) -> Self {
+        GrantExpiration::AtTime(timestamp)
+    }
+}
+
+impl GrantExpiration {
+    /// Turns a relative expiration into an absolute one, the other expirations are returned as is
+    pub fn resolve(self, now: Timestamp) -> Self {
+        match self {
+            GrantExpiration::SecondsFromNow(seconds) => {
+                GrantExpiration::AtTime(now.plus_seconds(seconds))
+            }
+            expiration => expiration,
+        }
+    }
+
+    /// The timestamp to put on the grant message, None being a grant that never expires
+    pub fn timestamp(&self) -> Result<Option<Timestamp>, GrantSpecError> {
+        match self {
+            GrantExpiration::Never => Ok(None),
+            GrantExpiration::AtTime(timestamp) => Ok(Some(*timestamp)),
+            GrantExpiration::SecondsFromNow(seconds) => {
+                Err(GrantSpecError::UnresolvedExpiration(*seconds))
+            }
+        }
+    }
+
+    /// Returns whichever expiration lasts longer with `Never` outlasting everything.
+    /// Absolute and relative expirations can only be compared once they've been resolved.
+    pub fn later(self, other: GrantExpiration) -> Result<Self, GrantSpecError> {
+        match (self, other) {
+            (GrantExpiration::Never, _) | (_, GrantExpiration::Never) => Ok(GrantExpiration::Never),
+            (GrantExpiration::AtTime(a), GrantExpiration::AtTime(b)) => {
+                Ok(GrantExpiration::AtTime(a.max(b)))
+            }
+            (GrantExpiration::SecondsFromNow(a), GrantExpiration::SecondsFromNow(b)) => {
+                Ok(GrantExpiration::SecondsFromNow(a.max(b)))
+            }
+            (GrantExpiration::SecondsFromNow(seconds), GrantExpiration::AtTime(_))
+            | (GrantExpiration::AtTime(_), GrantExpiration::SecondsFromNow(seconds)) => {
+                Err(GrantSpecError::UnresolvedExpiration(seconds))
+            }
+        }
+    }
+}
+
+/// Resolves the relative expirations of all the requirements against the current block time
+pub fn resolve_grant_reqs(grants: Vec<GrantRequirement>, now: Timestamp) -> Vec<GrantRequirement> {
+    grants
+        .into_iter()
+        .map(|grant| grant.resolve_expiration(now))
+        .collect()
 }
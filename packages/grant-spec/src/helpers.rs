@@ -0,0 +1,29 @@
+use std::collections::BTreeMap;
+
+use cosmwasm_std::{from_slice, Binary, Coin, Uint128};
+use serde::de::IgnoredAny;
+
+/// Returns the top level keys of a json contract message, or None if the message isn't a json object
+pub fn message_keys(msg: &Binary) -> Option<Vec<String>> {
+    from_slice::<BTreeMap<String, IgnoredAny>>(msg.as_slice())
+        .ok()
+        .map(|keys| keys.into_keys().collect())
+}
+
+/// Checks that every coin in `required` fits within the amount of the same denom in `available`
+pub fn coins_cover(available: &[Coin], required: &[Coin]) -> bool {
+    required.iter().all(|required_coin| {
+        available
+            .iter()
+            .filter(|coin| coin.denom.eq(&required_coin.denom))
+            .fold(Uint128::zero(), |total, coin| {
+                total.saturating_add(coin.amount)
+            })
+            .ge(&required_coin.amount)
+    })
+}
+
+/// Checks that every item in `required` is also in `available`
+pub fn is_subset<T: PartialEq>(available: &[T], required: &[T]) -> bool {
+    required.iter().all(|item| available.contains(item))
+}
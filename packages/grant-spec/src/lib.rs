@@ -1,5 +1,21 @@
+// lets the generated code from cw-grant-spec-derive refer to this crate by name from within it
+extern crate self as cw_grant_spec;
+
+pub mod coverage;
+pub mod decoding;
+pub mod describe;
+pub mod encoding;
+pub mod errors;
 pub mod grantable_trait;
 pub mod grants;
+pub mod helpers;
+pub mod planner;
+pub mod proto;
+pub mod validation;
 
 #[cfg(test)]
 pub mod tests;
+
+pub use cw_grant_spec_derive::{grant_spec_queries, MessageKeys};
+// the trait shares its name with the derive so a single import brings in both
+pub use grants::MessageKeys;
@@ -0,0 +1,130 @@
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::CosmosMsg;
+
+use crate::{
+    coverage::is_grant_req_covered,
+    errors::GrantSpecError,
+    grantable_trait::{dedupe_grant_reqs_with_settings, DedupeSettings},
+    grants::{ExistingGrant, GrantRequirement},
+};
+
+/// The default number of messages in a single transaction, chosen to keep transactions well
+/// below the gas limits of most wallets
+pub const DEFAULT_MAX_MSGS_PER_TX: usize = 10;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PlannerSettings {
+    /// the most messages a single transaction in the plan may contain
+    pub max_msgs_per_tx: usize,
+    pub dedupe: DedupeSettings,
+}
+
+impl Default for PlannerSettings {
+    fn default() -> Self {
+        PlannerSettings {
+            max_msgs_per_tx: DEFAULT_MAX_MSGS_PER_TX,
+            dedupe: DedupeSettings::default(),
+        }
+    }
+}
+
+/// The order in which requirements need to be signed
+#[cw_serde]
+#[derive(Copy, Eq, PartialOrd, Ord)]
+pub enum PlanStage {
+    /// authz grants and feegrant allowances, these don't depend on anything
+    Grants,
+    /// contract executions that expect the grants to exist already
+    ContractExecutions,
+}
+
+impl PlanStage {
+    fn of(grant: &GrantRequirement) -> Self {
+        match grant {
+            GrantRequirement::GrantSpec { .. } | GrantRequirement::FeeAllowanceSpec { .. } => {
+                PlanStage::Grants
+            }
+            GrantRequirement::ContractExec { .. } => PlanStage::ContractExecutions,
+        }
+    }
+}
+
+/// A single transaction the granter needs to sign
+#[cw_serde]
+pub struct GrantTxBatch {
+    pub stage: PlanStage,
+    pub requirements: Vec<GrantRequirement>,
+    /// every requirement in the batch is already covered by the existing grants
+    pub skippable: bool,
+}
+
+impl GrantTxBatch {
+    /// Encodes the batch as the messages of its transaction
+    pub fn to_msgs(&self) -> Result<Vec<CosmosMsg>, GrantSpecError> {
+        self.requirements
+            .iter()
+            .map(GrantRequirement::to_cosmos_msg)
+            .collect()
+    }
+}
+
+#[cw_serde]
+pub struct GrantPlan {
+    pub batches: Vec<GrantTxBatch>,
+}
+
+impl GrantPlan {
+    /// The batches that still need to be signed
+    pub fn pending_batches(&self) -> impl Iterator<Item = &GrantTxBatch> {
+        self.batches.iter().filter(|batch| !batch.skippable)
+    }
+}
+
+/// Plans the transactions the granter needs to sign to fulfill the requirements.
+///
+/// The requirements are deduped, ordered so that every grant is signed before the contract
+/// executions that depend on it and split into batches of at most `max_msgs_per_tx` messages.
+/// A batch never mixes stages, and within a stage the requirements the existing grants already
+/// cover are batched before and apart from the rest, so those batches can be skipped on their own.
+/// The same requirements and existing grants always result in the same plan.
+pub fn plan_grant_txs(
+    requirements: Vec<GrantRequirement>,
+    existing_grants: &[ExistingGrant],
+    settings: &PlannerSettings,
+) -> Result<GrantPlan, GrantSpecError> {
+    if settings.max_msgs_per_tx == 0 {
+        return Err(GrantSpecError::ZeroMaxMsgsPerTx);
+    }
+
+    let mut requirements = dedupe_grant_reqs_with_settings(requirements, settings.dedupe.clone())?
+        .into_iter()
+        .map(|requirement| {
+            let covered = is_grant_req_covered(&requirement, existing_grants);
+            (PlanStage::of(&requirement), covered, requirement)
+        })
+        .collect::<Vec<_>>();
+    // within a stage the covered requirements come first, the sort is stable so the order
+    // among them is kept
+    requirements.sort_by_key(|(stage, covered, _)| (*stage, !covered));
+
+    let mut batches: Vec<GrantTxBatch> = vec![];
+    for (stage, covered, requirement) in requirements {
+        match batches.last_mut() {
+            // covered requirements are batched apart from the rest so that they can be skipped
+            Some(batch)
+                if batch.stage.eq(&stage)
+                    && batch.skippable.eq(&covered)
+                    && batch.requirements.len() < settings.max_msgs_per_tx =>
+            {
+                batch.requirements.push(requirement);
+            }
+            _ => batches.push(GrantTxBatch {
+                stage,
+                requirements: vec![requirement],
+                skippable: covered,
+            }),
+        }
+    }
+
+    Ok(GrantPlan { batches })
+}
@@ -0,0 +1,115 @@
+//! Protobuf definitions for the authorization types that `cosmos-sdk-proto` does not ship.
+//! These mirror the wasmd `authz.proto`, the bank `SendAuthorization` with its `allow_list`
+//! and the ibc-go `TransferAuthorization`.
+
+use cosmos_sdk_proto::cosmos::base::v1beta1::Coin;
+use prost_types::Any;
+
+pub const GENERIC_AUTHORIZATION_TYPE_URL: &str = "/cosmos.authz.v1beta1.GenericAuthorization";
+pub const SEND_AUTHORIZATION_TYPE_URL: &str = "/cosmos.bank.v1beta1.SendAuthorization";
+pub const STAKE_AUTHORIZATION_TYPE_URL: &str = "/cosmos.staking.v1beta1.StakeAuthorization";
+pub const CONTRACT_EXECUTION_AUTHORIZATION_TYPE_URL: &str =
+    "/cosmwasm.wasm.v1.ContractExecutionAuthorization";
+pub const TRANSFER_AUTHORIZATION_TYPE_URL: &str =
+    "/ibc.applications.transfer.v1.TransferAuthorization";
+
+pub const MAX_CALLS_LIMIT_TYPE_URL: &str = "/cosmwasm.wasm.v1.MaxCallsLimit";
+pub const MAX_FUNDS_LIMIT_TYPE_URL: &str = "/cosmwasm.wasm.v1.MaxFundsLimit";
+pub const COMBINED_LIMIT_TYPE_URL: &str = "/cosmwasm.wasm.v1.CombinedLimit";
+
+pub const ALLOW_ALL_MESSAGES_FILTER_TYPE_URL: &str = "/cosmwasm.wasm.v1.AllowAllMessagesFilter";
+pub const ACCEPTED_MESSAGE_KEYS_FILTER_TYPE_URL: &str =
+    "/cosmwasm.wasm.v1.AcceptedMessageKeysFilter";
+pub const ACCEPTED_MESSAGES_FILTER_TYPE_URL: &str = "/cosmwasm.wasm.v1.AcceptedMessagesFilter";
+
+pub const BASIC_ALLOWANCE_TYPE_URL: &str = "/cosmos.feegrant.v1beta1.BasicAllowance";
+pub const PERIODIC_ALLOWANCE_TYPE_URL: &str = "/cosmos.feegrant.v1beta1.PeriodicAllowance";
+pub const ALLOWED_MSG_ALLOWANCE_TYPE_URL: &str = "/cosmos.feegrant.v1beta1.AllowedMsgAllowance";
+
+pub const MSG_GRANT_TYPE_URL: &str = "/cosmos.authz.v1beta1.MsgGrant";
+pub const MSG_REVOKE_TYPE_URL: &str = "/cosmos.authz.v1beta1.MsgRevoke";
+pub const MSG_EXECUTE_CONTRACT_TYPE_URL: &str = "/cosmwasm.wasm.v1.MsgExecuteContract";
+pub const MSG_GRANT_ALLOWANCE_TYPE_URL: &str = "/cosmos.feegrant.v1beta1.MsgGrantAllowance";
+pub const MSG_REVOKE_ALLOWANCE_TYPE_URL: &str = "/cosmos.feegrant.v1beta1.MsgRevokeAllowance";
+
+/// SendAuthorization as of cosmos-sdk v0.47 which added the `allow_list`
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SendAuthorization {
+    #[prost(message, repeated, tag = "1")]
+    pub spend_limit: Vec<Coin>,
+    /// allow_list specifies an optional list of addresses to whom the grantee can send tokens
+    #[prost(string, repeated, tag = "2")]
+    pub allow_list: Vec<String>,
+}
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ContractExecutionAuthorization {
+    #[prost(message, repeated, tag = "1")]
+    pub grants: Vec<ContractGrant>,
+}
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ContractGrant {
+    #[prost(string, tag = "1")]
+    pub contract: String,
+    /// one of MaxCallsLimit, MaxFundsLimit or CombinedLimit
+    #[prost(message, optional, tag = "2")]
+    pub limit: Option<Any>,
+    /// one of AllowAllMessagesFilter, AcceptedMessageKeysFilter or AcceptedMessagesFilter
+    #[prost(message, optional, tag = "3")]
+    pub filter: Option<Any>,
+}
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct MaxCallsLimit {
+    #[prost(uint64, tag = "1")]
+    pub remaining: u64,
+}
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct MaxFundsLimit {
+    #[prost(message, repeated, tag = "1")]
+    pub amounts: Vec<Coin>,
+}
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CombinedLimit {
+    #[prost(uint64, tag = "1")]
+    pub calls_remaining: u64,
+    #[prost(message, repeated, tag = "2")]
+    pub amounts: Vec<Coin>,
+}
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct AllowAllMessagesFilter {}
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct AcceptedMessageKeysFilter {
+    #[prost(string, repeated, tag = "1")]
+    pub keys: Vec<String>,
+}
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct AcceptedMessagesFilter {
+    #[prost(bytes = "vec", repeated, tag = "1")]
+    pub messages: Vec<Vec<u8>>,
+}
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct TransferAuthorization {
+    #[prost(message, repeated, tag = "1")]
+    pub allocations: Vec<Allocation>,
+}
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Allocation {
+    #[prost(string, tag = "1")]
+    pub source_port: String,
+    #[prost(string, tag = "2")]
+    pub source_channel: String,
+    #[prost(message, repeated, tag = "3")]
+    pub spend_limit: Vec<Coin>,
+    /// an empty allow list permits any receiver address
+    #[prost(string, repeated, tag = "4")]
+    pub allow_list: Vec<String>,
+}
@@ -1,14 +1,48 @@
 use std::vec;
 
-use cosmwasm_std::{coin, coins, Addr, Timestamp};
+use cosmos_sdk_proto::{
+    cosmos::authz::v1beta1::{
+        GenericAuthorization, GrantAuthorization, MsgGrant, MsgRevoke, QueryGranterGrantsResponse,
+    },
+    cosmos::feegrant::v1beta1::{BasicAllowance, MsgGrantAllowance},
+    cosmwasm::wasm::v1::MsgExecuteContract,
+    traits::Message,
+};
+use cosmwasm_schema::{cw_serde, QueryResponses};
+use cosmwasm_std::{
+    coin, coins, from_binary, testing::mock_env, to_binary, Addr, Binary, Coin, CosmosMsg,
+    StdResult, Timestamp,
+};
 
 use crate::{
-    grantable_trait::dedupe_grant_reqs,
+    coverage::missing_grant_reqs,
+    decoding::decode_granter_grants_response,
+    describe::{
+        describe_grant_reqs, descriptions_to_text, format_coins, format_coins_with_metadata,
+        DenomDisplay, DescriptionParam, DescriptionValue, GrantDescription,
+    },
+    encoding::{grant_reqs_to_msgs, proto_timestamp, revoke_reqs_to_anys},
+    errors::GrantSpecError,
+    grant_spec_queries,
+    grantable_trait::{
+        dedupe_grant_reqs, dedupe_grant_reqs_with_settings, DedupeSettings, GrantStructure,
+        Grantable, MaxTokensMerge, MergeStrategy,
+    },
     grants::{
-        AuthorizationType, ContractExecutionAuthorizationFilter,
-        ContractExecutionAuthorizationLimit, ContractExecutionSetting, GrantRequirement,
-        StakeAuthorizationPolicy, StakeAuthorizationType, StakeAuthorizationValidators,
+        AllowanceType, AuthorizationType, ContractExecutionAuthorizationFilter,
+        ContractExecutionAuthorizationLimit, ContractExecutionSetting, ExistingGrant, GrantBase,
+        GrantExpiration, GrantRequirement, RevokeRequirement, StakeAuthorizationPolicy,
+        StakeAuthorizationType, StakeAuthorizationValidators, TransferAuthorizationSetting,
+    },
+    helpers::message_keys,
+    planner::{plan_grant_txs, GrantTxBatch, PlanStage, PlannerSettings},
+    proto::{
+        ContractExecutionAuthorization, BASIC_ALLOWANCE_TYPE_URL,
+        CONTRACT_EXECUTION_AUTHORIZATION_TYPE_URL, GENERIC_AUTHORIZATION_TYPE_URL,
+        MSG_EXECUTE_CONTRACT_TYPE_URL, MSG_GRANT_ALLOWANCE_TYPE_URL, MSG_GRANT_TYPE_URL,
+        MSG_REVOKE_ALLOWANCE_TYPE_URL, MSG_REVOKE_TYPE_URL,
     },
+    MessageKeys,
 };
 
 #[test]
@@ -30,7 +64,7 @@ pub fn dedupe_basic_grants() {
                 },
                 granter: granter1.clone(),
                 grantee: grantee1.clone(),
-                expiration: Timestamp::from_seconds(0)
+                expiration: Timestamp::from_seconds(0).into()
             },
             GrantRequirement::GrantSpec {
                 grant_type: AuthorizationType::StakeAuthorization {
@@ -40,9 +74,10 @@ pub fn dedupe_basic_grants() {
                 },
                 granter: granter1.clone(),
                 grantee: grantee1.clone(),
-                expiration: Timestamp::from_seconds(0)
+                expiration: Timestamp::from_seconds(0).into()
             }
-        ]),
+        ])
+        .unwrap(),
         vec![GrantRequirement::GrantSpec {
             grant_type: AuthorizationType::StakeAuthorization {
                 max_tokens: None,
@@ -51,7 +86,7 @@ pub fn dedupe_basic_grants() {
             },
             granter: granter1.clone(),
             grantee: grantee1.clone(),
-            expiration: Timestamp::from_seconds(0)
+            expiration: Timestamp::from_seconds(0).into()
         }]
     );
 
@@ -69,7 +104,7 @@ pub fn dedupe_basic_grants() {
                 },
                 granter: granter1.clone(),
                 grantee: grantee1.clone(),
-                expiration: Timestamp::from_seconds(0)
+                expiration: Timestamp::from_seconds(0).into()
             },
             GrantRequirement::GrantSpec {
                 grant_type: AuthorizationType::StakeAuthorization {
@@ -83,9 +118,10 @@ pub fn dedupe_basic_grants() {
                 },
                 granter: granter1.clone(),
                 grantee: grantee1.clone(),
-                expiration: Timestamp::from_seconds(0)
+                expiration: Timestamp::from_seconds(0).into()
             }
-        ]),
+        ])
+        .unwrap(),
         vec![GrantRequirement::GrantSpec {
             grant_type: AuthorizationType::StakeAuthorization {
                 max_tokens: None,
@@ -98,7 +134,7 @@ pub fn dedupe_basic_grants() {
             },
             granter: granter1.clone(),
             grantee: grantee1.clone(),
-            expiration: Timestamp::from_seconds(0)
+            expiration: Timestamp::from_seconds(0).into()
         }]
     );
 
@@ -112,7 +148,7 @@ pub fn dedupe_basic_grants() {
                 },
                 granter: granter1.clone(),
                 grantee: grantee1.clone(),
-                expiration: Timestamp::from_seconds(0)
+                expiration: Timestamp::from_seconds(0).into()
             },
             GrantRequirement::GrantSpec {
                 grant_type: AuthorizationType::SendAuthorization {
@@ -121,7 +157,7 @@ pub fn dedupe_basic_grants() {
                 },
                 granter: granter1.clone(),
                 grantee: grantee1.clone(),
-                expiration: Timestamp::from_seconds(0)
+                expiration: Timestamp::from_seconds(0).into()
             },
             GrantRequirement::GrantSpec {
                 grant_type: AuthorizationType::SendAuthorization {
@@ -130,9 +166,10 @@ pub fn dedupe_basic_grants() {
                 },
                 granter: granter1.clone(),
                 grantee: grantee2.clone(),
-                expiration: Timestamp::from_seconds(0)
+                expiration: Timestamp::from_seconds(0).into()
             }
-        ]),
+        ])
+        .unwrap(),
         vec![
             GrantRequirement::GrantSpec {
                 grant_type: AuthorizationType::SendAuthorization {
@@ -141,7 +178,7 @@ pub fn dedupe_basic_grants() {
                 },
                 granter: granter1.clone(),
                 grantee: grantee1.clone(),
-                expiration: Timestamp::from_seconds(0)
+                expiration: Timestamp::from_seconds(0).into()
             },
             GrantRequirement::GrantSpec {
                 grant_type: AuthorizationType::SendAuthorization {
@@ -150,12 +187,113 @@ pub fn dedupe_basic_grants() {
                 },
                 granter: granter1.clone(),
                 grantee: grantee2.clone(),
-                expiration: Timestamp::from_seconds(0)
+                expiration: Timestamp::from_seconds(0).into()
             }
         ]
     );
 }
 
+#[test]
+pub fn dedupe_keeps_order() {
+    let base = GrantBase {
+        granter: Addr::unchecked("granter1"),
+        grantee: Addr::unchecked("grantee1"),
+        expiration: Timestamp::from_seconds(0).into(),
+    };
+    let contract_exec = GrantRequirement::ContractExec {
+        contract_addr: Addr::unchecked("contract1"),
+        msg: to_binary(&"grant").unwrap(),
+        sender: base.granter.clone(),
+    };
+    let send_auth = GrantRequirement::GrantSpec {
+        grant_type: AuthorizationType::SendAuthorization {
+            spend_limit: Some(coins(100, "ujuno")),
+            allow_list: None,
+        },
+        granter: base.granter.clone(),
+        grantee: base.grantee.clone(),
+        expiration: Timestamp::from_seconds(0).into(),
+    };
+    let withdraw_auth = GrantRequirement::generic_auth(
+        base.clone(),
+        "/cosmos.distribution.v1beta1.MsgWithdrawDelegatorReward",
+    );
+
+    assert_eq!(
+        dedupe_grant_reqs(vec![
+            contract_exec.clone(),
+            withdraw_auth.clone(),
+            send_auth,
+            contract_exec.clone(),
+            withdraw_auth.clone(),
+            // authz only keeps one grant per message type so the generic one takes over
+            GrantRequirement::generic_auth(base.clone(), "/cosmos.bank.v1beta1.MsgSend"),
+        ])
+        .unwrap(),
+        vec![
+            withdraw_auth,
+            GrantRequirement::generic_auth(base, "/cosmos.bank.v1beta1.MsgSend"),
+            contract_exec,
+        ]
+    );
+}
+
+#[test]
+pub fn dedupe_stake_max_tokens() {
+    let granter1 = Addr::unchecked("granter1");
+    let grantee1 = Addr::unchecked("grantee1");
+    let stake_req = |max_tokens: Option<Coin>| GrantRequirement::GrantSpec {
+        grant_type: AuthorizationType::StakeAuthorization {
+            max_tokens,
+            authorization_type: StakeAuthorizationType::Delegate,
+            validators: None,
+        },
+        granter: granter1.clone(),
+        grantee: grantee1.clone(),
+        expiration: Timestamp::from_seconds(0).into(),
+    };
+
+    // caps with the same denom are summed by default
+    assert_eq!(
+        dedupe_grant_reqs(vec![
+            stake_req(Some(coin(100, "ujuno"))),
+            stake_req(Some(coin(50, "ujuno")))
+        ]),
+        Ok(vec![stake_req(Some(coin(150, "ujuno")))])
+    );
+
+    assert_eq!(
+        dedupe_grant_reqs_with_settings(
+            vec![
+                stake_req(Some(coin(100, "ujuno"))),
+                stake_req(Some(coin(50, "ujuno")))
+            ],
+            DedupeSettings {
+                stake_max_tokens: MaxTokensMerge::Max,
+                ..DedupeSettings::default()
+            }
+        ),
+        Ok(vec![stake_req(Some(coin(100, "ujuno")))])
+    );
+
+    // the cap is only removed if one of the grants is already uncapped
+    assert_eq!(
+        dedupe_grant_reqs(vec![stake_req(Some(coin(100, "ujuno"))), stake_req(None)]),
+        Ok(vec![stake_req(None)])
+    );
+
+    assert_eq!(
+        dedupe_grant_reqs(vec![
+            stake_req(Some(coin(100, "ujuno"))),
+            stake_req(Some(coin(50, "uatom")))
+        ]),
+        Err(GrantSpecError::MismatchedStakeDenoms {
+            a: "ujuno".to_string(),
+            b: "uatom".to_string(),
+        })
+    );
+}
+
 #[test]
 pub fn dedupe_contract_auth_grants() {
     let user1 = Addr::unchecked("user1");
@@ -164,7 +302,7 @@ pub fn dedupe_contract_auth_grants() {
     let grantee1 = Addr::unchecked("grantee1");
     // let grantee2 = Addr::unchecked("grantee2");
     let contract1 = Addr::unchecked("contract1");
-    // let contract2 = Addr::unchecked("contract2");
+    let contract2 = Addr::unchecked("contract2");
 
     // Test concatenation of contract execution authorizations
     assert_eq!(
@@ -183,7 +321,7 @@ pub fn dedupe_contract_auth_grants() {
                 ]),
                 granter: user1.clone(),
                 grantee: grantee1.clone(),
-                expiration: Timestamp::from_seconds(0)
+                expiration: Timestamp::from_seconds(0).into()
             },
             GrantRequirement::GrantSpec {
                 grant_type: AuthorizationType::ContractExecutionAuthorization(vec![
@@ -199,9 +337,10 @@ pub fn dedupe_contract_auth_grants() {
                 ]),
                 granter: user1.clone(),
                 grantee: grantee1.clone(),
-                expiration: Timestamp::from_seconds(0)
+                expiration: Timestamp::from_seconds(0).into()
             },
-        ]),
+        ])
+        .unwrap(),
         vec![GrantRequirement::GrantSpec {
             grant_type: AuthorizationType::ContractExecutionAuthorization(vec![
                 ContractExecutionSetting {
@@ -210,22 +349,93 @@ pub fn dedupe_contract_auth_grants() {
                         remaining: u64::MAX
                     },
                     filter: ContractExecutionAuthorizationFilter::AcceptedMessageKeysFilter {
-                        keys: vec!["key2".to_string()]
+                        keys: vec!["key1".to_string(), "key2".to_string()]
                     }
                 },
+            ]),
+            granter: user1.clone(),
+            grantee: grantee1.clone(),
+            expiration: Timestamp::from_seconds(0).into()
+        },]
+    );
+
+    // Test merging of the limits and filters for the same contract
+    assert_eq!(
+        dedupe_grant_reqs(vec![
+            GrantRequirement::GrantSpec {
+                grant_type: AuthorizationType::ContractExecutionAuthorization(vec![
+                    ContractExecutionSetting {
+                        contract_addr: contract1.clone(),
+                        limit: ContractExecutionAuthorizationLimit::CombinedLimit {
+                            calls_remaining: 2,
+                            amounts: coins(10, "ujuno")
+                        },
+                        filter: ContractExecutionAuthorizationFilter::AcceptedMessagesFilter {
+                            messages: vec![Binary::from(br#"{"claim":{}}"#)]
+                        }
+                    },
+                    ContractExecutionSetting {
+                        contract_addr: contract2.clone(),
+                        limit: ContractExecutionAuthorizationLimit::MaxCallsLimit { remaining: 1 },
+                        filter: ContractExecutionAuthorizationFilter::AcceptedMessageKeysFilter {
+                            keys: vec!["key1".to_string()]
+                        }
+                    }
+                ]),
+                granter: user1.clone(),
+                grantee: grantee1.clone(),
+                expiration: Timestamp::from_seconds(0).into()
+            },
+            GrantRequirement::GrantSpec {
+                grant_type: AuthorizationType::ContractExecutionAuthorization(vec![
+                    ContractExecutionSetting {
+                        contract_addr: contract1.clone(),
+                        limit: ContractExecutionAuthorizationLimit::CombinedLimit {
+                            calls_remaining: 3,
+                            amounts: vec![coin(5, "ujuno"), coin(1, "uatom")]
+                        },
+                        filter: ContractExecutionAuthorizationFilter::AcceptedMessageKeysFilter {
+                            keys: vec!["stake".to_string()]
+                        }
+                    },
+                    ContractExecutionSetting {
+                        contract_addr: contract2.clone(),
+                        limit: ContractExecutionAuthorizationLimit::MaxFundsLimit {
+                            amounts: coins(7, "ujuno")
+                        },
+                        filter: ContractExecutionAuthorizationFilter::AllowAllMessagesFilter
+                    }
+                ]),
+                granter: user1.clone(),
+                grantee: grantee1.clone(),
+                expiration: Timestamp::from_seconds(10).into()
+            },
+        ])
+        .unwrap(),
+        vec![GrantRequirement::GrantSpec {
+            grant_type: AuthorizationType::ContractExecutionAuthorization(vec![
                 ContractExecutionSetting {
                     contract_addr: contract1.clone(),
-                    limit: ContractExecutionAuthorizationLimit::MaxCallsLimit {
-                        remaining: u64::MAX
+                    limit: ContractExecutionAuthorizationLimit::CombinedLimit {
+                        calls_remaining: 5,
+                        amounts: vec![coin(1, "uatom"), coin(15, "ujuno")]
                     },
                     filter: ContractExecutionAuthorizationFilter::AcceptedMessageKeysFilter {
-                        keys: vec!["key1".to_string()]
+                        keys: vec!["stake".to_string(), "claim".to_string()]
                     }
                 },
+                // a max funds limit doesn't limit the calls so the merged limit can't either
+                ContractExecutionSetting {
+                    contract_addr: contract2.clone(),
+                    limit: ContractExecutionAuthorizationLimit::MaxFundsLimit {
+                        amounts: coins(7, "ujuno")
+                    },
+                    filter: ContractExecutionAuthorizationFilter::AllowAllMessagesFilter
+                },
             ]),
             granter: user1.clone(),
             grantee: grantee1.clone(),
-            expiration: Timestamp::from_seconds(0)
+            expiration: Timestamp::from_seconds(10).into()
         },]
     );
 }
@@ -250,7 +460,7 @@ pub fn dedupe_send_auth_grants() {
                 },
                 granter: granter1.clone(),
                 grantee: grantee1.clone(),
-                expiration: Timestamp::from_seconds(0)
+                expiration: Timestamp::from_seconds(0).into()
             },
             GrantRequirement::GrantSpec {
                 grant_type: AuthorizationType::SendAuthorization {
@@ -259,9 +469,10 @@ pub fn dedupe_send_auth_grants() {
                 },
                 granter: granter1.clone(),
                 grantee: grantee1.clone(),
-                expiration: Timestamp::from_seconds(0)
+                expiration: Timestamp::from_seconds(0).into()
             },
-        ]),
+        ])
+        .unwrap(),
         vec![GrantRequirement::GrantSpec {
             grant_type: AuthorizationType::SendAuthorization {
                 spend_limit: Some(vec![coin(200, "aeth"), coin(300, "ubtc"),]),
@@ -269,8 +480,1556 @@ pub fn dedupe_send_auth_grants() {
             },
             granter: granter1.clone(),
             grantee: grantee1.clone(),
-            expiration: Timestamp::from_seconds(0)
+            expiration: Timestamp::from_seconds(0).into()
         },],
         "send auths should be concatenated"
     );
 }
+
+#[test]
+pub fn dedupe_transfer_auth_grants() {
+    let base = GrantBase {
+        granter: Addr::unchecked("granter1"),
+        grantee: Addr::unchecked("grantee1"),
+        expiration: Timestamp::from_seconds(0).into(),
+    };
+
+    assert_eq!(
+        dedupe_grant_reqs(vec![
+            GrantRequirement::transfer_auth(
+                base.clone(),
+                vec![
+                    TransferAuthorizationSetting::transfer_channel("channel-0", coins(10, "ujuno"))
+                        .with_allow_list(vec!["receiver1"]),
+                    TransferAuthorizationSetting::transfer_channel("channel-1", coins(5, "ujuno")),
+                ]
+            ),
+            GrantRequirement::transfer_auth(
+                base.clone(),
+                vec![
+                    TransferAuthorizationSetting::transfer_channel(
+                        "channel-0",
+                        vec![coin(1, "uatom"), coin(10, "ujuno")]
+                    )
+                    .with_allow_list(vec!["receiver2", "receiver1"]),
+                    TransferAuthorizationSetting::transfer_channel("channel-1", coins(5, "ujuno"))
+                        .with_allow_list(vec!["receiver1"]),
+                ]
+            ),
+        ])
+        .unwrap(),
+        vec![GrantRequirement::transfer_auth(
+            base,
+            vec![
+                TransferAuthorizationSetting::transfer_channel(
+                    "channel-0",
+                    vec![coin(1, "uatom"), coin(20, "ujuno")]
+                )
+                .with_allow_list(vec!["receiver1", "receiver2"]),
+                // one of the settings allows any receiver so the merged one does too
+                TransferAuthorizationSetting::transfer_channel("channel-1", coins(10, "ujuno")),
+            ]
+        )]
+    );
+}
+
+#[test]
+pub fn dedupe_fee_allowances() {
+    let base = GrantBase {
+        granter: Addr::unchecked("granter1"),
+        grantee: Addr::unchecked("grantee1"),
+        expiration: Timestamp::from_seconds(0).into(),
+    };
+    let exec_only = vec!["/cosmos.authz.v1beta1.MsgExec"];
+
+    assert_eq!(
+        dedupe_grant_reqs(vec![
+            GrantRequirement::fee_allowance(
+                base.clone(),
+                AllowanceType::PeriodicAllowance {
+                    spend_limit: Some(coins(100, "ujuno")),
+                    period: 86_400,
+                    period_spend_limit: coins(10, "ujuno"),
+                }
+                .only_messages(exec_only.clone())
+            ),
+            GrantRequirement::fee_allowance(
+                GrantBase {
+                    expiration: Timestamp::from_seconds(10).into(),
+                    ..base.clone()
+                },
+                AllowanceType::PeriodicAllowance {
+                    spend_limit: Some(coins(50, "ujuno")),
+                    period: 3_600,
+                    period_spend_limit: coins(1, "ujuno"),
+                }
+                .only_messages(vec!["/cosmwasm.wasm.v1.MsgExecuteContract"])
+            ),
+        ])
+        .unwrap(),
+        vec![GrantRequirement::fee_allowance(
+            GrantBase {
+                expiration: Timestamp::from_seconds(10).into(),
+                ..base.clone()
+            },
+            AllowanceType::PeriodicAllowance {
+                spend_limit: Some(coins(150, "ujuno")),
+                period: 3_600,
+                period_spend_limit: coins(11, "ujuno"),
+            }
+            .only_messages(vec![
+                "/cosmos.authz.v1beta1.MsgExec",
+                "/cosmwasm.wasm.v1.MsgExecuteContract"
+            ])
+        )]
+    );
+
+    // an unrestricted allowance drops the message restriction of the other one
+    assert_eq!(
+        dedupe_grant_reqs(vec![
+            GrantRequirement::fee_allowance(
+                base.clone(),
+                AllowanceType::basic(Some(coins(100, "ujuno"))).only_messages(exec_only)
+            ),
+            GrantRequirement::fee_allowance(base.clone(), AllowanceType::basic(None)),
+        ])
+        .unwrap(),
+        vec![GrantRequirement::fee_allowance(
+            base.clone(),
+            AllowanceType::basic(None)
+        )]
+    );
+
+    let fee_allowance = GrantRequirement::fee_allowance(
+        base.clone(),
+        AllowanceType::basic(Some(coins(100, "ujuno"))),
+    );
+    let any = fee_allowance.to_any().unwrap();
+    assert_eq!(any.type_url, MSG_GRANT_ALLOWANCE_TYPE_URL);
+    let grant_allowance = MsgGrantAllowance::decode(any.value.as_slice()).unwrap();
+    let allowance = grant_allowance.allowance.unwrap();
+    assert_eq!(allowance.type_url, BASIC_ALLOWANCE_TYPE_URL);
+    assert_eq!(
+        BasicAllowance::decode(allowance.value.as_slice())
+            .unwrap()
+            .expiration,
+        Some(proto_timestamp(&Timestamp::from_seconds(0)))
+    );
+
+    let revoke = RevokeRequirement::from(fee_allowance);
+    assert_eq!(
+        revoke,
+        RevokeRequirement::RevokeFeeAllowance {
+            granter: base.granter,
+            grantee: base.grantee,
+        }
+    );
+    assert_eq!(revoke.to_any().type_url, MSG_REVOKE_ALLOWANCE_TYPE_URL);
+}
+
+#[test]
+pub fn encode_grant_reqs() {
+    let granter = Addr::unchecked("granter1");
+    let grantee = Addr::unchecked("grantee1");
+    let contract = Addr::unchecked("contract1");
+    let base = GrantBase {
+        granter: granter.clone(),
+        grantee: grantee.clone(),
+        expiration: Timestamp::from_seconds(1_700_000_000).into(),
+    };
+
+    let msgs = grant_reqs_to_msgs(vec![
+        GrantRequirement::ContractExec {
+            contract_addr: contract.clone(),
+            msg: to_binary(&"grant").unwrap(),
+            sender: granter.clone(),
+        },
+        GrantRequirement::generic_auth(
+            base.clone(),
+            "/cosmos.distribution.v1beta1.MsgWithdrawDelegatorReward",
+        ),
+        GrantRequirement::default_contract_exec_auth(base, contract.clone(), vec!["execute"], None),
+    ])
+    .unwrap();
+
+    assert_eq!(msgs.len(), 3, "every requirement should be encoded");
+
+    let decode_stargate = |msg: &CosmosMsg| match msg {
+        CosmosMsg::Stargate { type_url, value } => (type_url.clone(), value.clone()),
+        _ => panic!("expected a stargate message"),
+    };
+
+    // the grants keep the order they were given in while the contract execution comes last
+    let (type_url, value) = decode_stargate(&msgs[0]);
+    assert_eq!(type_url, MSG_GRANT_TYPE_URL);
+    let authorization = MsgGrant::decode(value.as_slice())
+        .unwrap()
+        .grant
+        .unwrap()
+        .authorization
+        .unwrap();
+    assert_eq!(authorization.type_url, GENERIC_AUTHORIZATION_TYPE_URL);
+    assert_eq!(
+        GenericAuthorization::decode(authorization.value.as_slice())
+            .unwrap()
+            .msg,
+        "/cosmos.distribution.v1beta1.MsgWithdrawDelegatorReward"
+    );
+
+    let (type_url, value) = decode_stargate(&msgs[1]);
+    assert_eq!(type_url, MSG_GRANT_TYPE_URL);
+    let contract_grant = MsgGrant::decode(value.as_slice()).unwrap();
+    assert_eq!(contract_grant.granter, granter.to_string());
+    assert_eq!(contract_grant.grantee, grantee.to_string());
+    let grant = contract_grant.grant.unwrap();
+    assert_eq!(grant.expiration.unwrap().seconds, 1_700_000_000);
+    let authorization = grant.authorization.unwrap();
+    assert_eq!(
+        authorization.type_url,
+        CONTRACT_EXECUTION_AUTHORIZATION_TYPE_URL
+    );
+    let contract_auth =
+        ContractExecutionAuthorization::decode(authorization.value.as_slice()).unwrap();
+    assert_eq!(contract_auth.grants.len(), 1);
+    assert_eq!(contract_auth.grants[0].contract, contract.to_string());
+
+    // the contract execution is encoded as a MsgExecuteContract sent by the granter
+    let (type_url, value) = decode_stargate(&msgs[2]);
+    assert_eq!(type_url, MSG_EXECUTE_CONTRACT_TYPE_URL);
+    let execute = MsgExecuteContract::decode(value.as_slice()).unwrap();
+    assert_eq!(execute.sender, granter.to_string());
+    assert_eq!(execute.contract, contract.to_string());
+    assert_eq!(Binary::from(execute.msg), to_binary(&"grant").unwrap());
+
+    // an allow list can't be encoded without a spend limit since the chain requires a positive one
+    assert_eq!(
+        grant_reqs_to_msgs(vec![GrantRequirement::GrantSpec {
+            grant_type: AuthorizationType::SendAuthorization {
+                spend_limit: None,
+                allow_list: Some(vec![Addr::unchecked("receiver1")]),
+            },
+            granter,
+            grantee,
+            expiration: Timestamp::from_seconds(1_700_000_000).into(),
+        }]),
+        Err(GrantSpecError::AllowListWithoutSpendLimit)
+    );
+}
+
+#[test]
+pub fn encode_revoke_reqs() {
+    let granter = Addr::unchecked("granter1");
+    let grantee = Addr::unchecked("grantee1");
+    let contract = Addr::unchecked("contract1");
+
+    let undelegate_revoke: RevokeRequirement = GrantRequirement::GrantSpec {
+        grant_type: AuthorizationType::StakeAuthorization {
+            max_tokens: None,
+            authorization_type: StakeAuthorizationType::Undelegate,
+            validators: None,
+        },
+        granter: granter.clone(),
+        grantee: grantee.clone(),
+        expiration: Timestamp::from_seconds(0).into(),
+    }
+    .into();
+
+    let anys = revoke_reqs_to_anys(vec![
+        undelegate_revoke.clone(),
+        RevokeRequirement::ContractExec {
+            contract_addr: contract.clone(),
+            msg: to_binary(&"revoke").unwrap(),
+            sender: granter.clone(),
+        },
+        undelegate_revoke,
+    ]);
+
+    assert_eq!(anys.len(), 2, "duplicate revokes should be dropped");
+
+    assert_eq!(anys[0].type_url, MSG_REVOKE_TYPE_URL);
+    assert_eq!(
+        MsgRevoke::decode(anys[0].value.as_slice()).unwrap(),
+        MsgRevoke {
+            granter: granter.to_string(),
+            grantee: grantee.to_string(),
+            msg_type_url: "/cosmos.staking.v1beta1.MsgUndelegate".to_string(),
+        }
+    );
+
+    assert_eq!(anys[1].type_url, MSG_EXECUTE_CONTRACT_TYPE_URL);
+    let execute = MsgExecuteContract::decode(anys[1].value.as_slice()).unwrap();
+    assert_eq!(execute.sender, granter.to_string());
+    assert_eq!(execute.contract, contract.to_string());
+}
+
+#[test]
+pub fn decode_existing_grants() {
+    let granter = Addr::unchecked("granter1");
+    let grantee = Addr::unchecked("grantee1");
+
+    let grant_types = vec![
+        AuthorizationType::GenericAuthorization {
+            msg: "/cosmos.distribution.v1beta1.MsgSetWithdrawAddress".to_string(),
+        },
+        AuthorizationType::SendAuthorization {
+            spend_limit: Some(coins(100, "ujuno")),
+            allow_list: Some(vec![Addr::unchecked("receiver1")]),
+        },
+        AuthorizationType::StakeAuthorization {
+            max_tokens: Some(coin(1_000, "ujuno")),
+            authorization_type: StakeAuthorizationType::Redelegate,
+            validators: Some(StakeAuthorizationPolicy::DenyList(
+                StakeAuthorizationValidators {
+                    address: vec!["validator1".to_string()],
+                },
+            )),
+        },
+        AuthorizationType::ContractExecutionAuthorization(vec![
+            ContractExecutionSetting {
+                contract_addr: Addr::unchecked("contract1"),
+                limit: ContractExecutionAuthorizationLimit::CombinedLimit {
+                    calls_remaining: 10,
+                    amounts: coins(5, "ujuno"),
+                },
+                filter: ContractExecutionAuthorizationFilter::AcceptedMessagesFilter {
+                    messages: vec![to_binary(&"execute").unwrap()],
+                },
+            },
+            ContractExecutionSetting {
+                contract_addr: Addr::unchecked("contract2"),
+                limit: ContractExecutionAuthorizationLimit::MaxFundsLimit {
+                    amounts: coins(5, "ujuno"),
+                },
+                filter: ContractExecutionAuthorizationFilter::AllowAllMessagesFilter,
+            },
+        ]),
+        AuthorizationType::TransferAuthorization(vec![TransferAuthorizationSetting {
+            source_port: "transfer".to_string(),
+            source_channel: "channel-0".to_string(),
+            spend_limit: coins(100, "ujuno"),
+            allow_list: vec![],
+        }]),
+    ];
+
+    let response = QueryGranterGrantsResponse {
+        grants: grant_types
+            .iter()
+            .map(|grant_type| GrantAuthorization {
+                granter: granter.to_string(),
+                grantee: grantee.to_string(),
+                authorization: Some(grant_type.to_any().unwrap()),
+                expiration: Some(proto_timestamp(&Timestamp::from_seconds(1_700_000_000))),
+            })
+            .collect(),
+        pagination: None,
+    };
+
+    assert_eq!(
+        decode_granter_grants_response(&response),
+        Ok(grant_types
+            .into_iter()
+            .map(|grant_type| ExistingGrant {
+                grant_type,
+                granter: granter.clone(),
+                grantee: grantee.clone(),
+                expiration: Some(Timestamp::from_seconds(1_700_000_000)),
+            })
+            .collect::<Vec<_>>()),
+        "every authorization type should survive an encode/decode round trip"
+    );
+}
+
+#[test]
+pub fn missing_grants_against_existing() {
+    let granter = Addr::unchecked("granter1");
+    let grantee = Addr::unchecked("grantee1");
+    let contract = Addr::unchecked("contract1");
+    let base = GrantBase {
+        granter: granter.clone(),
+        grantee: grantee.clone(),
+        expiration: Timestamp::from_seconds(1_000).into(),
+    };
+
+    let existing_grants = vec![
+        // a generic authorization covers any typed authorization for the same message
+        ExistingGrant {
+            grant_type: AuthorizationType::GenericAuthorization {
+                msg: "/cosmos.bank.v1beta1.MsgSend".to_string(),
+            },
+            granter: granter.clone(),
+            grantee: grantee.clone(),
+            expiration: None,
+        },
+        ExistingGrant {
+            grant_type: AuthorizationType::ContractExecutionAuthorization(vec![
+                ContractExecutionSetting {
+                    contract_addr: contract.clone(),
+                    limit: ContractExecutionAuthorizationLimit::default(),
+                    filter: ContractExecutionAuthorizationFilter::AllowAllMessagesFilter,
+                },
+            ]),
+            granter: granter.clone(),
+            grantee: grantee.clone(),
+            expiration: Some(Timestamp::from_seconds(2_000)),
+        },
+        // expires before the requirement does so it doesn't cover it
+        ExistingGrant {
+            grant_type: AuthorizationType::StakeAuthorization {
+                max_tokens: None,
+                authorization_type: StakeAuthorizationType::Delegate,
+                validators: None,
+            },
+            granter: granter.clone(),
+            grantee: grantee.clone(),
+            expiration: Some(Timestamp::from_seconds(500)),
+        },
+    ];
+
+    let send_req = GrantRequirement::GrantSpec {
+        grant_type: AuthorizationType::SendAuthorization {
+            spend_limit: Some(coins(100, "ujuno")),
+            allow_list: None,
+        },
+        granter: granter.clone(),
+        grantee: grantee.clone(),
+        expiration: Timestamp::from_seconds(1_000).into(),
+    };
+    let contract_req =
+        GrantRequirement::default_contract_exec_auth(base.clone(), contract, vec!["execute"], None);
+    let stake_req = GrantRequirement::GrantSpec {
+        grant_type: AuthorizationType::StakeAuthorization {
+            max_tokens: Some(coin(10, "ujuno")),
+            authorization_type: StakeAuthorizationType::Delegate,
+            validators: None,
+        },
+        granter: granter.clone(),
+        grantee: grantee.clone(),
+        expiration: Timestamp::from_seconds(1_000).into(),
+    };
+    let withdraw_req = GrantRequirement::generic_auth(
+        base,
+        "/cosmos.distribution.v1beta1.MsgWithdrawDelegatorReward",
+    );
+
+    assert_eq!(
+        missing_grant_reqs(
+            vec![
+                send_req,
+                contract_req,
+                stake_req.clone(),
+                withdraw_req.clone()
+            ],
+            &existing_grants
+        )
+        .unwrap(),
+        vec![stake_req, withdraw_req]
+    );
+
+    // a smaller limit doesn't cover a larger one
+    assert!(!AuthorizationType::SendAuthorization {
+        spend_limit: Some(coins(50, "ujuno")),
+        allow_list: None,
+    }
+    .covers(&AuthorizationType::SendAuthorization {
+        spend_limit: Some(coins(100, "ujuno")),
+        allow_list: None,
+    }));
+
+    // message keys cover the raw messages that use them
+    assert!(
+        ContractExecutionAuthorizationFilter::AcceptedMessageKeysFilter {
+            keys: vec!["execute".to_string()],
+        }
+        .covers(
+            &ContractExecutionAuthorizationFilter::AcceptedMessagesFilter {
+                messages: vec![Binary::from(br#"{"execute":{}}"#)],
+            }
+        )
+    );
+
+    // the chain rejects a message with more than one top level key even when all of them are allowed
+    assert!(
+        !ContractExecutionAuthorizationFilter::AcceptedMessageKeysFilter {
+            keys: vec!["execute".to_string(), "revoke".to_string()],
+        }
+        .covers(
+            &ContractExecutionAuthorizationFilter::AcceptedMessagesFilter {
+                messages: vec![Binary::from(br#"{"execute":{},"revoke":{}}"#)],
+            }
+        )
+    );
+}
+
+#[test]
+pub fn validate_grant_reqs() {
+    let now = Timestamp::from_seconds(100);
+    let base = GrantBase {
+        granter: Addr::unchecked("granter1"),
+        grantee: Addr::unchecked("grantee1"),
+        expiration: Timestamp::from_seconds(200).into(),
+    };
+    let contract = Addr::unchecked("contract1");
+
+    assert_eq!(
+        GrantRequirement::default_contract_exec_auth(
+            base.clone(),
+            contract.clone(),
+            vec!["execute"],
+            Some("ujuno")
+        )
+        .validate(now),
+        Ok(())
+    );
+
+    assert_eq!(
+        GrantRequirement::generic_auth(
+            GrantBase {
+                expiration: now.into(),
+                ..base.clone()
+            },
+            "/cosmos.bank.v1beta1.MsgSend"
+        )
+        .validate(now),
+        Err(GrantSpecError::ExpirationInPast {
+            expiration: now,
+            now
+        })
+    );
+
+    assert_eq!(
+        GrantRequirement::generic_auth(
+            GrantBase {
+                grantee: base.granter.clone(),
+                ..base.clone()
+            },
+            "/cosmos.bank.v1beta1.MsgSend"
+        )
+        .validate(now),
+        Err(GrantSpecError::GranterIsGrantee("granter1".to_string()))
+    );
+
+    assert_eq!(
+        AuthorizationType::StakeAuthorization {
+            max_tokens: None,
+            authorization_type: StakeAuthorizationType::Delegate,
+            validators: Some(StakeAuthorizationPolicy::AllowList(
+                StakeAuthorizationValidators { address: vec![] }
+            )),
+        }
+        .validate(),
+        Err(GrantSpecError::EmptyStakeValidators)
+    );
+
+    assert_eq!(
+        AuthorizationType::SendAuthorization {
+            spend_limit: None,
+            allow_list: Some(vec![Addr::unchecked("receiver1")]),
+        }
+        .validate(),
+        Err(GrantSpecError::AllowListWithoutSpendLimit)
+    );
+
+    assert_eq!(
+        AuthorizationType::ContractExecutionAuthorization(vec![ContractExecutionSetting {
+            contract_addr: contract.clone(),
+            limit: ContractExecutionAuthorizationLimit::MaxFundsLimit {
+                amounts: coins(0, "ujuno")
+            },
+            filter: ContractExecutionAuthorizationFilter::AllowAllMessagesFilter,
+        }])
+        .validate(),
+        Err(GrantSpecError::ZeroFundsLimit(contract.to_string()))
+    );
+}
+
+#[cw_serde]
+pub struct TestGrantSettings {
+    pub msg_type_url: String,
+}
+
+#[grant_spec_queries(TestGrantSettings)]
+#[cw_serde]
+#[derive(QueryResponses)]
+pub enum TestQueryMsg {
+    #[returns(String)]
+    Version {},
+}
+
+impl Grantable for TestQueryMsg {
+    type GrantSettings = TestGrantSettings;
+
+    fn query_grants(
+        grant: GrantStructure<TestGrantSettings>,
+        _current_timestamp: Timestamp,
+    ) -> StdResult<Vec<GrantRequirement>> {
+        Ok(vec![GrantRequirement::generic_auth(
+            GrantBase {
+                granter: grant.granter,
+                grantee: grant.grant_contract,
+                expiration: grant.expiration,
+            },
+            &grant.grant_data.msg_type_url,
+        )])
+    }
+}
+
+// only the generated queries, with QueryResponses derived by its full path
+#[grant_spec_queries(TestGrantSettings)]
+#[cw_serde]
+#[derive(cosmwasm_schema::QueryResponses)]
+pub enum GrantSpecOnlyQueryMsg {}
+
+impl Grantable for GrantSpecOnlyQueryMsg {
+    type GrantSettings = TestGrantSettings;
+
+    fn query_grants(
+        grant: GrantStructure<TestGrantSettings>,
+        current_timestamp: Timestamp,
+    ) -> StdResult<Vec<GrantRequirement>> {
+        Ok(vec![
+            TestQueryMsg::query_grants(grant.clone(), current_timestamp)?.remove(0),
+            GrantRequirement::ContractExec {
+                contract_addr: grant.grant_contract,
+                msg: to_binary(&"grant")?,
+                sender: grant.granter,
+            },
+        ])
+    }
+
+    fn revoke_msg(_grant: &GrantStructure<TestGrantSettings>) -> StdResult<Option<Binary>> {
+        to_binary(&"revoke").map(Some)
+    }
+}
+
+#[test]
+pub fn grant_spec_queries_macro() {
+    let env = mock_env();
+    let granter = Addr::unchecked("granter1");
+    let grant_data = TestGrantSettings {
+        msg_type_url: "/cosmos.bank.v1beta1.MsgSend".to_string(),
+    };
+
+    let grants: Vec<GrantRequirement> = from_binary(
+        &TestQueryMsg::GrantSpec {
+            granter: granter.clone(),
+            grantee: Addr::unchecked("grantee1"),
+            expiration: Timestamp::from_seconds(1_000).into(),
+            grant_data: grant_data.clone(),
+        }
+        .grant_spec_query(&env)
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        grants,
+        vec![GrantRequirement::generic_auth(
+            GrantBase {
+                granter: granter.clone(),
+                grantee: env.contract.address.clone(),
+                expiration: Timestamp::from_seconds(1_000).into(),
+            },
+            "/cosmos.bank.v1beta1.MsgSend"
+        )]
+    );
+
+    let revokes: Vec<RevokeRequirement> = from_binary(
+        &TestQueryMsg::RevokeSpec {
+            granter: granter.clone(),
+            grantee: Addr::unchecked("grantee1"),
+            grant_data: grant_data.clone(),
+        }
+        .grant_spec_query(&env)
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        revokes,
+        vec![RevokeRequirement::RevokeSpec {
+            grant_type: "/cosmos.bank.v1beta1.MsgSend".to_string(),
+            granter: granter.clone(),
+            grantee: env.contract.address.clone(),
+        }]
+    );
+
+    let descriptions: Vec<GrantDescription> = from_binary(
+        &TestQueryMsg::DescribeGrantSpec {
+            granter: granter.clone(),
+            grantee: Addr::unchecked("grantee1"),
+            expiration: Timestamp::from_seconds(1_000).into(),
+            grant_data,
+        }
+        .grant_spec_query(&env)
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        descriptions_to_text(&descriptions),
+        "Allows cosmos2contract to send any of your tokens until 1970-01-01"
+    );
+
+    assert!(TestQueryMsg::Version {}
+        .grant_spec_query(&mock_env())
+        .is_err());
+
+    // the contract executions are revoked with the revoke message
+    let revokes: Vec<RevokeRequirement> = from_binary(
+        &GrantSpecOnlyQueryMsg::RevokeSpec {
+            granter: granter.clone(),
+            grantee: Addr::unchecked("grantee1"),
+            grant_data: TestGrantSettings {
+                msg_type_url: "/cosmos.bank.v1beta1.MsgSend".to_string(),
+            },
+        }
+        .grant_spec_query(&env)
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        revokes,
+        vec![
+            RevokeRequirement::RevokeSpec {
+                grant_type: "/cosmos.bank.v1beta1.MsgSend".to_string(),
+                granter: granter.clone(),
+                grantee: env.contract.address.clone(),
+            },
+            RevokeRequirement::ContractExec {
+                contract_addr: env.contract.address.clone(),
+                msg: to_binary(&"revoke").unwrap(),
+                sender: granter,
+            }
+        ]
+    );
+}
+
+#[test]
+pub fn describe_grant_reqs_text_and_params() {
+    let base = GrantBase {
+        granter: Addr::unchecked("granter1"),
+        grantee: Addr::unchecked("grantee1"),
+        expiration: Timestamp::from_seconds(1_700_000_000).into(),
+    };
+
+    let descriptions = describe_grant_reqs(vec![
+        GrantRequirement::generic_auth(
+            base.clone(),
+            "/cosmos.distribution.v1beta1.MsgWithdrawDelegatorReward",
+        ),
+        GrantRequirement::GrantSpec {
+            grant_type: AuthorizationType::SendAuthorization {
+                spend_limit: Some(vec![coin(50_000_000, "ujuno"), coin(1_500_000, "uatom")]),
+                allow_list: Some(vec![Addr::unchecked("addr1"), Addr::unchecked("addr2")]),
+            },
+            granter: base.granter.clone(),
+            grantee: base.grantee.clone(),
+            expiration: base.expiration,
+        },
+        GrantRequirement::fee_allowance(
+            base.clone(),
+            AllowanceType::PeriodicAllowance {
+                spend_limit: None,
+                period: 86_400,
+                period_spend_limit: coins(250_000, "ujuno"),
+            },
+        ),
+        GrantRequirement::ContractExec {
+            contract_addr: Addr::unchecked("contract1"),
+            msg: Binary::from(br#"{"register":{}}"#.to_vec()),
+            sender: base.granter.clone(),
+        },
+    ])
+    .unwrap();
+
+    assert_eq!(
+        descriptions_to_text(&descriptions),
+        [
+            "Allows grantee1 to withdraw your staking rewards until 2023-11-14",
+            "Allows grantee1 to send up to 50000000 ujuno, 1500000 uatom to 2 addresses until 2023-11-14",
+            "Allows grantee1 to pay fees from your account, at most 250000 ujuno every 1 day until 2023-11-14",
+            "Executes register on contract contract1",
+        ]
+        .join("\n")
+    );
+
+    assert_eq!(descriptions[1].key, "authz.send");
+    assert_eq!(
+        descriptions[1].params,
+        vec![
+            DescriptionParam {
+                name: "grantee".to_string(),
+                value: DescriptionValue::Address("grantee1".to_string()),
+            },
+            DescriptionParam {
+                name: "spend_limit".to_string(),
+                value: DescriptionValue::Coins(vec![
+                    coin(50_000_000, "ujuno"),
+                    coin(1_500_000, "uatom")
+                ]),
+            },
+            DescriptionParam {
+                name: "allow_list".to_string(),
+                value: DescriptionValue::Addresses(vec!["addr1".to_string(), "addr2".to_string()]),
+            },
+            DescriptionParam {
+                name: "expiration".to_string(),
+                value: DescriptionValue::Expiration(GrantExpiration::AtTime(
+                    Timestamp::from_seconds(1_700_000_000),
+                )),
+            },
+        ]
+    );
+
+    // amounts are only converted to a display denom when its metadata is given
+    let metadata = [DenomDisplay {
+        denom: "ujuno".to_string(),
+        display: "JUNO".to_string(),
+        exponent: 6,
+    }];
+    let coins = vec![
+        coin(1_500_000, "ujuno"),
+        coin(2_000_000, "usdc"),
+        coin(
+            5,
+            "ibc/27394FB092D2ECCD56123C74F36E4C1F926001CEADA9CA97EA622B25F41E5EB2",
+        ),
+    ];
+    assert_eq!(
+        format_coins_with_metadata(&coins, &metadata),
+        "1.5 JUNO, 2000000 usdc, 5 ibc/27394FB092D2ECCD56123C74F36E4C1F926001CEADA9CA97EA622B25F41E5EB2"
+    );
+    assert_eq!(format_coins(&coins[..2]), "1500000 ujuno, 2000000 usdc");
+}
+
+#[test]
+pub fn relative_and_optional_expirations() {
+    let now = Timestamp::from_seconds(1_000);
+    let base = GrantBase {
+        granter: Addr::unchecked("granter1"),
+        grantee: Addr::unchecked("grantee1"),
+        expiration: GrantExpiration::SecondsFromNow(500),
+    };
+    let msg_type_url = "/cosmos.bank.v1beta1.MsgSend";
+
+    // relative expirations are resolved against the current time
+    assert_eq!(
+        GrantRequirement::generic_auth(base.clone(), msg_type_url).resolve_expiration(now),
+        GrantRequirement::generic_auth(
+            GrantBase {
+                expiration: Timestamp::from_seconds(1_500).into(),
+                ..base.clone()
+            },
+            msg_type_url
+        )
+    );
+    assert_eq!(GrantExpiration::Never.resolve(now), GrantExpiration::Never);
+
+    // expirations from before they could be relative or left out are plain timestamps
+    assert_eq!(
+        from_binary::<GrantExpiration>(&Binary::from(br#""1700000000000000000""#)).unwrap(),
+        GrantExpiration::AtTime(Timestamp::from_seconds(1_700_000_000))
+    );
+    assert_eq!(
+        from_binary::<GrantBase>(&Binary::from(
+            br#"{"granter":"granter1","grantee":"grantee1","expiration":"1500000000000"}"#
+        ))
+        .unwrap(),
+        GrantBase {
+            expiration: Timestamp::from_nanos(1_500_000_000_000).into(),
+            ..base.clone()
+        }
+    );
+    for expiration in [
+        GrantExpiration::Never,
+        GrantExpiration::AtTime(now),
+        GrantExpiration::SecondsFromNow(500),
+    ] {
+        assert_eq!(
+            from_binary::<GrantExpiration>(&to_binary(&expiration).unwrap()).unwrap(),
+            expiration
+        );
+    }
+    assert!(from_binary::<GrantExpiration>(&Binary::from(br#"{"at_height":5}"#)).is_err());
+
+    // unresolved expirations can't be encoded
+    assert_eq!(
+        GrantRequirement::generic_auth(base.clone(), msg_type_url).to_any(),
+        Err(GrantSpecError::UnresolvedExpiration(500))
+    );
+
+    // grants that never expire are encoded without an expiration
+    let never = GrantBase {
+        expiration: GrantExpiration::Never,
+        ..base.clone()
+    };
+    let any = GrantRequirement::generic_auth(never.clone(), msg_type_url)
+        .to_any()
+        .unwrap();
+    assert_eq!(
+        MsgGrant::decode(any.value.as_slice())
+            .unwrap()
+            .grant
+            .unwrap()
+            .expiration,
+        None
+    );
+
+    // never outlasts any other expiration when deduping
+    assert_eq!(
+        dedupe_grant_reqs(vec![
+            GrantRequirement::generic_auth(
+                GrantBase {
+                    expiration: Timestamp::from_seconds(2_000).into(),
+                    ..base.clone()
+                },
+                msg_type_url
+            ),
+            GrantRequirement::generic_auth(never.clone(), msg_type_url),
+        ])
+        .unwrap(),
+        vec![GrantRequirement::generic_auth(never, msg_type_url)]
+    );
+    assert_eq!(
+        dedupe_grant_reqs(vec![
+            GrantRequirement::generic_auth(
+                GrantBase {
+                    expiration: GrantExpiration::SecondsFromNow(100),
+                    ..base.clone()
+                },
+                msg_type_url
+            ),
+            GrantRequirement::generic_auth(base.clone(), msg_type_url),
+        ])
+        .unwrap(),
+        vec![GrantRequirement::generic_auth(base.clone(), msg_type_url)]
+    );
+    assert_eq!(
+        dedupe_grant_reqs(vec![
+            GrantRequirement::generic_auth(
+                GrantBase {
+                    expiration: Timestamp::from_seconds(2_000).into(),
+                    ..base.clone()
+                },
+                msg_type_url
+            ),
+            GrantRequirement::generic_auth(base, msg_type_url),
+        ]),
+        Err(GrantSpecError::UnresolvedExpiration(500))
+    );
+}
+
+#[test]
+pub fn plan_grant_txs_orders_and_batches() {
+    let base = GrantBase {
+        granter: Addr::unchecked("granter1"),
+        grantee: Addr::unchecked("grantee1"),
+        expiration: Timestamp::from_seconds(1_000).into(),
+    };
+    let existing_grant = |msg: &str| ExistingGrant {
+        grant_type: AuthorizationType::GenericAuthorization {
+            msg: msg.to_string(),
+        },
+        granter: base.granter.clone(),
+        grantee: base.grantee.clone(),
+        expiration: None,
+    };
+    let exec = |msg: &[u8]| GrantRequirement::ContractExec {
+        contract_addr: Addr::unchecked("contract1"),
+        msg: Binary::from(msg),
+        sender: base.granter.clone(),
+    };
+    let withdraw = GrantRequirement::generic_auth(
+        base.clone(),
+        "/cosmos.distribution.v1beta1.MsgWithdrawDelegatorReward",
+    );
+    let set_withdraw_address = GrantRequirement::generic_auth(
+        base.clone(),
+        "/cosmos.distribution.v1beta1.MsgSetWithdrawAddress",
+    );
+    let send = GrantRequirement::generic_auth(base.clone(), "/cosmos.bank.v1beta1.MsgSend");
+    let fee_allowance = GrantRequirement::fee_allowance(base.clone(), AllowanceType::basic(None));
+
+    let plan = plan_grant_txs(
+        vec![
+            exec(br#"{"grant":{}}"#),
+            withdraw.clone(),
+            set_withdraw_address.clone(),
+            send.clone(),
+            fee_allowance.clone(),
+            exec(br#"{"register":{}}"#),
+            // duplicates are merged away before batching
+            withdraw.clone(),
+        ],
+        &[
+            existing_grant("/cosmos.distribution.v1beta1.MsgWithdrawDelegatorReward"),
+            existing_grant("/cosmos.distribution.v1beta1.MsgSetWithdrawAddress"),
+        ],
+        &PlannerSettings {
+            max_msgs_per_tx: 2,
+            ..PlannerSettings::default()
+        },
+    )
+    .unwrap();
+
+    assert_eq!(
+        plan.batches,
+        vec![
+            GrantTxBatch {
+                stage: PlanStage::Grants,
+                requirements: vec![withdraw.clone(), set_withdraw_address.clone()],
+                skippable: true,
+            },
+            GrantTxBatch {
+                stage: PlanStage::Grants,
+                requirements: vec![send.clone(), fee_allowance.clone()],
+                skippable: false,
+            },
+            GrantTxBatch {
+                stage: PlanStage::ContractExecutions,
+                requirements: vec![exec(br#"{"grant":{}}"#), exec(br#"{"register":{}}"#)],
+                skippable: false,
+            },
+        ]
+    );
+    assert_eq!(plan.pending_batches().count(), 2);
+    assert_eq!(plan.batches[1].to_msgs().unwrap().len(), 2);
+
+    // covered requirements are batched ahead of and apart from the uncovered ones regardless of
+    // the order they are given in
+    let plan = plan_grant_txs(
+        vec![
+            send.clone(),
+            withdraw.clone(),
+            fee_allowance.clone(),
+            set_withdraw_address.clone(),
+        ],
+        &[
+            existing_grant("/cosmos.distribution.v1beta1.MsgWithdrawDelegatorReward"),
+            existing_grant("/cosmos.distribution.v1beta1.MsgSetWithdrawAddress"),
+        ],
+        &PlannerSettings {
+            max_msgs_per_tx: 3,
+            ..PlannerSettings::default()
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        plan.batches,
+        vec![
+            GrantTxBatch {
+                stage: PlanStage::Grants,
+                requirements: vec![withdraw.clone(), set_withdraw_address.clone()],
+                skippable: true,
+            },
+            GrantTxBatch {
+                stage: PlanStage::Grants,
+                requirements: vec![send.clone(), fee_allowance.clone()],
+                skippable: false,
+            },
+        ]
+    );
+
+    assert_eq!(
+        plan_grant_txs(
+            vec![],
+            &[],
+            &PlannerSettings {
+                max_msgs_per_tx: 0,
+                ..PlannerSettings::default()
+            }
+        ),
+        Err(GrantSpecError::ZeroMaxMsgsPerTx)
+    );
+}
+
+#[test]
+pub fn dedupe_merge_strategies() {
+    let base = GrantBase {
+        granter: Addr::unchecked("granter1"),
+        grantee: Addr::unchecked("grantee1"),
+        expiration: Timestamp::from_seconds(1_000).into(),
+    };
+    let union = DedupeSettings::default();
+    let intersection = DedupeSettings {
+        strategy: MergeStrategy::Intersection,
+        ..DedupeSettings::default()
+    };
+    let grant = |grant_type: AuthorizationType| GrantRequirement::GrantSpec {
+        grant_type,
+        granter: base.granter.clone(),
+        grantee: base.grantee.clone(),
+        expiration: base.expiration,
+    };
+    let send_auth = |spend_limit: Option<Vec<Coin>>, allow_list: Option<Vec<&str>>| {
+        grant(AuthorizationType::SendAuthorization {
+            spend_limit,
+            allow_list: allow_list
+                .map(|allow_list| allow_list.into_iter().map(Addr::unchecked).collect()),
+        })
+    };
+    let stake_auth = |max_tokens: Option<Coin>, validators: Option<StakeAuthorizationPolicy>| {
+        grant(AuthorizationType::StakeAuthorization {
+            max_tokens,
+            authorization_type: StakeAuthorizationType::Delegate,
+            validators,
+        })
+    };
+    let validators = |address: Vec<&str>| StakeAuthorizationValidators {
+        address: address.into_iter().map(String::from).collect(),
+    };
+
+    // a limit that only one side sets is dropped by the union and kept by the intersection
+    let send_auths = vec![
+        send_auth(Some(vec![coin(100, "ujuno"), coin(10, "uatom")]), None),
+        send_auth(
+            Some(vec![coin(50, "ujuno")]),
+            Some(vec!["receiver1", "receiver2"]),
+        ),
+    ];
+    assert_eq!(
+        dedupe_grant_reqs_with_settings(send_auths.clone(), union.clone()),
+        Ok(vec![send_auth(
+            Some(vec![coin(10, "uatom"), coin(150, "ujuno")]),
+            None
+        )])
+    );
+    assert_eq!(
+        dedupe_grant_reqs_with_settings(send_auths, intersection.clone()),
+        Ok(vec![send_auth(
+            Some(vec![coin(50, "ujuno")]),
+            Some(vec!["receiver1", "receiver2"])
+        )])
+    );
+    assert_eq!(
+        dedupe_grant_reqs_with_settings(
+            vec![
+                send_auth(None, Some(vec!["receiver1"])),
+                send_auth(None, Some(vec!["receiver2"])),
+            ],
+            intersection.clone()
+        ),
+        Err(GrantSpecError::EmptyMergeIntersection(
+            "allowed receivers".to_string()
+        ))
+    );
+
+    // deny lists only keep the validators both sides deny with the union and all of them with the intersection
+    let stake_auths = vec![
+        stake_auth(
+            Some(coin(100, "ujuno")),
+            Some(StakeAuthorizationPolicy::DenyList(validators(vec![
+                "validator1",
+                "validator2",
+            ]))),
+        ),
+        stake_auth(
+            Some(coin(50, "ujuno")),
+            Some(StakeAuthorizationPolicy::DenyList(validators(vec![
+                "validator2",
+                "validator3",
+            ]))),
+        ),
+    ];
+    assert_eq!(
+        dedupe_grant_reqs_with_settings(stake_auths.clone(), union.clone()),
+        Ok(vec![stake_auth(
+            Some(coin(150, "ujuno")),
+            Some(StakeAuthorizationPolicy::DenyList(validators(vec![
+                "validator2"
+            ])))
+        )])
+    );
+    assert_eq!(
+        dedupe_grant_reqs_with_settings(stake_auths, intersection.clone()),
+        Ok(vec![stake_auth(
+            Some(coin(50, "ujuno")),
+            Some(StakeAuthorizationPolicy::DenyList(validators(vec![
+                "validator1",
+                "validator2",
+                "validator3"
+            ])))
+        )])
+    );
+
+    // a policy on only one side is dropped by the union and kept by the intersection
+    let stake_auths = vec![
+        stake_auth(
+            None,
+            Some(StakeAuthorizationPolicy::AllowList(validators(vec![
+                "validator1",
+                "validator2",
+            ]))),
+        ),
+        stake_auth(Some(coin(50, "ujuno")), None),
+    ];
+    assert_eq!(
+        dedupe_grant_reqs_with_settings(stake_auths.clone(), union.clone()),
+        Ok(vec![stake_auth(None, None)])
+    );
+    assert_eq!(
+        dedupe_grant_reqs_with_settings(stake_auths, intersection.clone()),
+        Ok(vec![stake_auth(
+            Some(coin(50, "ujuno")),
+            Some(StakeAuthorizationPolicy::AllowList(validators(vec![
+                "validator1",
+                "validator2"
+            ])))
+        )])
+    );
+
+    // a deny list merged with an allow list only keeps the validators that aren't allowed with the union
+    // and only the allowed validators that aren't denied with the intersection, regardless of order
+    let stake_auths = vec![
+        stake_auth(
+            None,
+            Some(StakeAuthorizationPolicy::DenyList(validators(vec![
+                "validator1",
+                "validator2",
+            ]))),
+        ),
+        stake_auth(
+            None,
+            Some(StakeAuthorizationPolicy::AllowList(validators(vec![
+                "validator2",
+                "validator3",
+            ]))),
+        ),
+    ];
+    assert_eq!(
+        dedupe_grant_reqs_with_settings(stake_auths.clone(), union.clone()),
+        Ok(vec![stake_auth(
+            None,
+            Some(StakeAuthorizationPolicy::DenyList(validators(vec![
+                "validator1"
+            ])))
+        )])
+    );
+    assert_eq!(
+        dedupe_grant_reqs_with_settings(
+            stake_auths.into_iter().rev().collect(),
+            intersection.clone()
+        ),
+        Ok(vec![stake_auth(
+            None,
+            Some(StakeAuthorizationPolicy::AllowList(validators(vec![
+                "validator3"
+            ])))
+        )])
+    );
+
+    // an allow list that only covers denied validators allows anything with the union
+    // and nothing with the intersection
+    let stake_auths = vec![
+        stake_auth(
+            None,
+            Some(StakeAuthorizationPolicy::AllowList(validators(vec![
+                "validator1",
+            ]))),
+        ),
+        stake_auth(
+            None,
+            Some(StakeAuthorizationPolicy::DenyList(validators(vec![
+                "validator1",
+            ]))),
+        ),
+    ];
+    assert_eq!(
+        dedupe_grant_reqs_with_settings(stake_auths.clone(), union.clone()),
+        Ok(vec![stake_auth(None, None)])
+    );
+    assert_eq!(
+        dedupe_grant_reqs_with_settings(stake_auths, intersection.clone()),
+        Err(GrantSpecError::EmptyMergeIntersection(
+            "allowed validators".to_string()
+        ))
+    );
+
+    // contract settings keep the fewest calls and only the raw messages the keys allow
+    let register = Binary::from(br#"{"register":{}}"#.to_vec());
+    let claim = Binary::from(br#"{"claim":{}}"#.to_vec());
+    let contract_auth = |limit: ContractExecutionAuthorizationLimit,
+                         filter: ContractExecutionAuthorizationFilter| {
+        grant(AuthorizationType::ContractExecutionAuthorization(vec![
+            ContractExecutionSetting {
+                contract_addr: Addr::unchecked("contract1"),
+                limit,
+                filter,
+            },
+        ]))
+    };
+    assert_eq!(
+        dedupe_grant_reqs_with_settings(
+            vec![
+                contract_auth(
+                    ContractExecutionAuthorizationLimit::MaxCallsLimit { remaining: 5 },
+                    ContractExecutionAuthorizationFilter::AcceptedMessageKeysFilter {
+                        keys: vec!["register".to_string()],
+                    },
+                ),
+                contract_auth(
+                    ContractExecutionAuthorizationLimit::MaxCallsLimit { remaining: 2 },
+                    ContractExecutionAuthorizationFilter::AcceptedMessagesFilter {
+                        messages: vec![register.clone(), claim],
+                    },
+                ),
+            ],
+            intersection.clone()
+        ),
+        Ok(vec![contract_auth(
+            ContractExecutionAuthorizationLimit::MaxCallsLimit { remaining: 2 },
+            ContractExecutionAuthorizationFilter::AcceptedMessagesFilter {
+                messages: vec![register],
+            },
+        )])
+    );
+
+    // fee allowances keep their message restrictions and the longer period when intersected
+    let allowances = vec![
+        GrantRequirement::fee_allowance(
+            base.clone(),
+            AllowanceType::PeriodicAllowance {
+                spend_limit: None,
+                period: 3_600,
+                period_spend_limit: coins(10, "ujuno"),
+            },
+        ),
+        GrantRequirement::fee_allowance(
+            base.clone(),
+            AllowanceType::PeriodicAllowance {
+                spend_limit: Some(coins(1_000, "ujuno")),
+                period: 86_400,
+                period_spend_limit: coins(20, "ujuno"),
+            }
+            .only_messages(vec!["/cosmos.bank.v1beta1.MsgSend"]),
+        ),
+    ];
+    assert_eq!(
+        dedupe_grant_reqs_with_settings(allowances.clone(), union),
+        Ok(vec![GrantRequirement::fee_allowance(
+            base.clone(),
+            AllowanceType::PeriodicAllowance {
+                spend_limit: None,
+                period: 3_600,
+                period_spend_limit: coins(30, "ujuno"),
+            }
+        )])
+    );
+    assert_eq!(
+        dedupe_grant_reqs_with_settings(allowances, intersection),
+        Ok(vec![GrantRequirement::fee_allowance(
+            base,
+            AllowanceType::PeriodicAllowance {
+                spend_limit: Some(coins(1_000, "ujuno")),
+                period: 86_400,
+                period_spend_limit: coins(10, "ujuno"),
+            }
+            .only_messages(vec!["/cosmos.bank.v1beta1.MsgSend"])
+        )])
+    );
+}
+
+#[cw_serde]
+#[derive(MessageKeys)]
+pub enum TestExecuteMsg {
+    Register {
+        name: String,
+    },
+    ClaimRewards(),
+    #[serde(rename = "withdraw")]
+    WithdrawAll {},
+    // unit variants are serialized as plain strings so they can't be matched by key
+    Ping,
+}
+
+#[test]
+pub fn derive_message_keys() {
+    assert_eq!(
+        TestExecuteMsg::message_keys(),
+        vec![
+            TestExecuteMsgKey::Register,
+            TestExecuteMsgKey::ClaimRewards,
+            TestExecuteMsgKey::WithdrawAll,
+        ]
+    );
+
+    // the keys match the ones the messages are actually serialized with
+    let serialized_keys = [
+        TestExecuteMsg::Register {
+            name: "name".to_string(),
+        },
+        TestExecuteMsg::ClaimRewards(),
+        TestExecuteMsg::WithdrawAll {},
+    ]
+    .iter()
+    .map(|msg| message_keys(&to_binary(msg).unwrap()).unwrap())
+    .collect::<Vec<_>>()
+    .concat();
+    assert_eq!(
+        serialized_keys,
+        vec!["register", "claim_rewards", "withdraw"]
+    );
+
+    assert_eq!(
+        ContractExecutionAuthorizationFilter::all_message_keys::<TestExecuteMsg>(),
+        ContractExecutionAuthorizationFilter::AcceptedMessageKeysFilter {
+            keys: vec![
+                "register".to_string(),
+                "claim_rewards".to_string(),
+                "withdraw".to_string()
+            ],
+        }
+    );
+
+    let base = GrantBase {
+        granter: Addr::unchecked("granter1"),
+        grantee: Addr::unchecked("grantee1"),
+        expiration: GrantExpiration::Never,
+    };
+    assert_eq!(
+        GrantRequirement::default_contract_exec_auth(
+            base.clone(),
+            Addr::unchecked("contract1"),
+            vec![TestExecuteMsgKey::ClaimRewards],
+            None
+        ),
+        GrantRequirement::default_contract_exec_auth(
+            base,
+            Addr::unchecked("contract1"),
+            vec!["claim_rewards"],
+            None
+        )
+    );
+}
+
+// without any messages that can be matched by key
+#[cw_serde]
+#[derive(MessageKeys)]
+pub enum TestUnitExecuteMsg {
+    Ping,
+    Pong,
+}
+
+// plain serde with its own renaming rule and a rename that differs between directions
+#[derive(cosmwasm_schema::serde::Serialize, cosmwasm_schema::serde::Deserialize, MessageKeys)]
+#[serde(crate = "cosmwasm_schema::serde", rename_all = "camelCase")]
+pub enum TestCamelExecuteMsg {
+    ClaimRewards {},
+    #[serde(rename(serialize = "withdraw_all", deserialize = "withdraw"))]
+    WithdrawAll {},
+    #[serde(rename(serialize = "pause_all"))]
+    PauseAll {},
+}
+
+#[test]
+pub fn derive_message_keys_serde_renames() {
+    assert_eq!(TestUnitExecuteMsg::message_keys(), vec![]);
+
+    // the keys are the names the messages are deserialized from
+    assert_eq!(
+        ContractExecutionAuthorizationFilter::all_message_keys::<TestCamelExecuteMsg>(),
+        ContractExecutionAuthorizationFilter::AcceptedMessageKeysFilter {
+            keys: vec![
+                "claimRewards".to_string(),
+                "withdraw".to_string(),
+                "pauseAll".to_string()
+            ],
+        }
+    );
+    assert_eq!(
+        message_keys(&to_binary(&TestCamelExecuteMsg::ClaimRewards {}).unwrap()),
+        Some(vec!["claimRewards".to_string()])
+    );
+    assert!(cosmwasm_std::from_slice::<TestCamelExecuteMsg>(br#"{"withdraw":{}}"#).is_ok());
+    assert!(cosmwasm_std::from_slice::<TestCamelExecuteMsg>(br#"{"pauseAll":{}}"#).is_ok());
+}
+
+#[test]
+pub fn contract_execution_setting_builder() {
+    let contract = Addr::unchecked("contract1");
+    let register = TestExecuteMsg::Register {
+        name: "name".to_string(),
+    };
+
+    assert_eq!(
+        ContractExecutionSetting::builder(contract.clone()).build(),
+        ContractExecutionSetting {
+            contract_addr: contract.clone(),
+            limit: ContractExecutionAuthorizationLimit::default(),
+            filter: ContractExecutionAuthorizationFilter::AllowAllMessagesFilter,
+        }
+    );
+
+    // calls and funds together become a combined limit
+    assert_eq!(
+        ContractExecutionSetting::builder(contract.clone())
+            .max_calls(5)
+            .max_funds(coins(100, "ujuno"))
+            .max_funds(coins(50, "ujuno"))
+            .all_message_keys::<TestExecuteMsg>()
+            .build(),
+        ContractExecutionSetting {
+            contract_addr: contract.clone(),
+            limit: ContractExecutionAuthorizationLimit::CombinedLimit {
+                calls_remaining: 5,
+                amounts: coins(150, "ujuno"),
+            },
+            filter: ContractExecutionAuthorizationFilter::AcceptedMessageKeysFilter {
+                keys: vec![
+                    "register".to_string(),
+                    "claim_rewards".to_string(),
+                    "withdraw".to_string()
+                ],
+            },
+        }
+    );
+
+    assert_eq!(
+        ContractExecutionSetting::builder(contract.clone())
+            .any_amount_of(vec!["ujuno"])
+            .messages(std::slice::from_ref(&register))
+            .unwrap()
+            .build(),
+        ContractExecutionSetting {
+            contract_addr: contract.clone(),
+            limit: ContractExecutionAuthorizationLimit::single_fund_limit("ujuno"),
+            filter: ContractExecutionAuthorizationFilter::AcceptedMessagesFilter {
+                messages: vec![to_binary(&register).unwrap()],
+            },
+        }
+    );
+
+    let base = GrantBase {
+        granter: Addr::unchecked("granter1"),
+        grantee: Addr::unchecked("grantee1"),
+        expiration: GrantExpiration::Never,
+    };
+    assert_eq!(
+        GrantRequirement::try_contract_exec_messages_multidenoms_auth(
+            base.clone(),
+            contract.clone(),
+            vec![register.clone()],
+            vec!["ujuno", "uatom"],
+        )
+        .unwrap(),
+        GrantRequirement::contract_exec_auth(
+            base.clone(),
+            vec![ContractExecutionSetting {
+                contract_addr: contract.clone(),
+                limit: ContractExecutionAuthorizationLimit::multiple_fund_limits(vec![
+                    "uatom", "ujuno"
+                ]),
+                filter: ContractExecutionAuthorizationFilter::AcceptedMessagesFilter {
+                    messages: vec![to_binary(&register).unwrap()],
+                },
+            }]
+        )
+    );
+
+    // messages that can't be serialized are an error instead of a panic
+    let unserializable = std::collections::BTreeMap::from([((1u8, 2u8), 3u8)]);
+    assert!(GrantRequirement::try_contract_exec_messages_auth(
+        base,
+        contract,
+        vec![unserializable],
+        None
+    )
+    .is_err());
+}
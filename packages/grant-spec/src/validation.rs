@@ -0,0 +1,237 @@
+use std::collections::BTreeSet;
+
+use cosmwasm_std::{Addr, Coin, Timestamp};
+
+use crate::{
+    errors::GrantSpecError,
+    grants::{
+        AllowanceType, AuthorizationType, ContractExecutionAuthorizationFilter,
+        ContractExecutionAuthorizationLimit, ContractExecutionSetting, GrantExpiration,
+        GrantRequirement, StakeAuthorizationPolicy, StakeAuthorizationType,
+        StakeAuthorizationValidators,
+    },
+};
+
+/// Validates every requirement, returning the first error encountered
+pub fn validate_grant_reqs(
+    grants: &[GrantRequirement],
+    now: Timestamp,
+) -> Result<(), GrantSpecError> {
+    grants.iter().try_for_each(|grant| grant.validate(now))
+}
+
+impl GrantRequirement {
+    /// Checks that the requirement can actually be granted at `now`
+    pub fn validate(&self, now: Timestamp) -> Result<(), GrantSpecError> {
+        match self {
+            GrantRequirement::GrantSpec {
+                grant_type,
+                granter,
+                grantee,
+                expiration,
+            } => {
+                validate_grant_base(granter, grantee, expiration, now)?;
+                grant_type.validate()
+            }
+            GrantRequirement::FeeAllowanceSpec {
+                allowance,
+                granter,
+                grantee,
+                expiration,
+            } => {
+                validate_grant_base(granter, grantee, expiration, now)?;
+                allowance.validate()
+            }
+            // the contract validates its own messages
+            GrantRequirement::ContractExec { .. } => Ok(()),
+        }
+    }
+}
+
+fn validate_grant_base(
+    granter: &Addr,
+    grantee: &Addr,
+    expiration: &GrantExpiration,
+    now: Timestamp,
+) -> Result<(), GrantSpecError> {
+    if let GrantExpiration::AtTime(expiration) = expiration.resolve(now) {
+        if expiration.le(&now) {
+            return Err(GrantSpecError::ExpirationInPast { expiration, now });
+        }
+    }
+
+    if granter.eq(grantee) {
+        return Err(GrantSpecError::GranterIsGrantee(granter.to_string()));
+    }
+
+    Ok(())
+}
+
+/// A limit has to contain coins and none of them may be zero
+fn validate_coins(coins: &[Coin]) -> Result<(), GrantSpecError> {
+    if coins.is_empty() {
+        return Err(GrantSpecError::EmptySpendLimit);
+    }
+
+    match coins.iter().find(|coin| coin.amount.is_zero()) {
+        Some(coin) => Err(GrantSpecError::ZeroAmount(coin.denom.to_string())),
+        None => Ok(()),
+    }
+}
+
+impl AuthorizationType {
+    /// Checks that the authorization would be accepted by the module it is meant for
+    pub fn validate(&self) -> Result<(), GrantSpecError> {
+        match self {
+            AuthorizationType::GenericAuthorization { msg } => {
+                if msg.is_empty() {
+                    return Err(GrantSpecError::EmptyMsgTypeUrl);
+                }
+            }
+            AuthorizationType::SendAuthorization {
+                spend_limit,
+                allow_list,
+            } => {
+                if let Some(spend_limit) = spend_limit {
+                    validate_coins(spend_limit)?;
+                }
+                if allow_list.as_ref().is_some_and(|list| list.is_empty()) {
+                    return Err(GrantSpecError::EmptyAllowList);
+                }
+                // the chain requires a positive spend limit on every SendAuthorization
+                if spend_limit.is_none() && allow_list.is_some() {
+                    return Err(GrantSpecError::AllowListWithoutSpendLimit);
+                }
+            }
+            AuthorizationType::StakeAuthorization {
+                max_tokens,
+                authorization_type,
+                validators,
+            } => {
+                if authorization_type.eq(&StakeAuthorizationType::Unspecified) {
+                    return Err(GrantSpecError::UnspecifiedStakeAuthorizationType);
+                }
+                if let Some(max_tokens) = max_tokens {
+                    validate_coins(std::slice::from_ref(max_tokens))?;
+                }
+                if let Some(
+                    StakeAuthorizationPolicy::AllowList(StakeAuthorizationValidators { address })
+                    | StakeAuthorizationPolicy::DenyList(StakeAuthorizationValidators { address }),
+                ) = validators
+                {
+                    if address.is_empty() {
+                        return Err(GrantSpecError::EmptyStakeValidators);
+                    }
+                }
+            }
+            AuthorizationType::ContractExecutionAuthorization(settings) => {
+                if settings.is_empty() {
+                    return Err(GrantSpecError::EmptyContractExecutionSettings);
+                }
+
+                let mut contracts = BTreeSet::new();
+                for setting in settings {
+                    // the wasm module rejects authorizations that list a contract twice
+                    if !contracts.insert(&setting.contract_addr) {
+                        return Err(GrantSpecError::DuplicateContract(
+                            setting.contract_addr.to_string(),
+                        ));
+                    }
+                    setting.validate()?;
+                }
+            }
+            AuthorizationType::TransferAuthorization(settings) => {
+                if settings.is_empty() {
+                    return Err(GrantSpecError::EmptyTransferAllocations);
+                }
+
+                let mut channels = BTreeSet::new();
+                for setting in settings {
+                    if !channels.insert((&setting.source_port, &setting.source_channel)) {
+                        return Err(GrantSpecError::DuplicateTransferChannel {
+                            port: setting.source_port.to_string(),
+                            channel: setting.source_channel.to_string(),
+                        });
+                    }
+                    validate_coins(&setting.spend_limit)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl ContractExecutionSetting {
+    pub fn validate(&self) -> Result<(), GrantSpecError> {
+        let contract = self.contract_addr.to_string();
+
+        let (calls, funds) = self.limit.calls_and_funds();
+        if calls == Some(0) {
+            return Err(GrantSpecError::ZeroCallsLimit(contract));
+        }
+        // a max calls limit doesn't allow any funds so it's the only limit that may leave them out
+        if !matches!(
+            self.limit,
+            ContractExecutionAuthorizationLimit::MaxCallsLimit { .. }
+        ) && (funds.is_empty() || funds.iter().any(|coin| coin.amount.is_zero()))
+        {
+            return Err(GrantSpecError::ZeroFundsLimit(contract));
+        }
+
+        match &self.filter {
+            ContractExecutionAuthorizationFilter::AllowAllMessagesFilter => Ok(()),
+            ContractExecutionAuthorizationFilter::AcceptedMessageKeysFilter { keys } => {
+                if keys.is_empty() || keys.iter().any(|key| key.is_empty()) {
+                    Err(GrantSpecError::EmptyMessageFilter(contract))
+                } else {
+                    Ok(())
+                }
+            }
+            ContractExecutionAuthorizationFilter::AcceptedMessagesFilter { messages } => {
+                if messages.is_empty() {
+                    Err(GrantSpecError::EmptyMessageFilter(contract))
+                } else {
+                    Ok(())
+                }
+            }
+        }
+    }
+}
+
+impl AllowanceType {
+    /// Checks that the allowance would be accepted by the feegrant module
+    pub fn validate(&self) -> Result<(), GrantSpecError> {
+        match self {
+            AllowanceType::BasicAllowance { spend_limit } => {
+                if let Some(spend_limit) = spend_limit {
+                    validate_coins(spend_limit)?;
+                }
+            }
+            AllowanceType::PeriodicAllowance {
+                spend_limit,
+                period,
+                period_spend_limit,
+            } => {
+                if let Some(spend_limit) = spend_limit {
+                    validate_coins(spend_limit)?;
+                }
+                if *period == 0 {
+                    return Err(GrantSpecError::ZeroAllowancePeriod);
+                }
+                validate_coins(period_spend_limit)?;
+            }
+            AllowanceType::AllowedMsgAllowance {
+                allowance,
+                allowed_messages,
+            } => {
+                if allowed_messages.is_empty() {
+                    return Err(GrantSpecError::EmptyAllowedMessages);
+                }
+                allowance.validate()?;
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -51,7 +51,8 @@ pub fn main() -> anyhow::Result<()> {
             )
             .unwrap();
     } else {
-        withdraw_tax.migrate_if_needed(&withdraw_rewards_tax_grant::msg::InstantiateMsg {})?;
+        withdraw_tax
+            .migrate_if_needed(&withdraw_rewards_tax_grant::msg::MigrateMsg { limit: None })?;
     }
 
     println!("withdraw tax grant: {}", withdraw_tax.addr_str()?);